@@ -0,0 +1,379 @@
+//! Writes compiled modules to files.
+
+use std::env;
+use std::ffi::{CStr, CString};
+use std::fmt;
+use std::os::raw::{c_char, c_uint};
+use std::path::Path;
+use std::process::Command;
+use std::ptr;
+use std::sync::{Once, ONCE_INIT};
+
+use parse::Type;
+use parse::ast::{Item, Unit};
+use compile::{ModuleProvider, OptLevel};
+use diagnostics::{Diagnostic, ErrorCode, Severity};
+
+use iron_llvm::LLVMRef;
+use llvm_sys::prelude::*;
+use llvm_sys::core::{LLVMGetNamedFunction, LLVMSetValueName, LLVMAddFunction, LLVMFunctionType,
+                     LLVMAppendBasicBlock, LLVMCreateBuilder, LLVMPositionBuilderAtEnd,
+                     LLVMBuildCall, LLVMBuildRet, LLVMBuildGlobalStringPtr, LLVMBuildFPExt,
+                     LLVMBuildSelect, LLVMDisposeBuilder, LLVMDisposeMessage, LLVMSetTarget,
+                     LLVMConstInt, LLVMInt32Type, LLVMInt8Type, LLVMDoubleType, LLVMPointerType,
+                     LLVMPrintModuleToFile};
+use llvm_sys::analysis::{LLVMVerifyModule, LLVMVerifierFailureAction};
+use llvm_sys::bit_writer::LLVMWriteBitcodeToFile;
+use llvm_sys::target::{LLVM_InitializeAllTargetInfos, LLVM_InitializeAllTargets,
+                       LLVM_InitializeAllTargetMCs, LLVM_InitializeAllAsmPrinters,
+                       LLVMSetModuleDataLayout, LLVMDisposeTargetData};
+use llvm_sys::target_machine::{LLVMTargetRef, LLVMTargetMachineRef, LLVMGetDefaultTargetTriple,
+                               LLVMGetTargetFromTriple, LLVMCreateTargetMachine,
+                               LLVMCreateTargetDataLayout,
+                               LLVMDisposeTargetMachine, LLVMTargetMachineEmitToFile,
+                               LLVMCodeGenOptLevel, LLVMRelocMode, LLVMCodeModel,
+                               LLVMCodeGenFileType};
+
+/// Name the protosnirk `main` function is given in executables, so that
+/// the entry point can be called `main`
+pub const RENAMED_MAIN: &'static str = "__protosnirk_main";
+
+/// Reasons a compiled program couldn't be written out
+#[derive(Debug, Clone, PartialEq)]
+pub enum EmitError {
+    /// The module failed LLVM's verifier
+    InvalidModule { message: String },
+    /// LLVM can't compile for the target
+    UnknownTarget { triple: String, message: String },
+    /// A file couldn't be written
+    CouldNotWrite { path: String, message: String },
+    /// The linker couldn't be run, or failed
+    CouldNotLink { message: String }
+}
+
+impl fmt::Display for EmitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            EmitError::InvalidModule { ref message } =>
+                write!(f, "Compiled module is invalid: {}", message),
+            EmitError::UnknownTarget { ref triple, ref message } =>
+                write!(f, "Cannot compile for {}: {}", triple, message),
+            EmitError::CouldNotWrite { ref path, ref message } =>
+                write!(f, "Could not write {}: {}", path, message),
+            EmitError::CouldNotLink { ref message } =>
+                write!(f, "Could not link: {}", message)
+        }
+    }
+}
+
+impl EmitError {
+    /// Get a diagnostic for the error if it's a problem with the compiled
+    /// program, rather than with the system it's written out on.
+    pub fn get_diagnostic(&self) -> Option<Diagnostic> {
+        match *self {
+            EmitError::InvalidModule { ref message } =>
+                Some(Diagnostic::new(ErrorCode::InvalidModule, Severity::Error,
+                                     format!("Compiled module is invalid: {}", message), None, vec![])),
+            _ => None
+        }
+    }
+}
+
+static INITIALIZE_TARGETS: Once = ONCE_INIT;
+
+/// Writes modules to object files with an LLVM `TargetMachine`, and links
+/// them into executables.
+pub struct Emitter {
+    target_machine: LLVMTargetMachineRef,
+    triple: String
+}
+impl Emitter {
+    /// Create an emitter for the machine the compiler is running on.
+    pub fn native(level: OptLevel) -> Result<Emitter, EmitError> {
+        Emitter::new(&default_triple(), "", "", level)
+    }
+
+    /// Create an emitter for the target `triple`, such as
+    /// `aarch64-unknown-linux-gnu`.
+    ///
+    /// `cpu` and `features` are given to LLVM as they are, for example
+    /// `cortex-a53` and `+neon,+crc`. Empty strings use the target's defaults.
+    /// Machine code is generated with as much optimization as `level` asks for.
+    pub fn new(triple: &str, cpu: &str, features: &str, level: OptLevel)
+            -> Result<Emitter, EmitError> {
+        let unknown_target = |message: &str| EmitError::UnknownTarget {
+            triple: triple.to_string(), message: message.to_string()
+        };
+        INITIALIZE_TARGETS.call_once(|| unsafe {
+            LLVM_InitializeAllTargetInfos();
+            LLVM_InitializeAllTargets();
+            LLVM_InitializeAllTargetMCs();
+            LLVM_InitializeAllAsmPrinters();
+        });
+        let c_triple = try!(CString::new(triple)
+            .map_err(|_| unknown_target("the target triple contains nul")));
+        let c_cpu = try!(CString::new(cpu)
+            .map_err(|_| unknown_target("the CPU name contains nul")));
+        let c_features = try!(CString::new(features)
+            .map_err(|_| unknown_target("the target features contain nul")));
+        let mut target: LLVMTargetRef = ptr::null_mut();
+        let mut message = ptr::null_mut();
+        unsafe {
+            if LLVMGetTargetFromTriple(c_triple.as_ptr(), &mut target, &mut message) != 0 {
+                return Err(unknown_target(&take_message(message)))
+            }
+        }
+        let target_machine = unsafe {
+            LLVMCreateTargetMachine(target, c_triple.as_ptr(),
+                                    c_cpu.as_ptr(),
+                                    c_features.as_ptr(),
+                                    codegen_level(level),
+                                    // Position independent, as linkers default to PIE executables
+                                    LLVMRelocMode::LLVMRelocPIC,
+                                    LLVMCodeModel::LLVMCodeModelDefault)
+        };
+        if target_machine.is_null() {
+            return Err(unknown_target("LLVM could not create a target machine for it"))
+        }
+        Ok(Emitter { target_machine: target_machine, triple: triple.to_string() })
+    }
+
+    pub fn get_triple(&self) -> &str {
+        &self.triple
+    }
+
+    /// Set a module's target triple and data layout to the emitter's.
+    ///
+    /// This should be done before the module is compiled into, so the
+    /// compiler and optimizer see the target's type sizes and alignments.
+    pub fn prepare_module<M: ModuleProvider>(&self, provider: &mut M) {
+        let module = provider.get_module_mut().to_ref();
+        let c_triple = CString::new(self.triple.clone()).expect("Triple was checked for nul");
+        unsafe {
+            LLVMSetTarget(module, c_triple.as_ptr());
+            let data_layout = LLVMCreateTargetDataLayout(self.target_machine);
+            LLVMSetModuleDataLayout(module, data_layout);
+            LLVMDisposeTargetData(data_layout);
+        }
+    }
+
+    /// Compile a module to an object file at `path`.
+    ///
+    /// The module should have been given to `prepare_module` before it was
+    /// compiled into.
+    pub fn emit_object<M: ModuleProvider>(&self, provider: &M, path: &Path) -> Result<(), EmitError> {
+        try!(verify_module(provider));
+        let module = provider.get_module().to_ref();
+        let c_path = try!(path_to_c_string(path));
+        let c_triple = CString::new(self.triple.clone()).expect("Triple was checked for nul");
+        let mut message = ptr::null_mut();
+        let failed = unsafe {
+            LLVMSetTarget(module, c_triple.as_ptr());
+            // LLVM takes the file name as mutable, but doesn't change it
+            LLVMTargetMachineEmitToFile(self.target_machine, module, c_path.as_ptr() as *mut c_char,
+                                        LLVMCodeGenFileType::LLVMObjectFile, &mut message)
+        };
+        if failed != 0 {
+            return Err(could_not_write(path, unsafe { take_message(message) }))
+        }
+        Ok(())
+    }
+
+    /// Whether the emitter compiles to WebAssembly, which is linked with
+    /// `link_wasm` rather than `link`.
+    pub fn is_wasm(&self) -> bool {
+        self.triple.starts_with("wasm32")
+    }
+
+    /// Link an object file with the C runtime into an executable at `output`.
+    ///
+    /// The C compiler in `$CC`, or `cc`, is used to link.
+    pub fn link(&self, object: &Path, output: &Path) -> Result<(), EmitError> {
+        let linker = env::var("CC").unwrap_or_else(|_| "cc".to_string());
+        let mut command = Command::new(&linker);
+        command.arg(object).arg("-o").arg(output);
+        run_linker(&linker, command)
+    }
+
+    /// Link a WebAssembly object file into a module at `output`, which
+    /// exports the functions named in `exports`.
+    ///
+    /// The linker in `$WASM_LD`, or `wasm-ld`, is used to link. Functions
+    /// the module calls but doesn't define are imported from the host.
+    pub fn link_wasm(&self, object: &Path, output: &Path, exports: &[String]) -> Result<(), EmitError> {
+        let linker = env::var("WASM_LD").unwrap_or_else(|_| "wasm-ld".to_string());
+        let mut command = Command::new(&linker);
+        command.arg(object).arg("--no-entry").arg("--allow-undefined");
+        for export in exports {
+            command.arg(format!("--export={}", export));
+        }
+        command.arg("-o").arg(output);
+        run_linker(&linker, command)
+    }
+}
+impl Drop for Emitter {
+    fn drop(&mut self) {
+        unsafe { LLVMDisposeTargetMachine(self.target_machine) }
+    }
+}
+
+/// Get the target triple of the machine the compiler is running on.
+pub fn default_triple() -> String {
+    unsafe {
+        let default_triple = LLVMGetDefaultTargetTriple();
+        let triple = CStr::from_ptr(default_triple).to_string_lossy().into_owned();
+        LLVMDisposeMessage(default_triple);
+        triple
+    }
+}
+
+/// Get the names of a unit's functions, which are exported from
+/// WebAssembly modules under their protosnirk names.
+pub fn wasm_exports(unit: &Unit) -> Vec<String> {
+    unit.get_items().iter()
+        .filter_map(|item| match *item {
+            Item::FnDeclaration(ref decl) => Some(decl.get_name().get_name().to_string()),
+            _ => None
+        })
+        .collect()
+}
+
+/// Check that a module is valid LLVM IR.
+pub fn verify_module<M: ModuleProvider>(provider: &M) -> Result<(), EmitError> {
+    let mut message = ptr::null_mut();
+    let broken = unsafe {
+        LLVMVerifyModule(provider.get_module().to_ref(),
+                         LLVMVerifierFailureAction::LLVMReturnStatusAction, &mut message)
+    };
+    let message = unsafe { take_message(message) };
+    if broken != 0 {
+        return Err(EmitError::InvalidModule { message: message.trim().to_string() })
+    }
+    Ok(())
+}
+
+/// Write a module to `path` as textual LLVM IR, once it's been verified.
+pub fn write_ir<M: ModuleProvider>(provider: &M, path: &Path) -> Result<(), EmitError> {
+    try!(verify_module(provider));
+    let c_path = try!(path_to_c_string(path));
+    let mut message = ptr::null_mut();
+    let failed = unsafe {
+        LLVMPrintModuleToFile(provider.get_module().to_ref(), c_path.as_ptr(), &mut message)
+    };
+    if failed != 0 {
+        return Err(could_not_write(path, unsafe { take_message(message) }))
+    }
+    Ok(())
+}
+
+/// Write a module to `path` as LLVM bitcode, once it's been verified.
+pub fn write_bitcode<M: ModuleProvider>(provider: &M, path: &Path) -> Result<(), EmitError> {
+    try!(verify_module(provider));
+    let c_path = try!(path_to_c_string(path));
+    let failed = unsafe {
+        LLVMWriteBitcodeToFile(provider.get_module().to_ref(), c_path.as_ptr())
+    };
+    if failed != 0 {
+        return Err(could_not_write(path, "LLVM could not write the bitcode".to_string()))
+    }
+    Ok(())
+}
+
+/// Add a C `main` function to a module, which calls the protosnirk `main`
+/// and prints its result.
+///
+/// The protosnirk `main` is renamed to `RENAMED_MAIN`, and must not take
+/// any arguments.
+pub fn add_entry_point<M: ModuleProvider>(provider: &mut M, return_type: &Type) {
+    let module = provider.get_module_mut().to_ref();
+    let main_name = CString::new("main").expect("Literal doesn't contain nul");
+    let renamed = CString::new(RENAMED_MAIN).expect("Literal doesn't contain nul");
+    unsafe {
+        let protosnirk_main = LLVMGetNamedFunction(module, main_name.as_ptr());
+        assert!(!protosnirk_main.is_null(), "Module does not have a main function");
+        LLVMSetValueName(protosnirk_main, renamed.as_ptr());
+
+        let string_type = LLVMPointerType(LLVMInt8Type(), 0);
+        let mut printf_args = [string_type];
+        let printf_type = LLVMFunctionType(LLVMInt32Type(), printf_args.as_mut_ptr(), 1, 1);
+        let printf = LLVMAddFunction(module, b"printf\0".as_ptr() as *const c_char, printf_type);
+
+        let entry_type = LLVMFunctionType(LLVMInt32Type(), ptr::null_mut(), 0, 0);
+        let entry = LLVMAddFunction(module, main_name.as_ptr(), entry_type);
+        let block = LLVMAppendBasicBlock(entry, b"entry\0".as_ptr() as *const c_char);
+        let builder = LLVMCreateBuilder();
+        LLVMPositionBuilderAtEnd(builder, block);
+        let result_name = if *return_type == Type::Empty { "\0" } else { "result\0" };
+        let result = LLVMBuildCall(builder, protosnirk_main, ptr::null_mut(), 0,
+                                   result_name.as_ptr() as *const c_char);
+        let print_args = match *return_type {
+            Type::Empty => None,
+            Type::Float => Some(("%g\n\0", LLVMBuildFPExt(builder, result, LLVMDoubleType(),
+                                                          b"double\0".as_ptr() as *const c_char))),
+            Type::Int => Some(("%lld\n\0", result)),
+            Type::String => Some(("%s\n\0", result)),
+            Type::Bool => {
+                let true_text = LLVMBuildGlobalStringPtr(builder, b"true\0".as_ptr() as *const c_char,
+                                                         b"true\0".as_ptr() as *const c_char);
+                let false_text = LLVMBuildGlobalStringPtr(builder, b"false\0".as_ptr() as *const c_char,
+                                                          b"false\0".as_ptr() as *const c_char);
+                Some(("%s\n\0", LLVMBuildSelect(builder, result, true_text, false_text,
+                                                b"text\0".as_ptr() as *const c_char)))
+            },
+            ref other => panic!("main cannot return {}", other)
+        };
+        if let Some((format, value)) = print_args {
+            let format = LLVMBuildGlobalStringPtr(builder, format.as_ptr() as *const c_char,
+                                                  b"format\0".as_ptr() as *const c_char);
+            let mut args = [format, value];
+            LLVMBuildCall(builder, printf, args.as_mut_ptr(), args.len() as c_uint,
+                          b"\0".as_ptr() as *const c_char);
+        }
+        LLVMBuildRet(builder, LLVMConstInt(LLVMInt32Type(), 0, 0));
+        LLVMDisposeBuilder(builder);
+    }
+}
+
+/// Run a linker, reporting its output if it fails.
+fn run_linker(linker: &str, mut command: Command) -> Result<(), EmitError> {
+    match command.output() {
+        Ok(ref linked) if linked.status.success() => Ok(()),
+        Ok(linked) => Err(EmitError::CouldNotLink {
+            message: format!("{} failed: {}", linker, String::from_utf8_lossy(&linked.stderr).trim())
+        }),
+        Err(error) => Err(EmitError::CouldNotLink {
+            message: format!("could not run {}: {}", linker, error)
+        })
+    }
+}
+
+/// Get the code generation level used for an optimization level.
+///
+/// Code generation doesn't optimize for size, so `Os` uses the default.
+fn codegen_level(level: OptLevel) -> LLVMCodeGenOptLevel {
+    match level {
+        OptLevel::O0 => LLVMCodeGenOptLevel::LLVMCodeGenLevelNone,
+        OptLevel::O1 => LLVMCodeGenOptLevel::LLVMCodeGenLevelLess,
+        OptLevel::O2 | OptLevel::Os => LLVMCodeGenOptLevel::LLVMCodeGenLevelDefault,
+        OptLevel::O3 => LLVMCodeGenOptLevel::LLVMCodeGenLevelAggressive
+    }
+}
+
+fn could_not_write(path: &Path, message: String) -> EmitError {
+    EmitError::CouldNotWrite { path: path.display().to_string(), message: message }
+}
+
+fn path_to_c_string(path: &Path) -> Result<CString, EmitError> {
+    CString::new(path.to_string_lossy().into_owned())
+        .map_err(|_| could_not_write(path, "the path contains nul".to_string()))
+}
+
+/// Take the text of an LLVM message, disposing of it.
+unsafe fn take_message(message: *mut c_char) -> String {
+    if message.is_null() {
+        return String::new()
+    }
+    let text = CStr::from_ptr(message).to_string_lossy().into_owned();
+    LLVMDisposeMessage(message);
+    text
+}
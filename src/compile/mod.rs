@@ -1,10 +1,15 @@
 mod context;
+mod emitter;
 mod module_compiler;
 mod module_provider;
+mod optimization;
 
 #[cfg(test)]
 mod tests;
 
 pub use self::module_provider::{ModuleProvider, SimpleModuleProvider};
-pub use self::module_compiler::ModuleCompiler;
+pub use self::module_compiler::{ModuleCompiler, add_abi_attributes, add_call_abi_attributes, llvm_type};
 pub use self::context::LLVMContext;
+pub use self::optimization::{OptLevel, PassPipeline};
+pub use self::emitter::{Emitter, EmitError, RENAMED_MAIN};
+pub use self::emitter::{add_entry_point, default_triple, verify_module, wasm_exports, write_ir, write_bitcode};
@@ -1,57 +1,385 @@
 use std::collections::{HashMap, BTreeMap};
+use std::ffi::CString;
+use std::i64;
+use std::os::raw::{c_char, c_uint};
+use std::ptr;
 
-use parse::{ASTVisitor, ScopeIndex, SymbolTable};
+use parse::{ASTVisitor, ScopeIndex, SymbolTable, Symbol, Source, Type, FnType, Intrinsic};
 use parse::ast::*;
 use compile::{LLVMContext, ModuleProvider};
 
-use llvm_sys::{self, LLVMOpcode, LLVMRealPredicate};
+use llvm_sys::{self, LLVMOpcode, LLVMRealPredicate, LLVMLinkage, LLVMTypeKind};
 use llvm_sys::prelude::*;
 use llvm_sys::analysis::LLVMVerifierFailureAction;
-use llvm_sys::core::LLVMFloatType;
+use llvm_sys::core::{LLVMFloatType, LLVMTypeOf, LLVMConstStringInContext, LLVMAddGlobal,
+                     LLVMGetNamedFunction, LLVMAddFunction, LLVMSetInitializer,
+                     LLVMSetGlobalConstant, LLVMSetLinkage,
+                     LLVMSetUnnamedAddr, LLVMConstInt, LLVMInt32TypeInContext,
+                     LLVMConstInBoundsGEP, LLVMInt1Type, LLVMGetTypeKind,
+                     LLVMInt8Type, LLVMPointerType, LLVMVoidType, LLVMFunctionType,
+                     LLVMInt64Type, LLVMGetBasicBlockTerminator, LLVMGetGlobalContext,
+                     LLVMGetEnumAttributeKindForName, LLVMCreateEnumAttribute,
+                     LLVMAddAttributeAtIndex, LLVMAddCallSiteAttribute};
 use iron_llvm::{LLVMRef, LLVMRefCtor};
 use iron_llvm::core::{Function, Builder};
-use iron_llvm::core::basic_block::BasicBlock;
+use iron_llvm::core::basic_block::{BasicBlock, BasicBlockRef};
 use iron_llvm::core::instruction::{PHINode, PHINodeRef};
 use iron_llvm::core::value::{RealConstRef, FunctionRef, Value};
 use iron_llvm::core::types::{RealTypeRef, FunctionTypeRef, FunctionTypeCtor, RealTypeCtor};
 use iron_llvm::core::value::{RealConstCtor, ConstCtor, FunctionCtor};
 
+/// Get the LLVM type used for values of the given type.
+pub fn llvm_type(type_: &Type) -> LLVMTypeRef {
+    unsafe {
+        match *type_ {
+            Type::Float => LLVMFloatType(),
+            Type::Int => LLVMInt64Type(),
+            Type::Bool => LLVMInt1Type(),
+            Type::String => LLVMPointerType(LLVMInt8Type(), 0),
+            Type::Empty => LLVMVoidType(),
+            Type::Fn(_) => panic!("Functions are not values yet"),
+            Type::Variable(_) => panic!("Type {:?} was not inferred", type_)
+        }
+    }
+}
+
+/// Mark the `Bool` parameters and return value of `function` as `zeroext`.
+///
+/// Rust (and C) expect `bool`s to be zero extended when they're passed in
+/// registers, which LLVM only does for `i1`s with the attribute.
+pub fn add_abi_attributes(function: LLVMValueRef, fn_type: &FnType) {
+    for index in bool_indices(fn_type, 1) {
+        unsafe { LLVMAddAttributeAtIndex(function, index, zeroext_attribute()) }
+    }
+}
+
+/// Mark the `Bool` arguments and result of a call as `zeroext`, for calls
+/// to pointers whose declarations don't have the attributes.
+///
+/// `first_arg` is the index of the first of `fn_type`'s arguments, which
+/// is 1 unless other arguments are passed before them.
+pub fn add_call_abi_attributes(call: LLVMValueRef, fn_type: &FnType, first_arg: c_uint) {
+    for index in bool_indices(fn_type, first_arg) {
+        unsafe { LLVMAddCallSiteAttribute(call, index, zeroext_attribute()) }
+    }
+}
+
+/// Get the attribute indices of the `Bool` arguments of `fn_type`, which
+/// start at `first_arg`, and of its return value, which is 0.
+fn bool_indices(fn_type: &FnType, first_arg: c_uint) -> Vec<c_uint> {
+    let mut indices = fn_type.get_args().iter()
+        .enumerate()
+        .filter(|&(_, &(_, ref arg_type))| *arg_type == Type::Bool)
+        .map(|(ix, _)| ix as c_uint + first_arg)
+        .collect::<Vec<_>>();
+    if *fn_type.get_return() == Type::Bool {
+        indices.push(0);
+    }
+    indices
+}
+
+unsafe fn zeroext_attribute() -> LLVMAttributeRef {
+    let name = "zeroext";
+    let kind = LLVMGetEnumAttributeKindForName(name.as_ptr() as *const c_char, name.len());
+    LLVMCreateEnumAttribute(LLVMGetGlobalContext(), kind, 0)
+}
+
 pub struct ModuleCompiler<M: ModuleProvider> {
     module_provider: M,
-    optimizations: bool,
+    /// Whether a function failed verification, so the module can't be optimized
+    has_invalid_fn: bool,
     context: LLVMContext,
     ir_code: Vec<LLVMValueRef>,
     symbols: SymbolTable,
-    scope_manager: HashMap<ScopeIndex, LLVMValueRef>
+    scope_manager: HashMap<ScopeIndex, LLVMValueRef>,
+    /// Values of `for` loop variables, which are phis instead of allocas
+    loop_variables: HashMap<ScopeIndex, LLVMValueRef>,
+    /// `(continue, break)` targets of the loops being compiled
+    loop_blocks: Vec<(BasicBlockRef, BasicBlockRef)>
 }
 impl<M: ModuleProvider> ModuleCompiler<M> {
-    pub fn new(symbols: SymbolTable, provider: M, optimizations: bool) -> ModuleCompiler<M> {
+    pub fn new(symbols: SymbolTable, provider: M) -> ModuleCompiler<M> {
         ModuleCompiler {
             module_provider: provider,
+            has_invalid_fn: false,
             context: LLVMContext::new(),
             symbols: symbols,
             ir_code: Vec::with_capacity(1),
             scope_manager: HashMap::new(),
-            optimizations: optimizations
+            loop_variables: HashMap::new(),
+            loop_blocks: Vec::new()
         }
     }
     pub fn decompose(self) -> (M, LLVMContext, SymbolTable) {
         (self.module_provider, self.context, self.symbols)
     }
+
+    /// Compile the body of a loop which jumps back to `continue_block`
+    /// and exits to `break_block`.
+    ///
+    /// Loops don't have values, so anything left by the body is discarded.
+    fn build_loop_body(&mut self, block: &Block,
+                       continue_block: BasicBlockRef, break_block: BasicBlockRef) {
+        let value_count = self.ir_code.len();
+        self.loop_blocks.push((continue_block, break_block));
+        self.check_block(block);
+        self.loop_blocks.pop();
+        self.ir_code.truncate(value_count);
+        self.context.builder_mut().build_br(&continue_block);
+    }
+
+    /// Start a new block after a `break`, `continue` or `return` so that any
+    /// statements following it are not emitted after a terminator.
+    fn build_unreachable_block(&mut self) {
+        let mut function = self.context.builder().get_insert_block().get_parent();
+        let mut after_block =
+            function.append_basic_block_in_context(self.context.global_context_mut(), "after_jump");
+        self.context.builder_mut().position_at_end(&mut after_block);
+    }
+
+    /// Trap if an `Int` division or remainder is undefined: when the divisor
+    /// is zero, or is -1 and the dividend is the smallest `Int`, so the
+    /// result overflows.
+    fn build_division_check(&mut self, dividend: LLVMValueRef, divisor: LLVMValueRef) {
+        use llvm_sys::LLVMIntPredicate::LLVMIntEQ;
+        let module = self.module_provider.get_module().to_ref();
+        let (trap, zero, minus_one, min) = unsafe {
+            let trap_name = b"llvm.trap\0".as_ptr() as *const c_char;
+            let mut trap = LLVMGetNamedFunction(module, trap_name);
+            if trap.is_null() {
+                let trap_type = LLVMFunctionType(LLVMVoidType(), ptr::null_mut(), 0, 0);
+                trap = LLVMAddFunction(module, trap_name, trap_type);
+            }
+            (trap,
+             LLVMConstInt(LLVMInt64Type(), 0, 0),
+             LLVMConstInt(LLVMInt64Type(), -1i64 as u64, 1),
+             LLVMConstInt(LLVMInt64Type(), i64::MIN as u64, 1))
+        };
+        let mut function = self.context.builder().get_insert_block().get_parent();
+        let mut trap_block =
+            function.append_basic_block_in_context(self.context.global_context_mut(), "div_trap");
+        let mut div_block =
+            function.append_basic_block_in_context(self.context.global_context_mut(), "div");
+        let mut builder = self.context.builder_mut();
+        let by_zero = builder.build_icmp(LLVMIntEQ, divisor, zero, "by_zero");
+        let by_minus_one = builder.build_icmp(LLVMIntEQ, divisor, minus_one, "by_minus_one");
+        let of_min = builder.build_icmp(LLVMIntEQ, dividend, min, "of_min");
+        let overflows = builder.build_binop(LLVMOpcode::LLVMAnd, by_minus_one, of_min, "overflows");
+        let undefined = builder.build_binop(LLVMOpcode::LLVMOr, by_zero, overflows, "undefined");
+        builder.build_cond_br(undefined, &trap_block, &div_block);
+        builder.position_at_end(&mut trap_block);
+        builder.build_call(trap, &mut [], "");
+        builder.build_unreachable();
+        builder.position_at_end(&mut div_block);
+    }
+
+    /// Emit an `and` or `or` operation.
+    ///
+    /// The right side is put in its own block which is skipped if the
+    /// left side already determines the result.
+    fn build_short_circuit(&mut self, binary_op: &BinaryOperation) {
+        let is_and = binary_op.get_operator() == Operator::And;
+        trace!("Checking short circuit {:?} lvalue", binary_op.get_operator());
+        self.check_expression(&*binary_op.left);
+        let left_value = self.ir_code.pop()
+            .expect("Could not generate lvalue of logical op");
+        let left_end_block = self.context.builder_mut().get_insert_block();
+
+        let mut function = self.context.builder().get_insert_block().get_parent();
+        let (rhs_name, end_name) = if is_and { ("and_rhs", "and_end") } else { ("or_rhs", "or_end") };
+        let mut rhs_block =
+            function.append_basic_block_in_context(self.context.global_context_mut(), rhs_name);
+        let mut end_block =
+            function.append_basic_block_in_context(self.context.global_context_mut(), end_name);
+        // `false and x` and `true or x` skip to the end with the left value
+        if is_and {
+            self.context.builder_mut().build_cond_br(left_value, &rhs_block, &end_block);
+        }
+        else {
+            self.context.builder_mut().build_cond_br(left_value, &end_block, &rhs_block);
+        }
+
+        trace!("Checking short circuit {:?} rvalue", binary_op.get_operator());
+        self.context.builder_mut().position_at_end(&mut rhs_block);
+        self.check_expression(&*binary_op.right);
+        let right_value = self.ir_code.pop()
+            .expect("Could not generate rvalue of logical op");
+        self.context.builder_mut().build_br(&end_block);
+        let right_end_block = self.context.builder_mut().get_insert_block();
+
+        self.context.builder_mut().position_at_end(&mut end_block);
+        let mut phi = unsafe {
+            PHINodeRef::from_ref(self.context.builder_mut().build_phi(LLVMInt1Type(), "logic_phi"))
+        };
+        phi.add_incoming(vec![left_value].as_mut_slice(), vec![left_end_block].as_mut_slice());
+        phi.add_incoming(vec![right_value].as_mut_slice(), vec![right_end_block].as_mut_slice());
+        self.ir_code.push(phi.to_ref());
+    }
+
+    /// Emit a comparison of two values, producing an `i1`.
+    ///
+    /// Floats are compared with ordered `fcmp`, everything else (`Bool`s)
+    /// with `icmp`.
+    fn build_comparison(&mut self, operator: Operator,
+                        left: LLVMValueRef, right: LLVMValueRef) -> LLVMValueRef {
+        use llvm_sys::LLVMRealPredicate::*;
+        use llvm_sys::LLVMIntPredicate::*;
+        let left_kind = unsafe { LLVMGetTypeKind(LLVMTypeOf(left)) };
+        let mut builder = self.context.builder_mut();
+        if left_kind == LLVMTypeKind::LLVMFloatTypeKind {
+            let (predicate, name) = match operator {
+                Operator::Equality => (LLVMRealOEQ, "eqtmp"),
+                Operator::NonEquality => (LLVMRealONE, "neqtmp"),
+                Operator::LessThan => (LLVMRealOLT, "lttmp"),
+                Operator::LessThanEquals => (LLVMRealOLE, "letmp"),
+                Operator::GreaterThan => (LLVMRealOGT, "gttmp"),
+                Operator::GreaterThanEquals => (LLVMRealOGE, "getmp"),
+                other => panic!("Invalid comparison operator {:?}", other)
+            };
+            builder.build_fcmp(predicate, left, right, name)
+        }
+        else {
+            let (predicate, name) = match operator {
+                Operator::Equality => (LLVMIntEQ, "eqtmp"),
+                Operator::NonEquality => (LLVMIntNE, "neqtmp"),
+                Operator::LessThan => (LLVMIntSLT, "lttmp"),
+                Operator::LessThanEquals => (LLVMIntSLE, "letmp"),
+                Operator::GreaterThan => (LLVMIntSGT, "gttmp"),
+                Operator::GreaterThanEquals => (LLVMIntSGE, "getmp"),
+                other => panic!("Invalid comparison operator {:?}", other)
+            };
+            builder.build_icmp(predicate, left, right, name)
+        }
+    }
+
+    /// Emit a call to a built in function, which is done inline.
+    fn build_intrinsic_call(&mut self, intrinsic: Intrinsic, fn_call: &FnCall) {
+        match *fn_call.get_args() {
+            FnCallArgs::SingleExpr(ref inner) => self.check_expression(inner),
+            FnCallArgs::Arguments(ref args) => {
+                let arg = &args[0];
+                match arg.get_expr() {
+                    Some(expr) => self.check_expression(expr),
+                    None => self.check_var_ref(arg.get_name())
+                }
+            }
+        }
+        let value = self.ir_code.pop()
+            .expect("Could not generate value of intrinsic arg");
+        let result_type = llvm_type(intrinsic.get_type().get_return());
+        let mut builder = self.context.builder_mut();
+        let converted = match intrinsic {
+            Intrinsic::ToFloat => builder.build_si_to_fp(value, result_type, "to_float"),
+            Intrinsic::ToInt => builder.build_fp_to_si(value, result_type, "to_int")
+        };
+        self.ir_code.push(converted);
+    }
+
+    /// Emit `text` as a private constant global and get an `i8*` to its first byte.
+    ///
+    /// The string is null terminated so it can be handed to C-style host functions.
+    fn build_string_constant(&mut self, text: &str) -> LLVMValueRef {
+        let context = self.context.global_context().to_ref();
+        let module = self.module_provider.get_module().to_ref();
+        unsafe {
+            let const_string = LLVMConstStringInContext(context,
+                                                        text.as_ptr() as *const c_char,
+                                                        text.len() as c_uint,
+                                                        0); // null terminate
+            let global = LLVMAddGlobal(module, LLVMTypeOf(const_string),
+                                       b"str\0".as_ptr() as *const c_char);
+            LLVMSetInitializer(global, const_string);
+            LLVMSetGlobalConstant(global, 1);
+            LLVMSetLinkage(global, LLVMLinkage::LLVMPrivateLinkage);
+            LLVMSetUnnamedAddr(global, 1);
+            let zero = LLVMConstInt(LLVMInt32TypeInContext(context), 0, 0);
+            let mut indices = [zero, zero];
+            LLVMConstInBoundsGEP(global, indices.as_mut_ptr(), indices.len() as c_uint)
+        }
+    }
+
+    /// Declare a function or global which is defined outside of the module.
+    ///
+    /// Code compiled afterwards can use the symbol, which is expected to be
+    /// defined elsewhere, such as in a module which was already given to the
+    /// JIT or in storage the JIT resolves globals to.
+    pub fn declare_external(&mut self, symbol: &Symbol) {
+        let name = symbol.get_declaration().get_text();
+        match symbol.get_source() {
+            Source::DeclaredFn => {
+                self.declare_fn(name, symbol.get_index().clone());
+            },
+            Source::Global => {
+                let global_type = llvm_type(symbol.get_type());
+                let c_name = CString::new(name).expect("Identifiers don't contain nul");
+                let global = unsafe {
+                    LLVMAddGlobal(self.module_provider.get_module().to_ref(), global_type, c_name.as_ptr())
+                };
+                // Globals are read and written like the allocas of local variables
+                self.scope_manager.insert(symbol.get_index().clone(), global);
+            },
+            other => panic!("Cannot declare {} {} externally", other.get_name(), name)
+        }
+    }
+
+    /// Add the prototype of a function to the module.
+    fn declare_fn(&mut self, name: &str, index: ScopeIndex) -> (FunctionRef, Vec<LLVMTypeRef>) {
+        trace!("Declaring {}", name);
+        let declared_type = self.symbols[&index]
+            .get_type()
+            .clone()
+            .expect_fn();
+        let return_type = llvm_type(declared_type.get_return());
+        let mut arg_types = declared_type.get_args().iter()
+            .map(|&(_, ref arg_type)| llvm_type(arg_type))
+            .collect::<Vec<_>>();
+        let fn_type = unsafe {
+            FunctionTypeRef::from_ref(LLVMFunctionType(return_type,
+                                                       arg_types.as_mut_ptr(),
+                                                       arg_types.len() as c_uint,
+                                                       0))
+        };
+        let fn_ref = FunctionRef::new(&mut self.module_provider.get_module_mut(), name, &fn_type);
+        add_abi_attributes(fn_ref.to_ref(), &declared_type);
+
+        // Gotta insert the fn ref first so it can be called recursively
+        self.scope_manager.insert(index, fn_ref.to_ref());
+        trace!("Inserted {} into the scope manager: {:?}", name, self.scope_manager);
+        (fn_ref, arg_types)
+    }
 }
 impl<M:ModuleProvider> ASTVisitor for ModuleCompiler<M> {
     fn check_literal(&mut self, literal: &Literal) {
         trace!("Checking literal {}", literal.token);
-        let float_type = RealTypeRef::get_float();
-        debug_assert!(!float_type.to_ref().is_null());
-        let float_value = literal.get_value();
-        let literal_value = RealConstRef::get(&float_type, float_value);
-        debug_assert!(!literal_value.to_ref().is_null());
-        self.ir_code.push(literal_value.to_ref());
+        match literal.get_value() {
+            LiteralValue::Float(float_value) => {
+                let float_type = RealTypeRef::get_float();
+                debug_assert!(!float_type.to_ref().is_null());
+                let literal_value = RealConstRef::get(&float_type, float_value);
+                debug_assert!(!literal_value.to_ref().is_null());
+                self.ir_code.push(literal_value.to_ref());
+            },
+            LiteralValue::Int(int_value) => {
+                let int_value = unsafe { LLVMConstInt(LLVMInt64Type(), int_value as u64, 1) };
+                self.ir_code.push(int_value);
+            },
+            LiteralValue::String(text) => {
+                let string_ptr = self.build_string_constant(&text);
+                self.ir_code.push(string_ptr);
+            },
+            LiteralValue::Bool(value) => {
+                let bool_value = unsafe { LLVMConstInt(LLVMInt1Type(), value as u64, 0) };
+                self.ir_code.push(bool_value);
+            }
+        }
     }
 
     fn check_var_ref(&mut self, ident_ref: &Identifier) {
         trace!("Checking variable ref {}", ident_ref.get_name());
+        if let Some(value) = self.loop_variables.get(&ident_ref.get_index()) {
+            self.ir_code.push(*value);
+            return
+        }
         let var_alloca = self.scope_manager.get(&ident_ref.get_index())
             .expect("Attempted to check var ref but had no alloca");
         let load_name = format!("load_{}", ident_ref.get_name());
@@ -66,8 +394,9 @@ impl<M:ModuleProvider> ASTVisitor for ModuleCompiler<M> {
         let decl_value = self.ir_code.pop()
             .expect("Did not have rvalue of declaration");
         let mut builder = self.context.builder_mut();
-        let float_type = RealTypeRef::get_float();
-        let alloca = builder.build_alloca(float_type.to_ref(), decl.get_name());
+        // Allocate space for whichever type of value we got
+        let value_type = unsafe { LLVMTypeOf(decl_value) };
+        let alloca = builder.build_alloca(value_type, decl.get_name());
         self.scope_manager.insert(decl.ident.get_index(), alloca.to_ref());
         builder.build_store(decl_value, alloca);
     }
@@ -84,15 +413,23 @@ impl<M:ModuleProvider> ASTVisitor for ModuleCompiler<M> {
     }
 
     fn check_unary_op(&mut self, unary_op: &UnaryOperation) {
-        debug_assert!(unary_op.operator == Operator::Subtraction,
+        debug_assert!(unary_op.operator == Operator::Subtraction
+                      || unary_op.operator == Operator::Not,
             "Invalid unary operator {:?}", unary_op.operator);
         self.check_expression(&*unary_op.expression);
         let inner_value = self.ir_code.pop()
             .expect("Did not generate value inside unary op");
+        let is_float = unsafe {
+            LLVMGetTypeKind(LLVMTypeOf(inner_value)) == LLVMTypeKind::LLVMFloatTypeKind
+        };
         let mut builder = self.context.builder_mut();
         let value = match unary_op.operator {
+            Operator::Subtraction if is_float =>
+                builder.build_fneg(inner_value, "negate"),
             Operator::Subtraction =>
                 builder.build_neg(inner_value, "negate"),
+            Operator::Not =>
+                builder.build_not(inner_value, "not"),
             other => panic!("Invalid unary operator {:?}", other)
         };
         self.ir_code.push(value);
@@ -100,6 +437,9 @@ impl<M:ModuleProvider> ASTVisitor for ModuleCompiler<M> {
 
     fn check_binary_op(&mut self, binary_op: &BinaryOperation) {
         trace!("Checking binary operation {:?}", binary_op.get_operator());
+        if binary_op.get_operator().is_logical() {
+            return self.build_short_circuit(binary_op)
+        }
         trace!("Checking {:?} lvalue", binary_op.get_operator());
         self.check_expression(&*binary_op.left);
         let left_register = self.ir_code.pop()
@@ -108,53 +448,60 @@ impl<M:ModuleProvider> ASTVisitor for ModuleCompiler<M> {
         self.check_expression(&*binary_op.right);
         let right_register = self.ir_code.pop()
             .expect("Could not generate rvalue of binary op");
+        let operator = binary_op.get_operator();
+        if operator.is_comparison() {
+            let comparison = self.build_comparison(operator, left_register, right_register);
+            self.ir_code.push(comparison);
+            return
+        }
+        let is_float = unsafe {
+            LLVMGetTypeKind(LLVMTypeOf(left_register)) == LLVMTypeKind::LLVMFloatTypeKind
+        };
+        if !is_float && (operator == Operator::Division || operator == Operator::Modulus) {
+            self.build_division_check(left_register, right_register);
+        }
         let mut builder = self.context.builder_mut();
         trace!("Appending binary operation");
-        use llvm_sys::LLVMRealPredicate::*;
-        let bin_op_value = match binary_op.get_operator() {
-            Operator::Addition =>
-                builder.build_fadd(left_register, right_register, "add"),
-            Operator::Subtraction =>
-                builder.build_fsub(left_register, right_register, "sub"),
-            Operator::Multiplication =>
-                builder.build_fmul(left_register, right_register, "mul"),
-            Operator::Division =>
-                builder.build_binop(LLVMOpcode::LLVMFDiv, left_register, right_register, "div"),
-            Operator::Modulus =>
-                builder.build_frem(left_register, right_register, "rem"),
-            // TODO binary operations should be handled seperately
-            // when types are added
-            Operator::Equality => {
-                let eq = builder.build_fcmp(LLVMRealOEQ, left_register, right_register, "eqtmp");
-                builder.build_ui_to_fp(eq, unsafe { LLVMFloatType() }, "eqcast")
-            },
-            Operator::NonEquality => {
-                let neq = builder.build_fcmp(LLVMRealONE, left_register, right_register, "neqtmp");
-                builder.build_ui_to_fp(neq, unsafe { LLVMFloatType() }, "neqcast")
-            },
-            Operator::LessThan => {
-                let lt = builder.build_fcmp(LLVMRealOLT, left_register, right_register, "lttmp");
-                builder.build_ui_to_fp(lt, unsafe { LLVMFloatType() }, "ltcast")
-            },
-            Operator::LessThanEquals => {
-                let le = builder.build_fcmp(LLVMRealOLE, left_register, right_register, "letmp");
-                builder.build_ui_to_fp(le, unsafe { LLVMFloatType() }, "lecast")
-            },
-            Operator::GreaterThan => {
-                let gt = builder.build_fcmp(LLVMRealOGT, left_register, right_register, "gttmp");
-                builder.build_ui_to_fp(gt, unsafe { LLVMFloatType() }, "gtcast")
-            },
-            Operator::GreaterThanEquals => {
-                let ge = builder.build_fcmp(LLVMRealOGE, left_register, right_register, "getmp");
-                builder.build_ui_to_fp(ge, unsafe { LLVMFloatType() }, "gecast")
+        let bin_op_value = if is_float {
+            match operator {
+                Operator::Addition =>
+                    builder.build_fadd(left_register, right_register, "add"),
+                Operator::Subtraction =>
+                    builder.build_fsub(left_register, right_register, "sub"),
+                Operator::Multiplication =>
+                    builder.build_fmul(left_register, right_register, "mul"),
+                Operator::Division =>
+                    builder.build_binop(LLVMOpcode::LLVMFDiv, left_register, right_register, "div"),
+                Operator::Modulus =>
+                    builder.build_frem(left_register, right_register, "rem"),
+                Operator::Custom => panic!("Cannot handle custom operator"),
+                other => unreachable!("Comparison operator {:?} was not handled", other)
+            }
+        }
+        else {
+            match operator {
+                Operator::Addition =>
+                    builder.build_add(left_register, right_register, "add"),
+                Operator::Subtraction =>
+                    builder.build_sub(left_register, right_register, "sub"),
+                Operator::Multiplication =>
+                    builder.build_mul(left_register, right_register, "mul"),
+                Operator::Division =>
+                    builder.build_binop(LLVMOpcode::LLVMSDiv, left_register, right_register, "div"),
+                Operator::Modulus =>
+                    builder.build_binop(LLVMOpcode::LLVMSRem, left_register, right_register, "rem"),
+                Operator::Custom => panic!("Cannot handle custom operator"),
+                other => unreachable!("Comparison operator {:?} was not handled", other)
             }
-            Operator::Custom => panic!("Cannot handle custom operator")
         };
         self.ir_code.push(bin_op_value);
     }
 
     fn check_fn_call(&mut self, fn_call: &FnCall) {
         trace!("Checking call to {}", fn_call.get_text());
+        if let Some(intrinsic) = Intrinsic::from_name(fn_call.get_text()) {
+            return self.build_intrinsic_call(intrinsic, fn_call)
+        }
         let mut arg_map = BTreeMap::new();
         let fn_type = self.symbols[&fn_call.get_name().get_index()]
                         .get_type()
@@ -204,7 +551,13 @@ impl<M:ModuleProvider> ASTVisitor for ModuleCompiler<M> {
         }
         trace!("Finished pushing args");
         debug_assert_eq!(arg_values.len(), fn_type.get_args().len());
-        let name = format!("call_{}", fn_call.get_text());
+        // Calls which don't produce a value can't be named
+        let name = if *fn_type.get_return() == Type::Empty {
+            String::new()
+        }
+        else {
+            format!("call_{}", fn_call.get_text())
+        };
         trace!("Scope manager: {:?}", self.scope_manager);
         trace!("Fn call index: {:?}", fn_call.get_name().get_index());
         let fn_ref = self.scope_manager[&fn_call.get_name().get_index()];
@@ -228,21 +581,13 @@ impl<M:ModuleProvider> ASTVisitor for ModuleCompiler<M> {
             // Hopefully doesn't happen, protosnirk doesn't support void types
             builder.build_ret_void();
         }
+        self.build_unreachable_block();
     }
 
     fn check_fn_declaration(&mut self, fn_declaration: &FnDeclaration) {
         trace!("Checking declaration of {}", fn_declaration.get_name().get_name());
-
-        let float_type = RealTypeRef::get_float();
-        let mut arg_types = vec![float_type.to_ref(); fn_declaration.get_args().len()];
-        let fn_type = FunctionTypeRef::get(&float_type, arg_types.as_mut_slice(), false);
-        let mut fn_ref = FunctionRef::new(&mut self.module_provider.get_module_mut(),
-            fn_declaration.get_name().get_name(), &fn_type);
-
-        // Gotta insert the fn ref first so it can be called recursively
-        self.scope_manager.insert(fn_declaration.get_name().get_index(), fn_ref.to_ref());
-        trace!("Inserted {} into the scope manager: {:?}",
-            fn_declaration.get_name().get_name(), self.scope_manager);
+        let (mut fn_ref, arg_types) = self.declare_fn(fn_declaration.get_name().get_name(),
+                                                      fn_declaration.get_name().get_index());
 
         // Gonna be fancy and have a separate basic block for parameters
         let mut entry_block = fn_ref.append_basic_block_in_context(self.context.global_context_mut(), "entry");
@@ -252,10 +597,11 @@ impl<M:ModuleProvider> ASTVisitor for ModuleCompiler<M> {
 
         // Rename args to %argname, create+remember allocas and store the function values there.
         // This allows LLVM to mutate function params even if we don't allow it right now.
-        for (ir_param, ast_param) in fn_ref.params_iter().zip(fn_declaration.get_args()) {
+        let params = fn_ref.params_iter().zip(fn_declaration.get_args()).zip(arg_types);
+        for ((ir_param, &(ref ast_param, _)), param_type) in params {
             trace!("Adding fn param {} (ix {:?})", ast_param.get_name(), ast_param.get_index());
             ir_param.set_name(ast_param.get_name());
-            let alloca = self.context.builder_mut().build_alloca(float_type.to_ref(), ast_param.get_name());
+            let alloca = self.context.builder_mut().build_alloca(param_type, ast_param.get_name());
             self.scope_manager.insert(ast_param.get_index(), alloca.to_ref());
             self.context.builder_mut().build_store(ir_param.to_ref(), alloca);
         }
@@ -265,16 +611,41 @@ impl<M:ModuleProvider> ASTVisitor for ModuleCompiler<M> {
         // Compile the function
         self.check_block(&fn_declaration.get_block());
 
-        if let Some(remaining_expr) = self.ir_code.pop() {
+        let returns_empty = *self.symbols[&fn_declaration.get_name().get_index()]
+            .get_type()
+            .clone()
+            .expect_fn()
+            .get_return() == Type::Empty;
+        if returns_empty {
+            let end_block = self.context.builder().get_insert_block();
+            if unsafe { LLVMGetBasicBlockTerminator(end_block.to_ref()) }.is_null() {
+                trace!("Function has no value, appending ret void");
+                self.context.builder_mut().build_ret_void();
+            }
+        }
+        else if let Some(remaining_expr) = self.ir_code.pop() {
             trace!("Found final expression, appending a return");
             self.context.builder_mut().build_ret(&remaining_expr);
-            //self.module_provider.get_module().dump();
+        }
+        else {
+            // The verifier checks that functions with values which don't
+            // end in one always return, so their last block can't be reached
+            let end_block = self.context.builder().get_insert_block();
+            if unsafe { LLVMGetBasicBlockTerminator(end_block.to_ref()) }.is_null() {
+                trace!("Function ends after a return, appending unreachable");
+                self.context.builder_mut().build_unreachable();
+            }
         }
 
-        assert!(!fn_ref.verify(LLVMVerifierFailureAction::LLVMPrintMessageAction));
-        if self.optimizations {
+        // Invalid functions are reported when the module is verified, and
+        // aren't optimized as the passes expect valid IR
+        let invalid = fn_ref.verify(LLVMVerifierFailureAction::LLVMReturnStatusAction);
+        if invalid {
+            self.has_invalid_fn = true;
+        }
+        else {
             trace!("Running optimizations on a function");
-            self.module_provider.get_pass_manager().run(&mut fn_ref);
+            self.module_provider.get_passes().run_on_function(fn_ref.to_ref());
         }
     }
 
@@ -284,22 +655,17 @@ impl<M:ModuleProvider> ASTVisitor for ModuleCompiler<M> {
         for fn_declaration in unit.get_items() {
             self.check_item(fn_declaration);
         }
-
-        // The final ir_code value should be a reference to the function
-        self.module_provider.get_module()
-            .verify(LLVMVerifierFailureAction::LLVMPrintMessageAction)
-            .unwrap();
+        if !self.has_invalid_fn {
+            trace!("Running optimizations on the module");
+            self.module_provider.optimize_module();
+        }
     }
 
     fn check_if_expr(&mut self, if_expr: &IfExpression) {
         // Build conditional expr
         self.check_expression(if_expr.get_condition());
-        let condition_expr = self.ir_code.pop()
+        let condition = self.ir_code.pop()
             .expect("Did not get value from if conditional");
-        let const_zero = RealConstRef::get(&unsafe {RealTypeRef::from_ref(LLVMFloatType())}, 0.0);
-        // hack: compare it to 0, due to lack of booleans right now
-        let condition = self.context.builder_mut()
-            .build_fcmp(LLVMRealPredicate::LLVMRealOEQ, condition_expr, const_zero.to_ref(), "ife_cond");
         // Create basic blocks in the function
         let mut function = self.context.builder().get_insert_block().get_parent();
         let mut then_block =
@@ -308,7 +674,7 @@ impl<M:ModuleProvider> ASTVisitor for ModuleCompiler<M> {
             function.append_basic_block_in_context(self.context.global_context_mut(), "ife_else");
         let mut end_block =
             function.append_basic_block_in_context(self.context.global_context_mut(), "ife_end");
-        // Branch off of the `== 0` comparison
+        // Branch off of the `Bool` condition
         self.context.builder_mut().build_cond_br(condition, &then_block, &else_block);
 
         // Emit the then code
@@ -329,7 +695,7 @@ impl<M:ModuleProvider> ASTVisitor for ModuleCompiler<M> {
 
         self.context.builder_mut().position_at_end(&mut end_block);
         let mut phi = unsafe {
-            PHINodeRef::from_ref(self.context.builder_mut().build_phi(LLVMFloatType(), "ifephi"))
+            PHINodeRef::from_ref(self.context.builder_mut().build_phi(LLVMTypeOf(then_value), "ifephi"))
         };
 
         phi.add_incoming(vec![then_value].as_mut_slice(), vec![then_end_block].as_mut_slice());
@@ -369,8 +735,6 @@ impl<M:ModuleProvider> ASTVisitor for ModuleCompiler<M> {
                 function.append_basic_block_in_context(self.context.global_context_mut(), "else_block"));
         }
 
-        let const_zero = RealConstRef::get(&unsafe { RealTypeRef::from_ref(LLVMFloatType())}, 0.0);
-
         trace!("Creating end block");
         condition_blocks.push(function.append_basic_block_in_context(self.context.global_context_mut(),
                                                                      "if_end"));
@@ -381,12 +745,9 @@ impl<M:ModuleProvider> ASTVisitor for ModuleCompiler<M> {
             self.check_expression(conditional.get_condition());
             let cond_value = self.ir_code.pop()
                 .expect("Did not get IR value from if block condition");
-            let cond_cmp_name = format!("if_{}_cmp", ix);
-            let cond_cmp = self.context.builder_mut()
-                .build_fcmp(LLVMRealPredicate::LLVMRealOEQ, cond_value, const_zero.to_ref(), &cond_cmp_name);
 
-            trace!("Building a break to next blocks {} -> {}, {}", cond_cmp_name, ix, ix + 1);
-            self.context.builder_mut().build_cond_br(cond_cmp,
+            trace!("Building a break to next blocks {}, {}", ix, ix + 1);
+            self.context.builder_mut().build_cond_br(cond_value,
                                                      &condition_blocks[ix],
                                                      &condition_blocks[ix + 1]);
 
@@ -441,17 +802,138 @@ impl<M:ModuleProvider> ASTVisitor for ModuleCompiler<M> {
                 .map(|c| c[0])
                 .collect::<Vec<_>>();
             incoming_conditions.push(*condition_blocks.last().expect("No condition blocks"));
-            self.module_provider.get_module().dump();
             trace!("Generating phi node with {} values and {} edges",
                 incoming_values.len(), incoming_conditions.len());
             let mut phi = unsafe {
-                PHINodeRef::from_ref(self.context.builder_mut().build_phi(LLVMFloatType(), "if_phi"))
+                PHINodeRef::from_ref(self.context.builder_mut().build_phi(LLVMTypeOf(incoming_values[0]), "if_phi"))
             };
             phi.add_incoming(incoming_values.as_mut_slice(), incoming_conditions.as_mut_slice());
             self.ir_code.push(phi.to_ref());
         }
     }
 
+    fn check_while_loop(&mut self, while_: &WhileLoop) {
+        trace!("Checking while loop");
+        let mut function = self.context.builder().get_insert_block().get_parent();
+        let mut header_block =
+            function.append_basic_block_in_context(self.context.global_context_mut(), "while_cond");
+        let mut body_block =
+            function.append_basic_block_in_context(self.context.global_context_mut(), "while_body");
+        let mut exit_block =
+            function.append_basic_block_in_context(self.context.global_context_mut(), "while_end");
+        self.context.builder_mut().build_br(&header_block);
+
+        // Check the condition at the start of each iteration
+        self.context.builder_mut().position_at_end(&mut header_block);
+        self.check_expression(while_.get_condition());
+        let condition = self.ir_code.pop()
+            .expect("Did not get value from while condition");
+        self.context.builder_mut().build_cond_br(condition, &body_block, &exit_block);
+
+        self.context.builder_mut().position_at_end(&mut body_block);
+        self.build_loop_body(while_.get_block(), header_block, exit_block);
+
+        self.context.builder_mut().position_at_end(&mut exit_block);
+    }
+
+    fn check_for_loop(&mut self, for_: &ForLoop) {
+        trace!("Checking for loop over {}", for_.get_ident().get_name());
+        self.check_expression(for_.get_start());
+        let start = self.ir_code.pop()
+            .expect("Did not get value from for loop start");
+        self.check_expression(for_.get_end());
+        let end = self.ir_code.pop()
+            .expect("Did not get value from for loop end");
+        let is_float = unsafe {
+            LLVMGetTypeKind(LLVMTypeOf(start)) == LLVMTypeKind::LLVMFloatTypeKind
+        };
+        let step = if let Some(step_expr) = for_.get_step() {
+            self.check_expression(step_expr);
+            self.ir_code.pop()
+                .expect("Did not get value from for loop step")
+        }
+        else if is_float {
+            RealConstRef::get(&RealTypeRef::get_float(), 1.0).to_ref()
+        }
+        else {
+            unsafe { LLVMConstInt(LLVMTypeOf(start), 1, 0) }
+        };
+        let entry_block = self.context.builder_mut().get_insert_block();
+
+        let mut function = self.context.builder().get_insert_block().get_parent();
+        let mut header_block =
+            function.append_basic_block_in_context(self.context.global_context_mut(), "for_cond");
+        let mut body_block =
+            function.append_basic_block_in_context(self.context.global_context_mut(), "for_body");
+        let mut step_block =
+            function.append_basic_block_in_context(self.context.global_context_mut(), "for_step");
+        let mut exit_block =
+            function.append_basic_block_in_context(self.context.global_context_mut(), "for_end");
+        self.context.builder_mut().build_br(&header_block);
+
+        // The loop variable starts at `start` and is stepped in `for_step`
+        self.context.builder_mut().position_at_end(&mut header_block);
+        let mut phi = unsafe {
+            PHINodeRef::from_ref(self.context.builder_mut()
+                .build_phi(LLVMTypeOf(start), for_.get_ident().get_name()))
+        };
+        phi.add_incoming(vec![start].as_mut_slice(), vec![entry_block].as_mut_slice());
+        let operator = if for_.is_inclusive() {
+            Operator::LessThanEquals
+        }
+        else {
+            Operator::LessThan
+        };
+        let condition = self.build_comparison(operator, phi.to_ref(), end);
+        self.context.builder_mut().build_cond_br(condition, &body_block, &exit_block);
+
+        self.loop_variables.insert(for_.get_ident().get_index(), phi.to_ref());
+        self.context.builder_mut().position_at_end(&mut body_block);
+        self.build_loop_body(for_.get_block(), step_block, exit_block);
+        self.loop_variables.remove(&for_.get_ident().get_index());
+
+        self.context.builder_mut().position_at_end(&mut step_block);
+        let next = if is_float {
+            self.context.builder_mut().build_fadd(phi.to_ref(), step, "for_next")
+        }
+        else {
+            self.context.builder_mut().build_add(phi.to_ref(), step, "for_next")
+        };
+        self.context.builder_mut().build_br(&header_block);
+        phi.add_incoming(vec![next].as_mut_slice(), vec![step_block].as_mut_slice());
+
+        self.context.builder_mut().position_at_end(&mut exit_block);
+    }
+
+    fn check_loop(&mut self, loop_: &Loop) {
+        trace!("Checking loop");
+        let mut function = self.context.builder().get_insert_block().get_parent();
+        let mut body_block =
+            function.append_basic_block_in_context(self.context.global_context_mut(), "loop_body");
+        let mut exit_block =
+            function.append_basic_block_in_context(self.context.global_context_mut(), "loop_end");
+        self.context.builder_mut().build_br(&body_block);
+
+        self.context.builder_mut().position_at_end(&mut body_block);
+        self.build_loop_body(loop_.get_block(), body_block, exit_block);
+
+        self.context.builder_mut().position_at_end(&mut exit_block);
+    }
+
+    fn check_break(&mut self, _break: &Break) {
+        let (_, break_block) = *self.loop_blocks.last()
+            .expect("Verifier allowed break outside of a loop");
+        self.context.builder_mut().build_br(&break_block);
+        self.build_unreachable_block();
+    }
+
+    fn check_continue(&mut self, _continue: &Continue) {
+        let (continue_block, _) = *self.loop_blocks.last()
+            .expect("Verifier allowed continue outside of a loop");
+        self.context.builder_mut().build_br(&continue_block);
+        self.build_unreachable_block();
+    }
+
     fn check_block(&mut self, block: &Block) {
         trace!("Checking block");
         for stmt in block.statements.iter() {
@@ -1,30 +1,30 @@
-use iron_llvm::core::{Module, FunctionPassManager};
+use iron_llvm::core::Module;
+
+use compile::{OptLevel, PassPipeline};
 
 pub trait ModuleProvider {
     fn get_module(&self) -> &Module;
     fn get_module_mut(&mut self) -> &mut Module;
-    fn get_pass_manager(&mut self) -> &mut FunctionPassManager;
+    fn get_passes(&mut self) -> &mut PassPipeline;
+
+    /// Run the module passes on the current module, once all of its
+    /// functions have been compiled.
+    fn optimize_module(&mut self);
 }
 
 pub struct SimpleModuleProvider {
+    // Fields are dropped in order, and the passes must be disposed of
+    // before the module they were created for
+    passes: PassPipeline,
     module: Module,
-    fn_pass_manager: FunctionPassManager,
 }
 impl SimpleModuleProvider {
-    pub fn new(name: &str, optimizations: bool) -> SimpleModuleProvider {
+    pub fn new(name: &str, level: OptLevel) -> SimpleModuleProvider {
         let module = Module::new(name);
-        let mut pass_manager = FunctionPassManager::new(&module);
-        if optimizations {
-            pass_manager.add_basic_alias_analysis_pass();
-            pass_manager.add_instruction_combining_pass();
-            pass_manager.add_reassociate_pass();
-            pass_manager.add_GVN_pass();
-            pass_manager.add_CFG_simplification_pass();
-            pass_manager.initialize();
-        }
+        let passes = PassPipeline::new(&module, level);
         SimpleModuleProvider {
             module: module,
-            fn_pass_manager: pass_manager
+            passes: passes
         }
     }
 }
@@ -36,7 +36,10 @@ impl ModuleProvider for SimpleModuleProvider {
     fn get_module_mut(&mut self) -> &mut Module {
         &mut self.module
     }
-    fn get_pass_manager(&mut self) -> &mut FunctionPassManager {
-        &mut self.fn_pass_manager
+    fn get_passes(&mut self) -> &mut PassPipeline {
+        &mut self.passes
+    }
+    fn optimize_module(&mut self) {
+        self.passes.run_on_module(&mut self.module);
     }
 }
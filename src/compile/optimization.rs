@@ -0,0 +1,179 @@
+//! Optimization levels, and the LLVM passes run at each of them.
+//!
+//! Functions are optimized as soon as they're compiled, and modules once all
+//! of their functions have been, so that functions can be inlined into each
+//! other and cleaned up afterwards.
+
+use std::fmt;
+
+use iron_llvm::LLVMRef;
+use iron_llvm::core::Module;
+use llvm_sys::prelude::*;
+use llvm_sys::core::{LLVMCreateFunctionPassManagerForModule, LLVMCreatePassManager,
+                     LLVMInitializeFunctionPassManager, LLVMFinalizeFunctionPassManager,
+                     LLVMRunFunctionPassManager, LLVMRunPassManager, LLVMDisposePassManager};
+use llvm_sys::transforms::scalar::{LLVMAddBasicAliasAnalysisPass, LLVMAddPromoteMemoryToRegisterPass,
+                                   LLVMAddScalarReplAggregatesPass, LLVMAddInstructionCombiningPass,
+                                   LLVMAddReassociatePass, LLVMAddGVNPass,
+                                   LLVMAddCFGSimplificationPass, LLVMAddTailCallEliminationPass,
+                                   LLVMAddLoopRotatePass, LLVMAddLICMPass,
+                                   LLVMAddIndVarSimplifyPass, LLVMAddLoopDeletionPass,
+                                   LLVMAddLoopUnrollPass};
+use llvm_sys::transforms::ipo::{LLVMAddFunctionAttrsPass, LLVMAddFunctionInliningPass};
+
+/// How much compiled code is optimized, as with the `-O` flags of C compilers
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum OptLevel {
+    /// No optimization
+    O0,
+    /// Promote variables to registers, simplify expressions and turn tail
+    /// calls into loops
+    O1,
+    /// `O1`, with loop optimizations and inlining
+    O2,
+    /// `O2`, with loops unrolled
+    O3,
+    /// `O2` without the passes which make code larger: loops aren't
+    /// unrolled and functions aren't inlined
+    Os
+}
+impl OptLevel {
+    /// Get the level with the given name: `0`, `1`, `2`, `3` or `s`.
+    pub fn from_name(name: &str) -> Option<OptLevel> {
+        match name {
+            "0" => Some(OptLevel::O0),
+            "1" => Some(OptLevel::O1),
+            "2" => Some(OptLevel::O2),
+            "3" => Some(OptLevel::O3),
+            "s" => Some(OptLevel::Os),
+            _ => None
+        }
+    }
+
+    fn optimizes_loops(self) -> bool {
+        match self {
+            OptLevel::O0 | OptLevel::O1 => false,
+            OptLevel::O2 | OptLevel::O3 | OptLevel::Os => true
+        }
+    }
+
+    fn inlines(self) -> bool {
+        self == OptLevel::O2 || self == OptLevel::O3
+    }
+}
+impl Default for OptLevel {
+    fn default() -> OptLevel {
+        OptLevel::O0
+    }
+}
+impl fmt::Display for OptLevel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            OptLevel::O0 => "0",
+            OptLevel::O1 => "1",
+            OptLevel::O2 => "2",
+            OptLevel::O3 => "3",
+            OptLevel::Os => "s"
+        };
+        write!(f, "-O{}", name)
+    }
+}
+
+/// The function and module passes for an optimization level.
+pub struct PassPipeline {
+    level: OptLevel,
+    function_passes: LLVMPassManagerRef,
+    module_passes: LLVMPassManagerRef
+}
+impl PassPipeline {
+    /// Create the passes for optimizing `module` at `level`.
+    pub fn new(module: &Module, level: OptLevel) -> PassPipeline {
+        unsafe {
+            let function_passes = LLVMCreateFunctionPassManagerForModule(module.to_ref());
+            add_function_passes(function_passes, level);
+            LLVMInitializeFunctionPassManager(function_passes);
+            let module_passes = LLVMCreatePassManager();
+            if level.inlines() {
+                LLVMAddFunctionAttrsPass(module_passes);
+                LLVMAddFunctionInliningPass(module_passes);
+                // Inlined code is simplified with the rest of the caller
+                add_function_passes(module_passes, level);
+            }
+            PassPipeline {
+                level: level,
+                function_passes: function_passes,
+                module_passes: module_passes
+            }
+        }
+    }
+
+    pub fn get_level(&self) -> OptLevel {
+        self.level
+    }
+
+    /// Optimize a function which has just been compiled.
+    ///
+    /// Returns whether the function was changed.
+    pub fn run_on_function(&mut self, function: LLVMValueRef) -> bool {
+        unsafe { LLVMRunFunctionPassManager(self.function_passes, function) != 0 }
+    }
+
+    /// Optimize a module once all of its functions have been compiled.
+    ///
+    /// Returns whether the module was changed.
+    pub fn run_on_module(&mut self, module: &mut Module) -> bool {
+        unsafe { LLVMRunPassManager(self.module_passes, module.to_ref()) != 0 }
+    }
+}
+impl Drop for PassPipeline {
+    fn drop(&mut self) {
+        unsafe {
+            LLVMFinalizeFunctionPassManager(self.function_passes);
+            LLVMDisposePassManager(self.function_passes);
+            LLVMDisposePassManager(self.module_passes);
+        }
+    }
+}
+
+/// Add the passes which optimize a function on its own.
+unsafe fn add_function_passes(passes: LLVMPassManagerRef, level: OptLevel) {
+    if level == OptLevel::O0 {
+        return
+    }
+    LLVMAddBasicAliasAnalysisPass(passes);
+    // Variables are compiled to allocas, which these turn into registers
+    LLVMAddPromoteMemoryToRegisterPass(passes);
+    LLVMAddScalarReplAggregatesPass(passes);
+    LLVMAddInstructionCombiningPass(passes);
+    LLVMAddReassociatePass(passes);
+    LLVMAddGVNPass(passes);
+    LLVMAddCFGSimplificationPass(passes);
+    // Recursive helper functions become loops
+    LLVMAddTailCallEliminationPass(passes);
+    if level.optimizes_loops() {
+        LLVMAddLoopRotatePass(passes);
+        LLVMAddLICMPass(passes);
+        LLVMAddIndVarSimplifyPass(passes);
+        LLVMAddLoopDeletionPass(passes);
+        if level == OptLevel::O3 {
+            LLVMAddLoopUnrollPass(passes);
+        }
+        LLVMAddInstructionCombiningPass(passes);
+        LLVMAddCFGSimplificationPass(passes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OptLevel;
+
+    #[test]
+    fn it_names_optimization_levels() {
+        for name in &["0", "1", "2", "3", "s"] {
+            let level = OptLevel::from_name(name).expect("Level was not found");
+            assert_eq!(level.to_string(), format!("-O{}", name));
+        }
+        assert_eq!(OptLevel::from_name("4"), None);
+        assert_eq!(OptLevel::from_name("fast"), None);
+    }
+}
@@ -1,19 +1,40 @@
+use std::env;
+use std::fs::File;
+use std::io::Read;
+use std::process::Command;
+
+use wasmi::{ImportsBuilder, ModuleInstance, NopExternals, RuntimeValue};
+
 use parse::tests::parser;
-use parse::{ErrorCollector, SymbolTable, ASTVisitor};
-use compile::{ModuleProvider, ModuleCompiler, SimpleModuleProvider};
+use parse::{ErrorCollector, SymbolTable, ASTVisitor, Definitions, Type};
+use compile::{ModuleProvider, ModuleCompiler, SimpleModuleProvider, Emitter, EmitError, OptLevel};
+use compile::{write_ir, verify_module, wasm_exports};
+use run::{MCJIT, LLVMJIT, CallError, HostFn, Value};
 
-pub fn create_module_compiler(input: &'static str, name: &str, optimize: bool)
+pub fn create_module_compiler(input: &'static str, name: &str, level: OptLevel)
         -> ModuleCompiler<SimpleModuleProvider> {
     let mut parser = parser(input);
     let program = parser.parse_unit()
         .expect("Could not parse program");
     let (block, table, _errors) = program.decompose();
-    let module_provider = SimpleModuleProvider::new(name, optimize);
-    let mut compiler = ModuleCompiler::new(table, module_provider, optimize);
+    let module_provider = SimpleModuleProvider::new(name, level);
+    let mut compiler = ModuleCompiler::new(table, module_provider);
     compiler.check_unit(&block);
     compiler
 }
 
+/// Compile a program with the JIT, checking that the module is valid
+pub fn create_jit(input: &'static str, name: &str) -> (MCJIT, SymbolTable) {
+    let program = parser(input).parse_unit()
+        .expect("Could not parse program");
+    let (unit, table, _errors) = program.decompose();
+    let mut compiler = ModuleCompiler::new(table, MCJIT::new(name.to_string(), OptLevel::O0));
+    compiler.check_unit(&unit);
+    let (jit, _context, table) = compiler.decompose();
+    verify_module(&jit).expect("Compiled module is invalid");
+    (jit, table)
+}
+
 #[test]
 fn compile_example() {
     let inputs = &[
@@ -35,8 +56,203 @@ fn fact(n)
     for (ix, input) in inputs.into_iter().enumerate() {
         trace!("Checking program {} - {:?}", ix, input);
         let name = format!("dump_basic_definitions_{}", ix);
-        let compiler = create_module_compiler(input, &name, false);
+        let compiler = create_module_compiler(input, &name, OptLevel::O0);
+        let (provider, _context, _symbols) = compiler.decompose();
+        let path = env::temp_dir().join(format!("{}.ll", name));
+        write_ir(&provider, &path).expect("Could not write IR");
+        trace!("Wrote IR to {}", path.display());
+    }
+}
+
+#[test]
+fn it_compiles_returns_inside_loops() {
+    let input = r#"
+fn fromWhile(n: Int) -> Int
+    while true
+        return n
+    0
+fn fromLoop(n: Int) -> Int
+    loop
+        if n > 2
+            return n
+        break
+    1
+fn fromFor(n: Int) -> Int
+    for i in 0..n
+        return i
+    2
+"#;
+    let compiler = create_module_compiler(input, "returns_inside_loops", OptLevel::O0);
+    let (provider, _context, _symbols) = compiler.decompose();
+    verify_module(&provider).expect("Returning from a loop made an invalid module");
+}
+
+#[test]
+fn it_traps_on_integer_division_by_zero() {
+    let input = r#"
+fn divide(x: Int) -> Int
+    x / 0
+fn remainder(x: Int) -> Int
+    x % 0
+"#;
+    for level in &[OptLevel::O0, OptLevel::O2] {
+        let name = format!("division_by_zero_{}", level);
+        let compiler = create_module_compiler(input, &name, *level);
         let (provider, _context, _symbols) = compiler.decompose();
-        provider.get_module().dump();
+        verify_module(&provider).expect("Checking division made an invalid module");
+        let path = env::temp_dir().join(format!("{}.ll", name));
+        write_ir(&provider, &path).expect("Could not write IR");
+        let mut ir = String::new();
+        File::open(&path).and_then(|mut file| file.read_to_string(&mut ir))
+            .expect("Could not read IR");
+        assert!(ir.contains("call void @llvm.trap()"),
+                "Division at {} does not trap:\n{}", level, ir);
     }
 }
+
+#[test]
+fn it_optimizes_at_every_level() {
+    let input = r#"
+fn factHelper(n, acc)
+    if n <= 1
+        acc
+    else
+        factHelper(n: n - 1, acc: acc * n)
+fn sumEvens(n)
+    let mut total = 0
+    for i in 0..=n by 2
+        total += i
+    while total > 1000
+        total -= 1000
+    total
+fn main()
+    factHelper(n: 5, acc: 1) + sumEvens(n: 10)
+"#;
+    let levels = &[OptLevel::O0, OptLevel::O1, OptLevel::O2, OptLevel::O3, OptLevel::Os];
+    for level in levels {
+        let name = format!("optimized_{}", level);
+        let compiler = create_module_compiler(input, &name, *level);
+        let (provider, _context, _symbols) = compiler.decompose();
+        verify_module(&provider)
+            .unwrap_or_else(|error| panic!("Optimizing at {} broke the module: {}", level, error));
+    }
+}
+
+#[test]
+fn it_calls_jit_compiled_functions() {
+    let input = r#"
+fn fact(n)
+    if n <= 1
+        1
+    else
+        n * fact(n: n - 1)
+fn scale(x: Float, factor: Float) -> Float
+    x * factor
+"#;
+    let (mut jit, table) = create_jit(input, "jit_call");
+    assert_eq!(jit.call(&table, "fact", &[("n", Value::Int(10))]), Ok(Value::Int(3628800)));
+    assert_eq!(jit.call(&table, "scale", &[("factor", Value::Float(0.5)), ("x", Value::Float(3.0))]),
+               Ok(Value::Float(1.5)));
+    assert_eq!(jit.call(&table, "fact", &[("n", Value::Float(10.0))]),
+               Err(CallError::ArgumentType {
+                   function: "fact".into(), name: "n".into(),
+                   expected: Type::Int, found: Type::Float
+               }));
+    assert_eq!(jit.call(&table, "factorial", &[("n", Value::Int(10))]),
+               Err(CallError::UnknownFunction("factorial".into())));
+}
+
+#[test]
+fn it_calls_host_closures_from_jit_compiled_code() {
+    let input = r#"
+fn run(x: Int) -> Int
+    let evenBonus = if isEven(x) => 100 else 0
+    choose(flag: x > 2, x) + evenBonus + addTen(x) + addTwenty(x)
+"#;
+    // Both adders have the same closure type, but must keep their own offsets
+    let add_offset = |name: &str, offset: i64| {
+        HostFn::from_closure(name, &["x"], move |x: i64| x + offset)
+            .expect("Could not create host function")
+    };
+    let host_fns = vec![
+        HostFn::from_closure("choose", &["flag", "x"], |flag: bool, x: i64| if flag { x } else { -x })
+            .expect("Could not create host function"),
+        HostFn::from_closure("isEven", &["x"], |x: i64| x % 2 == 0)
+            .expect("Could not create host function"),
+        add_offset("addTen", 10),
+        add_offset("addTwenty", 20)
+    ];
+    let mut definitions = Definitions::new();
+    for host_fn in &host_fns {
+        host_fn.define(&mut definitions);
+    }
+    let program = parser(input).parse_unit_incremental(&definitions)
+        .expect("Could not parse program");
+    let (unit, table, _errors) = program.decompose();
+    let mut jit = MCJIT::new("host_closures".to_string(), OptLevel::O0);
+    for host_fn in &host_fns {
+        jit.add_host_fn(host_fn);
+    }
+    let mut compiler = ModuleCompiler::new(table, jit);
+    for symbol in definitions.get_symbols().values() {
+        compiler.declare_external(symbol);
+    }
+    compiler.check_unit(&unit);
+    let (mut jit, _context, table) = compiler.decompose();
+    verify_module(&jit).expect("Compiled module is invalid");
+
+    assert_eq!(jit.call(&table, "run", &[("x", Value::Int(5))]), Ok(Value::Int(5 + 15 + 25)));
+    assert_eq!(jit.call(&table, "run", &[("x", Value::Int(2))]), Ok(Value::Int(-2 + 100 + 12 + 22)));
+}
+
+#[test]
+fn it_runs_functions_compiled_to_wasm() {
+    let input = r#"
+fn factHelper(n, acc)
+    if n <= 1
+        acc
+    else
+        factHelper(n: n - 1, acc: acc * n)
+fn fact(n)
+    factHelper(n, acc: 1)
+"#;
+    let program = parser(input).parse_unit()
+        .expect("Could not parse program");
+    let (unit, table, _errors) = program.decompose();
+    // The WebAssembly backend is experimental in LLVM 4, and linking needs
+    // `wasm-ld`, so the test is skipped when either is missing
+    let emitter = match Emitter::new("wasm32-unknown-unknown", "", "", OptLevel::O2) {
+        Ok(emitter) => emitter,
+        Err(error @ EmitError::UnknownTarget { .. }) => {
+            println!("Skipping WebAssembly test: {}", error);
+            return
+        }
+        Err(error) => panic!("Could not create emitter: {}", error)
+    };
+    let linker = env::var("WASM_LD").unwrap_or_else(|_| "wasm-ld".to_string());
+    if let Err(error) = Command::new(&linker).arg("--version").output() {
+        println!("Skipping WebAssembly test: could not run {}: {}", linker, error);
+        return
+    }
+    let mut provider = SimpleModuleProvider::new("wasm_fact", OptLevel::O2);
+    emitter.prepare_module(&mut provider);
+    let mut compiler = ModuleCompiler::new(table, provider);
+    compiler.check_unit(&unit);
+    let (provider, _context, _symbols) = compiler.decompose();
+
+    let object = env::temp_dir().join("wasm_fact.o");
+    let output = env::temp_dir().join("wasm_fact.wasm");
+    emitter.emit_object(&provider, &object).expect("Could not write object");
+    emitter.link_wasm(&object, &output, &wasm_exports(&unit)).expect("Could not link module");
+    let mut bytes = Vec::new();
+    File::open(&output).and_then(|mut file| file.read_to_end(&mut bytes))
+        .expect("Could not read module");
+
+    let module = ::wasmi::Module::from_buffer(&bytes).expect("Could not load module");
+    let instance = ModuleInstance::new(&module, &ImportsBuilder::default())
+        .expect("Could not instantiate module")
+        .assert_no_start();
+    let result = instance.invoke_export("fact", &[RuntimeValue::I64(5)], &mut NopExternals)
+        .expect("Could not call fact");
+    assert_eq!(result, Some(RuntimeValue::I64(120)));
+}
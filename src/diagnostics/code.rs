@@ -0,0 +1,271 @@
+//! Stable codes identifying each kind of diagnostic.
+
+use diagnostics::Severity;
+
+macro_rules! error_codes {
+    ($($(#[$attr:meta])* $name:ident : $code:expr, $title:expr, $explanation:expr;)*) => {
+        /// Identifies the kind of problem a diagnostic is about.
+        ///
+        /// Codes never change meaning once they have been published, so
+        /// they can be used to filter diagnostics or look up an explanation.
+        #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+        pub enum ErrorCode {
+            $($(#[$attr])* $name,)*
+        }
+        impl ErrorCode {
+            /// Every error code, in order.
+            pub fn all() -> &'static [ErrorCode] {
+                &[$(ErrorCode::$name,)*]
+            }
+            /// Get the code as it's shown to users, i.e. `E0003`.
+            pub fn get_code(self) -> &'static str {
+                match self {
+                    $(ErrorCode::$name => $code,)*
+                }
+            }
+            /// Get a short description of the code, i.e. "reassign immutable".
+            pub fn get_title(self) -> &'static str {
+                match self {
+                    $(ErrorCode::$name => $title,)*
+                }
+            }
+            /// Get a longer explanation of the problem, with an example.
+            pub fn get_explanation(self) -> &'static str {
+                match self {
+                    $(ErrorCode::$name => $explanation,)*
+                }
+            }
+        }
+    }
+}
+
+error_codes! {
+    /// A variable was used which hasn't been declared
+    UndeclaredVariable: "E0001", "undeclared variable",
+"A variable was used before it was declared, or it was declared in a scope
+which has ended.
+
+    let x = y + 1 // y hasn't been declared
+
+Declare the variable with `let` before using it.";
+
+    /// A name was declared twice in the same scope
+    AlreadyDeclared: "E0002", "already declared",
+"A variable, parameter or function was given a name which is already in use.
+
+    let x = 0
+    let x = 1 // x is already declared
+
+Use a different name, or make the first variable `mut` and assign to it.";
+
+    /// An immutable variable was assigned to
+    ReassignImmutable: "E0003", "reassign immutable",
+"A variable which wasn't declared `mut` was assigned to.
+
+    let x = 0
+    x += 1 // x is not mutable
+
+Declare the variable with `let mut` if it needs to change.";
+
+    /// A function was called which hasn't been declared
+    UnknownFunction: "E0004", "unknown function",
+"A function was called which hasn't been declared. Functions have to be
+declared before the functions which call them.
+
+    fn main() => foo(x: 1) // foo is not declared";
+
+    /// A function was called with the wrong number of arguments
+    WrongArgCount: "E0005", "wrong number of arguments",
+"A function was called with more or fewer arguments than it has parameters.
+
+    fn add(x, y) => x + y
+    fn main() => add(x: 1) // y is missing";
+
+    /// A function was called with an argument it doesn't have
+    UnknownParameter: "E0006", "unknown parameter",
+"A function was called with a named argument which doesn't match any of its
+parameters.
+
+    fn double(x) => x * 2
+    fn main() => double(y: 1) // double has no parameter y";
+
+    /// A type annotation names a type which doesn't exist
+    UnknownType: "E0007", "unknown type",
+"A type annotation used a name which isn't a type. The types are `Int`,
+`Float`, `Bool` and `String`.
+
+    let x: Integer = 0 // did you mean Int?";
+
+    /// A value has a different type than where it's used
+    TypeMismatch: "E0008", "type mismatch",
+"A value was used where a value of a different type was expected. Numbers are
+never converted implicitly: use `toFloat` or `toInt` to convert between them.
+
+    let x: Float = true // true is a Bool
+    let y = 1 + 2.5     // 1 is an Int and 2.5 is a Float";
+
+    /// Arithmetic was done on something which isn't a number
+    ExpectedNumber: "E0009", "expected number",
+"An arithmetic operator or a range was given a value which isn't an `Int` or
+a `Float`.
+
+    let text = \"hello\"
+    text - 1 // strings can't be subtracted";
+
+    /// `break` or `continue` was used outside of a loop
+    LoopControlOutsideLoop: "E0010", "break or continue outside of loop",
+"`break` and `continue` can only be used inside of a `while`, `for` or `loop`.
+
+    fn main()
+        break // not in a loop";
+
+    /// A function was declared with the name of a built in function
+    BuiltinRedeclared: "E0011", "redeclared builtin",
+"A function was declared with the same name as one of the built in functions,
+`toFloat` and `toInt`.
+
+    fn toInt(x) => x // toInt is built in";
+
+    /// A function with a value can reach its end without returning one
+    MissingReturn: "E0012", "missing return",
+"A function which returns a value can finish without returning one, such as
+when it ends in a loop or an `if` without an `else`.
+
+    fn first(n: Int) -> Int
+        while n > 0
+            return n // nothing is returned if n isn't positive
+
+Add a `return` or a final value after the loop, or use a `loop` which is only
+left by returning.";
+
+    /// Something which isn't a function was called
+    NotAFunction: "E0013", "not a function",
+"A variable or parameter was called as if it were a function. Only functions
+declared with `fn` can be called.
+
+    let f = 1
+    f(x: 2) // f is an Int";
+
+    /// A `for` loop was given a step which isn't positive
+    NonPositiveStep: "E0014", "non-positive step",
+"The step given to a `for` loop with `by` is zero or negative. Loops count up
+through their range, so the loop would never run or never end.
+
+    for i in 10..0 by -1 // counts up from 10, so never runs
+        total += i";
+
+    /// The parser found a token it didn't expect
+    UnexpectedToken: "E0100", "unexpected token",
+"The parser found a token which can't be used where it was written.
+
+    let = 1 // expected a variable name after let";
+
+    /// The file ended in the middle of something
+    UnexpectedEndOfFile: "E0101", "unexpected end of file",
+"The file ended before an expression or statement was finished.
+
+    let x = // expected a value";
+
+    /// Something without a value was used as an expression
+    ExpectedExpression: "E0102", "expected expression",
+"A declaration or assignment was used where a value is needed. They don't
+produce values.
+
+    let x = let y = 1 // let y = 1 doesn't have a value";
+
+    /// Something other than a variable was assigned to
+    ExpectedLValue: "E0103", "expected variable",
+"Only variables can be assigned to.
+
+    1 = x // 1 is not a variable";
+
+    /// An operator was used which doesn't exist
+    UnknownOperator: "E0104", "unknown operator",
+"The parser found an operator it doesn't know about.";
+
+    /// The lexer couldn't read a token
+    InvalidToken: "E0105", "invalid token",
+"The source contains text which isn't valid protosnirk, such as a string
+without a closing quote or a number which is too large.
+
+    let big = 99999999999999999999 // doesn't fit in an Int";
+
+    /// Block and inline `if` syntax were mixed together
+    InlineElseIf: "E0106", "mixed if syntax",
+"An `if` written inline with `=>` can't have an `else if`, and an `if` block
+can't have an inline `else if`. Use an `if` block for more than two branches.
+
+    let x = if a => 1 else if b => 2 else 3";
+
+    /// The compiler produced LLVM IR which LLVM's verifier rejected
+    InvalidModule: "E0200", "invalid module",
+"The LLVM IR compiled from the program was rejected by LLVM's verifier. This
+is a bug in protosnirk's compiler rather than a problem with the program, and
+the message says what LLVM found wrong.
+
+Please report it along with the program which caused it.";
+
+    /// A variable is never used
+    UnusedVariable: "W0001", "unused variable",
+"A variable, parameter or function was declared but never used. It may be a
+mistake, or left over from a change.
+
+    let x = 0 // x is never used";
+
+    /// A mutable variable is never changed
+    UnmutatedMutable: "W0002", "unmutated mutable",
+"A variable was declared `mut` but is never assigned to, so it doesn't need to
+be mutable.
+
+    let mut x = 0 // x is never changed
+    x + 1";
+}
+
+impl ErrorCode {
+    /// Find the error code with the given text, i.e. `E0003`.
+    pub fn from_code(code: &str) -> Option<ErrorCode> {
+        ErrorCode::all().iter()
+            .find(|error_code| error_code.get_code().eq_ignore_ascii_case(code))
+            .cloned()
+    }
+
+    /// How serious problems with this code are by default.
+    pub fn get_severity(self) -> Severity {
+        if self.get_code().starts_with('W') {
+            Severity::Warning
+        }
+        else {
+            Severity::Error
+        }
+    }
+
+    /// Get the catalogue entry for this code, as shown by `--explain`.
+    pub fn explain(self) -> String {
+        format!("{}: {}\n\n{}\n", self.get_code(), self.get_title(), self.get_explanation())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use diagnostics::Severity;
+    use super::ErrorCode;
+
+    #[test]
+    fn it_has_unique_codes() {
+        let mut seen = HashSet::new();
+        for code in ErrorCode::all() {
+            assert!(seen.insert(code.get_code()), "Code {} is used twice", code.get_code());
+        }
+    }
+
+    #[test]
+    fn it_looks_up_codes() {
+        assert_eq!(ErrorCode::from_code("E0003"), Some(ErrorCode::ReassignImmutable));
+        assert_eq!(ErrorCode::from_code("e0003"), Some(ErrorCode::ReassignImmutable));
+        assert_eq!(ErrorCode::from_code("E9999"), None);
+        assert_eq!(ErrorCode::UnusedVariable.get_severity(), Severity::Warning);
+        assert_eq!(ErrorCode::ReassignImmutable.get_severity(), Severity::Error);
+    }
+}
@@ -0,0 +1,174 @@
+//! Compiler messages which can be shown to the user
+
+use lex::{Token, TokenData, TextLocation, Span};
+use parse::{ParseError, ErrorCollector, VerifyError};
+use diagnostics::ErrorCode;
+
+/// How serious a diagnostic is
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Severity {
+    /// The program can't be compiled
+    Error,
+    /// The program compiles but probably has a mistake
+    Warning,
+    /// The program could be written more clearly
+    Lint
+}
+impl Severity {
+    pub fn get_name(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Lint => "lint"
+        }
+    }
+}
+
+/// A span of the source which a diagnostic points to.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Label {
+    span: Span,
+    text: Option<String>
+}
+impl Label {
+    pub fn new(span: Span, text: Option<String>) -> Label {
+        Label { span: span, text: text }
+    }
+    /// Create a label underlining the given token.
+    pub fn from_token(token: &Token, text: Option<String>) -> Label {
+        Label::new(token.get_span(), text)
+    }
+    pub fn get_span(&self) -> Span {
+        self.span
+    }
+    /// Get the location of the start of the label
+    pub fn get_location(&self) -> TextLocation {
+        self.span.start
+    }
+    pub fn get_text(&self) -> Option<&str> {
+        self.text.as_ref().map(|text| &**text)
+    }
+}
+
+/// An error, warning or lint about a program.
+///
+/// Every diagnostic has an `ErrorCode` saying what kind of problem it is.
+/// The `primary` label is where the problem was found, and is missing
+/// when the problem doesn't have a location (i.e. the end of the file).
+/// `secondary` labels point to related code, such as a declaration.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Diagnostic {
+    code: ErrorCode,
+    severity: Severity,
+    message: String,
+    primary: Option<Label>,
+    secondary: Vec<Label>
+}
+impl Diagnostic {
+    pub fn new(code: ErrorCode,
+               severity: Severity,
+               message: String,
+               primary: Option<Label>,
+               secondary: Vec<Label>) -> Diagnostic {
+        Diagnostic {
+            code: code,
+            severity: severity,
+            message: message,
+            primary: primary,
+            secondary: secondary
+        }
+    }
+
+    /// Create a diagnostic from an error found by the verifier.
+    ///
+    /// The severity is the one the error was collected as, which may
+    /// differ from the default severity of its code.
+    pub fn from_verify_error(error: &VerifyError, severity: Severity) -> Diagnostic {
+        let secondary = error.get_references().iter()
+            .map(|&(ref reference, label)| Label::from_token(reference, Some(label.into())))
+            .collect();
+        Diagnostic::new(error.get_code(),
+                        severity,
+                        error.get_text().to_string(),
+                        Some(Label::from_token(error.get_offender(), None)),
+                        secondary)
+    }
+
+    /// Get diagnostics for all of the errors, warnings and lints in a collector.
+    pub fn from_collector(collector: &ErrorCollector) -> Vec<Diagnostic> {
+        let errors = collector.get_errors().iter()
+            .map(|error| Diagnostic::from_verify_error(error, Severity::Error));
+        let warnings = collector.get_warnings().iter()
+            .map(|warning| Diagnostic::from_verify_error(warning, Severity::Warning));
+        let lints = collector.get_lints().iter()
+            .map(|lint| Diagnostic::from_verify_error(lint, Severity::Lint));
+        errors.chain(warnings).chain(lints).collect()
+    }
+
+    /// Get diagnostics for a parse error.
+    ///
+    /// Errors which contain other errors are flattened.
+    pub fn from_parse_error(error: &ParseError) -> Vec<Diagnostic> {
+        let (code, message, span) = match *error {
+            ParseError::ExpectedToken { ref expected, ref got } =>
+                (ErrorCode::UnexpectedToken,
+                 format!("Expected {:?}, got `{}`", expected, got.get_text()), Some(got.get_span())),
+            ParseError::ExpectedExpression { ref expected, ref got } =>
+                (ErrorCode::ExpectedExpression,
+                 format!("Expected {:?}", expected), Some(got.span())),
+            ParseError::ExpectedLValue(ref got) =>
+                (ErrorCode::ExpectedLValue,
+                 "Expected a variable to assign to".to_string(), Some(got.span())),
+            ParseError::ExpectedRValue(ref got) =>
+                (ErrorCode::ExpectedExpression,
+                 "Expected a value".to_string(), Some(got.span())),
+            ParseError::UnknownOperator { ref text, ref token_type } =>
+                (ErrorCode::UnknownOperator,
+                 format!("Unknown operator {:?} `{}`", token_type, text), None),
+            ParseError::LexError(ref token) => {
+                let message = match token.data {
+                    TokenData::Error(ref message) => message.to_string(),
+                    _ => format!("Unexpected `{}`", token.get_text())
+                };
+                (ErrorCode::InvalidToken, message, Some(token.get_span()))
+            },
+            ParseError::UnexpectedToken(ref token) => {
+                // Indentation tokens don't have any text
+                let message = if token.get_text().is_empty() {
+                    format!("Unexpected {:?}", token.data.get_type())
+                } else {
+                    format!("Unexpected `{}`", token.get_text())
+                };
+                (ErrorCode::UnexpectedToken, message, Some(token.get_span()))
+            },
+            ParseError::InlineElseIf(ref token) =>
+                (ErrorCode::InlineElseIf,
+                 "Cannot mix inline and block `if` syntax in an `else if`".to_string(),
+                 Some(token.get_span())),
+            ParseError::EOF =>
+                (ErrorCode::UnexpectedEndOfFile, "Unexpected end of file".to_string(), None),
+            ParseError::VerifierError { ref collection } =>
+                return Diagnostic::from_collector(collection),
+            ParseError::SyntaxErrors { ref errors, .. } =>
+                return errors.iter().flat_map(Diagnostic::from_parse_error).collect()
+        };
+        let primary = span.map(|span| Label::new(span, None));
+        vec![Diagnostic::new(code, code.get_severity(), message, primary, vec![])]
+    }
+
+    pub fn get_code(&self) -> ErrorCode {
+        self.code
+    }
+    pub fn get_severity(&self) -> Severity {
+        self.severity
+    }
+    pub fn get_message(&self) -> &str {
+        &self.message
+    }
+    pub fn get_primary(&self) -> Option<&Label> {
+        self.primary.as_ref()
+    }
+    pub fn get_secondary(&self) -> &[Label] {
+        &self.secondary
+    }
+}
@@ -0,0 +1,154 @@
+//! Emits diagnostics as JSON, for editors and other tools.
+//!
+//! Each diagnostic is written as a single JSON object on its own line:
+//!
+//! ```text
+//! {"file":"example.protosnirk","code":"E0002","title":"already declared",
+//!  "severity":"error","message":"Variable x is already declared",
+//!  "primary":{"byte_start":14,"byte_end":15,"line_start":2,"column_start":5,
+//!             "line_end":2,"column_end":6,"label":null},
+//!  "related":[{"byte_start":4,"byte_end":5,"line_start":1,"column_start":5,
+//!              "line_end":1,"column_end":6,"label":"previously declared here"}]}
+//! ```
+//!
+//! Byte offsets start at 0 and are into the UTF-8 source. Lines and columns
+//! start at 1, as they do in rendered diagnostics, and columns count
+//! characters. Ends are exclusive. `primary` is `null` for diagnostics
+//! which don't have a location, such as an unexpected end of file.
+
+use std::fmt::Write;
+
+use diagnostics::{Diagnostic, Label};
+
+/// Writes diagnostics about a source file in the JSON format described in
+/// the module documentation.
+#[derive(Debug, Clone)]
+pub struct JsonEmitter<'a> {
+    file_name: &'a str,
+    source: &'a str
+}
+impl<'a> JsonEmitter<'a> {
+    pub fn new(file_name: &'a str, source: &'a str) -> JsonEmitter<'a> {
+        JsonEmitter { file_name: file_name, source: source }
+    }
+
+    /// Emit all of the given diagnostics, one per line.
+    pub fn emit_all(&self, diagnostics: &[Diagnostic]) -> String {
+        diagnostics.iter()
+            .map(|diagnostic| self.emit(diagnostic))
+            .collect()
+    }
+
+    /// Emit a diagnostic as a JSON object, ending with a newline.
+    pub fn emit(&self, diagnostic: &Diagnostic) -> String {
+        let code = diagnostic.get_code();
+        let mut out = String::new();
+        write!(out, "{{\"file\":{},\"code\":{},\"title\":{},\"severity\":{},\"message\":{},\"primary\":",
+            json_string(self.file_name), json_string(code.get_code()),
+            json_string(code.get_title()), json_string(diagnostic.get_severity().get_name()),
+            json_string(diagnostic.get_message())).unwrap();
+        match diagnostic.get_primary() {
+            Some(primary) => out.push_str(&self.span(primary)),
+            None => out.push_str("null")
+        }
+        let related: Vec<String> = diagnostic.get_secondary().iter()
+            .map(|label| self.span(label))
+            .collect();
+        writeln!(out, ",\"related\":[{}]}}", related.join(",")).unwrap();
+        out
+    }
+
+    /// Get the JSON object for the location of a label.
+    fn span(&self, label: &Label) -> String {
+        let span = label.get_span();
+        let byte_start = self.byte_offset(span.start.index);
+        let byte_end = self.byte_offset(span.end.index);
+        let text = match label.get_text() {
+            Some(text) => json_string(text),
+            None => "null".to_string()
+        };
+        format!("{{\"byte_start\":{},\"byte_end\":{},\"line_start\":{},\"column_start\":{},\
+                 \"line_end\":{},\"column_end\":{},\"label\":{}}}",
+            byte_start, byte_end, span.start.line + 1, span.start.column + 1,
+            span.end.line + 1, span.end.column + 1, text)
+    }
+
+    /// Convert an index of a character in the source to a byte offset.
+    fn byte_offset(&self, char_index: usize) -> usize {
+        self.source.char_indices()
+            .nth(char_index)
+            .map(|(offset, _)| offset)
+            .unwrap_or(self.source.len())
+    }
+}
+
+/// Quote and escape a string for JSON.
+fn json_string(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c)
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use lex::{Token, TokenData, TextLocation, Span};
+    use diagnostics::{Diagnostic, ErrorCode, JsonEmitter};
+    use parse::{ParseError, VerifyError};
+    use super::json_string;
+
+    #[test]
+    fn it_emits_spans_and_related_locations() {
+        let source = "let é = 0\nlet é = 1\n";
+        let ident = |index, line| Token {
+            span: Span {
+                start: TextLocation { index: index, line: line, column: 4 },
+                end: TextLocation { index: index + 1, line: line, column: 5 }
+            },
+            text: Cow::Borrowed("é"),
+            data: TokenData::Ident
+        };
+        let error = VerifyError::new(ErrorCode::AlreadyDeclared, ident(14, 1),
+                                     vec![(ident(4, 0), "previously declared here")],
+                                     "Variable é is already declared".into());
+        let diagnostic = Diagnostic::from_verify_error(&error, ErrorCode::AlreadyDeclared.get_severity());
+        let emitted = JsonEmitter::new("example.protosnirk", source).emit(&diagnostic);
+        let expected = concat!(
+            r#"{"file":"example.protosnirk","code":"E0002","title":"already declared","#,
+            r#""severity":"error","message":"Variable é is already declared","#,
+            r#""primary":{"byte_start":15,"byte_end":17,"line_start":2,"column_start":5,"#,
+            r#""line_end":2,"column_end":6,"label":null},"#,
+            r#""related":[{"byte_start":4,"byte_end":6,"line_start":1,"column_start":5,"#,
+            r#""line_end":1,"column_end":6,"label":"previously declared here"}]}"#,
+            "\n");
+        assert_eq!(emitted, expected);
+    }
+
+    #[test]
+    fn it_emits_errors_without_locations() {
+        let diagnostics = Diagnostic::from_parse_error(&ParseError::EOF);
+        let emitted = JsonEmitter::new("f.protosnirk", "").emit_all(&diagnostics);
+        assert_eq!(emitted, concat!(
+            r#"{"file":"f.protosnirk","code":"E0101","title":"unexpected end of file","#,
+            r#""severity":"error","message":"Unexpected end of file","primary":null,"related":[]}"#,
+            "\n"));
+    }
+
+    #[test]
+    fn it_escapes_strings() {
+        assert_eq!(json_string("a \"quote\"\\\n\u{1}"), r#""a \"quote\"\\\n\u0001""#);
+    }
+}
@@ -0,0 +1,17 @@
+//! Presents errors, warnings and lints to the user.
+//!
+//! `ParseError`s and the `VerifyError`s in an `ErrorCollector` are converted
+//! into `Diagnostic`s, which a `Renderer` shows with the lines of source
+//! they point to, or a `JsonEmitter` writes as JSON for other tools. Every
+//! diagnostic has an `ErrorCode`, which can be looked up with
+//! `ErrorCode::explain`.
+
+mod code;
+mod diagnostic;
+mod json;
+mod render;
+
+pub use self::code::ErrorCode;
+pub use self::diagnostic::{Diagnostic, Label, Severity};
+pub use self::json::JsonEmitter;
+pub use self::render::Renderer;
@@ -0,0 +1,219 @@
+//! Renders diagnostics as text, with snippets of the source they point to.
+
+use std::fmt::Write;
+
+use diagnostics::{Diagnostic, Label, Severity};
+
+const RESET: &'static str = "\x1b[0m";
+const BOLD: &'static str = "\x1b[1m";
+const RED: &'static str = "\x1b[1;31m";
+const YELLOW: &'static str = "\x1b[1;33m";
+const CYAN: &'static str = "\x1b[1;36m";
+const BLUE: &'static str = "\x1b[1;34m";
+
+/// Renders diagnostics about a source file in the style of `rustc`.
+///
+/// ```text
+/// error[E0002]: Variable x is already declared
+///  --> example.protosnirk:2:5
+///   |
+/// 1 | let x = 0
+///   |     - previously declared here
+/// 2 | let x = 1
+///   |     ^
+/// ```
+#[derive(Debug, Clone)]
+pub struct Renderer<'a> {
+    file_name: &'a str,
+    source: &'a str,
+    /// Whether to use ANSI colour codes
+    colour: bool
+}
+impl<'a> Renderer<'a> {
+    pub fn new(file_name: &'a str, source: &'a str, colour: bool) -> Renderer<'a> {
+        Renderer { file_name: file_name, source: source, colour: colour }
+    }
+
+    /// Render all of the given diagnostics, separated by blank lines.
+    pub fn render_all(&self, diagnostics: &[Diagnostic]) -> String {
+        let rendered: Vec<String> = diagnostics.iter()
+            .map(|diagnostic| self.render(diagnostic))
+            .collect();
+        rendered.join("\n")
+    }
+
+    /// Render a diagnostic, ending with a newline.
+    pub fn render(&self, diagnostic: &Diagnostic) -> String {
+        let mut out = String::new();
+        let severity_colour = match diagnostic.get_severity() {
+            Severity::Error => RED,
+            Severity::Warning => YELLOW,
+            Severity::Lint => CYAN
+        };
+        writeln!(out, "{}{}[{}]:{} {}{}",
+            self.paint(severity_colour), diagnostic.get_severity().get_name(),
+            diagnostic.get_code().get_code(),
+            self.paint(BOLD), diagnostic.get_message(), self.paint(RESET)).unwrap();
+        let primary = match diagnostic.get_primary() {
+            Some(primary) => primary,
+            None => return out
+        };
+
+        // Labels are shown under their lines, in the order of the source
+        let mut labels: Vec<(&Label, bool)> = vec![(primary, true)];
+        labels.extend(diagnostic.get_secondary().iter().map(|label| (label, false)));
+        labels.sort_by_key(|&(label, is_primary)|
+            (label.get_location().line, !is_primary, label.get_location().column));
+        let last_line = labels.iter()
+            .map(|&(label, _)| label.get_location().line)
+            .max().unwrap_or(0);
+        let gutter_width = (last_line + 1).to_string().len();
+        let gutter = " ".repeat(gutter_width);
+
+        let location = primary.get_location();
+        writeln!(out, "{}{}-->{} {}:{}:{}", gutter, self.paint(BLUE), self.paint(RESET),
+            self.file_name, location.line + 1, location.column + 1).unwrap();
+        writeln!(out, "{} {}|{}", gutter, self.paint(BLUE), self.paint(RESET)).unwrap();
+
+        let source_lines: Vec<&str> = self.source.lines().collect();
+        let mut previous_line: Option<usize> = None;
+        for &(label, is_primary) in &labels {
+            let line = label.get_location().line;
+            let text = source_lines.get(line).cloned().unwrap_or("");
+            if previous_line != Some(line) {
+                if previous_line.map(|previous| previous + 1 < line).unwrap_or(false) {
+                    writeln!(out, "{}...{}", self.paint(BLUE), self.paint(RESET)).unwrap();
+                }
+                writeln!(out, "{}{:>width$} |{} {}", self.paint(BLUE), line + 1,
+                    self.paint(RESET), text, width = gutter_width).unwrap();
+                previous_line = Some(line);
+            }
+            let (marker, marker_colour) = if is_primary {
+                ("^", severity_colour)
+            }
+            else {
+                ("-", BLUE)
+            };
+            let mut marker_line = format!("{}{}", " ".repeat(label.get_location().column),
+                                          marker.repeat(underline_length(label, text)));
+            if let Some(text) = label.get_text() {
+                marker_line.push(' ');
+                marker_line.push_str(text);
+            }
+            writeln!(out, "{} {}|{} {}{}", gutter, self.paint(BLUE),
+                self.paint(marker_colour), marker_line, self.paint(RESET)).unwrap();
+        }
+        out
+    }
+
+    /// Get the given colour code, or nothing if colour is disabled.
+    fn paint(&self, code: &'static str) -> &'static str {
+        if self.colour { code } else { "" }
+    }
+}
+
+/// Get the number of characters to underline for a label on the given line.
+///
+/// Spans which continue onto later lines are underlined to the end of their
+/// first line. Something is always underlined, even for empty spans.
+fn underline_length(label: &Label, line_text: &str) -> usize {
+    let span = label.get_span();
+    let length = if span.end.line == span.start.line {
+        span.end.column.saturating_sub(span.start.column)
+    }
+    else {
+        line_text.chars().count().saturating_sub(span.start.column)
+    };
+    ::std::cmp::max(length, 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use lex::{Token, TokenData, TextLocation, Span};
+    use diagnostics::{Diagnostic, ErrorCode, Label, Severity, Renderer};
+    use parse::{ParseError, VerifyError};
+
+    fn ident(name: &'static str, index: usize, line: usize, column: usize) -> Token {
+        Token {
+            span: Span {
+                start: TextLocation { index: index, line: line, column: column },
+                end: TextLocation { index: index + name.len(), line: line, column: column + name.len() }
+            },
+            text: Cow::Borrowed(name),
+            data: TokenData::Ident
+        }
+    }
+
+    #[test]
+    fn it_renders_primary_and_secondary_labels() {
+        let source = "let x = 0\nlet x = 1\n";
+        let error = VerifyError::new(ErrorCode::AlreadyDeclared, ident("x", 14, 1, 4),
+                                     vec![(ident("x", 4, 0, 4), "previously declared here")],
+                                     "Variable x is already declared".into());
+        let diagnostic = Diagnostic::from_verify_error(&error, Severity::Error);
+        let rendered = Renderer::new("example.protosnirk", source, false).render(&diagnostic);
+        let expected = "\
+error[E0002]: Variable x is already declared
+ --> example.protosnirk:2:5
+  |
+1 | let x = 0
+  |     - previously declared here
+2 | let x = 1
+  |     ^
+";
+        assert_eq!(rendered, expected);
+    }
+
+    #[test]
+    fn it_underlines_whole_tokens() {
+        let source = "fn f()\n    unknown + 1\n";
+        let diagnostic = Diagnostic::new(ErrorCode::UnusedVariable, Severity::Warning,
+            "Something's up".into(),
+            Some(Label::from_token(&ident("unknown", 11, 1, 4), Some("here".into()))), vec![]);
+        let rendered = Renderer::new("f.protosnirk", source, false).render(&diagnostic);
+        let expected = "\
+warning[W0001]: Something's up
+ --> f.protosnirk:2:5
+  |
+2 |     unknown + 1
+  |     ^^^^^^^ here
+";
+        assert_eq!(rendered, expected);
+    }
+
+    #[test]
+    fn it_underlines_spans_to_the_end_of_their_first_line() {
+        let source = "fn f()\n    x +\n        1\n";
+        let span = Span {
+            start: TextLocation { index: 11, line: 1, column: 4 },
+            end: TextLocation { index: 24, line: 2, column: 9 }
+        };
+        let diagnostic = Diagnostic::new(ErrorCode::UnusedVariable, Severity::Warning,
+            "Something's up".into(), Some(Label::new(span, None)), vec![]);
+        let rendered = Renderer::new("f.protosnirk", source, false).render(&diagnostic);
+        let expected = "\
+warning[W0001]: Something's up
+ --> f.protosnirk:2:5
+  |
+2 |     x +
+  |     ^^^
+";
+        assert_eq!(rendered, expected);
+    }
+
+    #[test]
+    fn it_renders_errors_without_locations() {
+        let diagnostics = Diagnostic::from_parse_error(&ParseError::EOF);
+        let rendered = Renderer::new("f.protosnirk", "", false).render_all(&diagnostics);
+        assert_eq!(rendered, "error[E0101]: Unexpected end of file\n");
+    }
+
+    #[test]
+    fn it_renders_colour() {
+        let diagnostics = Diagnostic::from_parse_error(&ParseError::EOF);
+        let rendered = Renderer::new("f.protosnirk", "", true).render_all(&diagnostics);
+        assert_eq!(rendered, "\x1b[1;31merror[E0101]:\x1b[1m Unexpected end of file\x1b[0m\n");
+    }
+}
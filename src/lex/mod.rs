@@ -9,7 +9,7 @@ pub mod tokenizer;
 pub mod tests;
 
 pub use self::token::{Token, TokenType, TokenData};
-pub use self::textiter::{TextLocation, TextIter, PeekTextIter};
+pub use self::textiter::{TextLocation, Span, TextIter, PeekTextIter};
 pub use self::tokenizer::{Tokenizer, IterTokenizer};
 
 /// Type representing a borrowed or owned string
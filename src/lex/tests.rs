@@ -4,7 +4,7 @@
 use std::borrow::Cow;
 use std::str::Chars;
 
-use lex::{Token, TokenType, TokenData, TextLocation, Tokenizer, IterTokenizer};
+use lex::{Token, TokenType, TokenData, TextLocation, Span, Tokenizer, IterTokenizer};
 
 macro_rules! match_tokens {
     ($tokenizer:ident { $($token:expr),* }) => {
@@ -29,19 +29,17 @@ fn it_grabs_single_keyword() {
         Token {
             data: TokenData::Keyword,
             text: Cow::Borrowed("let"),
-            location: TextLocation {
-                index: 0,
-                line: 0,
-                column: 0
+            span: Span {
+                start: TextLocation { index: 0, line: 0, column: 0 },
+                end: TextLocation { index: 3, line: 0, column: 3 }
             }
         },
         Token {
             data: TokenData::EOF,
             text: Cow::Borrowed(""),
-            location: TextLocation {
-                index: 3,
-                line: 0,
-                column: 3
+            span: Span {
+                start: TextLocation { index: 3, line: 0, column: 3 },
+                end: TextLocation { index: 3, line: 0, column: 3 }
             }
         }
     });
@@ -51,19 +49,17 @@ fn it_grabs_single_keyword() {
         Token {
             data: TokenData::Keyword,
             text: Cow::Borrowed("mut"),
-            location: TextLocation {
-                index: 0,
-                line: 0,
-                column: 0
+            span: Span {
+                start: TextLocation { index: 0, line: 0, column: 0 },
+                end: TextLocation { index: 3, line: 0, column: 3 }
             }
         },
         Token {
             data: TokenData::EOF,
             text: Cow::Borrowed(""),
-            location: TextLocation {
-                index: 3,
-                line: 0,
-                column: 3
+            span: Span {
+                start: TextLocation { index: 3, line: 0, column: 3 },
+                end: TextLocation { index: 3, line: 0, column: 3 }
             }
         }
     });
@@ -73,19 +69,17 @@ fn it_grabs_single_keyword() {
         Token {
             data: TokenData::Keyword,
             text: Cow::Borrowed("return"),
-            location: TextLocation {
-                index: 0,
-                line: 0,
-                column: 0
+            span: Span {
+                start: TextLocation { index: 0, line: 0, column: 0 },
+                end: TextLocation { index: 6, line: 0, column: 6 }
             }
         },
         Token {
             data: TokenData::EOF,
             text: Cow::Borrowed(""),
-            location: TextLocation {
-                index: 6,
-                line: 0,
-                column: 6
+            span: Span {
+                start: TextLocation { index: 6, line: 0, column: 6 },
+                end: TextLocation { index: 6, line: 0, column: 6 }
             }
         }
     });
@@ -99,15 +93,17 @@ fn it_grabs_prefix_symbol_at_end_of_file() {
         Token {
             data: TokenData::Symbol,
             text: Cow::Borrowed("+"),
-            location: TextLocation::default()
+            span: Span {
+                start: TextLocation { index: 0, line: 0, column: 0 },
+                end: TextLocation { index: 1, line: 0, column: 1 }
+            }
         },
         Token {
             data: TokenData::EOF,
             text: Cow::Borrowed(""),
-            location: TextLocation {
-                index: 1,
-                line: 0,
-                column: 1
+            span: Span {
+                start: TextLocation { index: 1, line: 0, column: 1 },
+                end: TextLocation { index: 1, line: 0, column: 1 }
             }
         }
     });
@@ -121,24 +117,25 @@ fn it_grabs_adjacent_prefix_symbols() {
         Token {
             data: TokenData::Symbol,
             text: Cow::Borrowed("+"),
-            location: TextLocation::default()
+            span: Span {
+                start: TextLocation { index: 0, line: 0, column: 0 },
+                end: TextLocation { index: 1, line: 0, column: 1 }
+            }
         },
         Token {
             data: TokenData::Symbol,
             text: Cow::Borrowed("-"),
-            location: TextLocation {
-                index: 1,
-                line: 0,
-                column: 1
+            span: Span {
+                start: TextLocation { index: 1, line: 0, column: 1 },
+                end: TextLocation { index: 2, line: 0, column: 2 }
             }
         },
         Token {
             data: TokenData::EOF,
             text: Cow::Borrowed(""),
-            location: TextLocation {
-                index: 2,
-                line: 0,
-                column: 2
+            span: Span {
+                start: TextLocation { index: 2, line: 0, column: 2 },
+                end: TextLocation { index: 2, line: 0, column: 2 }
             }
         }
     });
@@ -152,15 +149,17 @@ fn it_grabs_prefix_symbol_mid_file() {
         Token {
             data: TokenData::Symbol,
             text: Cow::Borrowed("+"),
-            location: TextLocation::default()
+            span: Span {
+                start: TextLocation { index: 0, line: 0, column: 0 },
+                end: TextLocation { index: 1, line: 0, column: 1 }
+            }
         },
         Token {
             data: TokenData::EOF,
             text: Cow::Borrowed(""),
-            location: TextLocation {
-                index: 2,
-                line: 0,
-                column: 2
+            span: Span {
+                start: TextLocation { index: 2, line: 0, column: 2 },
+                end: TextLocation { index: 2, line: 0, column: 2 }
             }
         }
     });
@@ -174,24 +173,25 @@ fn it_gabs_unmatching_parens() {
         Token {
             data: TokenData::Symbol,
             text: Cow::Borrowed("("),
-            location: TextLocation::default()
+            span: Span {
+                start: TextLocation { index: 0, line: 0, column: 0 },
+                end: TextLocation { index: 1, line: 0, column: 1 }
+            }
         },
         Token {
             data: TokenData::Symbol,
             text: Cow::Borrowed("("),
-            location: TextLocation {
-                index: 1,
-                line: 0,
-                column: 1
+            span: Span {
+                start: TextLocation { index: 1, line: 0, column: 1 },
+                end: TextLocation { index: 2, line: 0, column: 2 }
             }
         },
         Token {
             data: TokenData::EOF,
             text: Cow::Borrowed(""),
-            location: TextLocation {
-                index: 2,
-                line: 0,
-                column: 2
+            span: Span {
+                start: TextLocation { index: 2, line: 0, column: 2 },
+                end: TextLocation { index: 2, line: 0, column: 2 }
             }
         }
     });
@@ -205,24 +205,25 @@ fn it_grabs_matching_parens() {
         Token {
             data: TokenData::Symbol,
             text: Cow::Borrowed("("),
-            location: TextLocation::default()
+            span: Span {
+                start: TextLocation { index: 0, line: 0, column: 0 },
+                end: TextLocation { index: 1, line: 0, column: 1 }
+            }
         },
         Token {
             data: TokenData::Symbol,
             text: Cow::Borrowed(")"),
-            location: TextLocation {
-                index: 1,
-                line: 0,
-                column: 1
+            span: Span {
+                start: TextLocation { index: 1, line: 0, column: 1 },
+                end: TextLocation { index: 2, line: 0, column: 2 }
             }
         },
         Token {
             data: TokenData::EOF,
             text: Cow::Borrowed(""),
-            location: TextLocation {
-                index: 2,
-                line: 0,
-                column: 2
+            span: Span {
+                start: TextLocation { index: 2, line: 0, column: 2 },
+                end: TextLocation { index: 2, line: 0, column: 2 }
             }
         }
     });
@@ -236,15 +237,17 @@ fn it_grabs_single_ident() {
         Token {
             data: TokenData::Ident,
             text: Cow::Borrowed("anIdentifier_2"),
-            location: TextLocation::default()
+            span: Span {
+                start: TextLocation { index: 0, line: 0, column: 0 },
+                end: TextLocation { index: 14, line: 0, column: 14 }
+            }
         },
         Token {
             data: TokenData::EOF,
             text: Cow::Borrowed(""),
-            location: TextLocation {
-                index: 14,
-                line: 0,
-                column: 14
+            span: Span {
+                start: TextLocation { index: 14, line: 0, column: 14 },
+                end: TextLocation { index: 14, line: 0, column: 14 }
             }
         }
     });
@@ -259,15 +262,17 @@ fn it_grabs_single_unicode_ident() {
         Token {
             data: TokenData::Ident,
             text: Cow::Borrowed(input),
-            location: TextLocation::default()
+            span: Span {
+                start: TextLocation { index: 0, line: 0, column: 0 },
+                end: TextLocation { index: 26, line: 0, column: 26 }
+            }
         },
         Token {
             data: TokenData::EOF,
             text: Cow::Borrowed(""),
-            location: TextLocation {
-                index: 26,
-                line: 0,
-                column: 26
+            span: Span {
+                start: TextLocation { index: 26, line: 0, column: 26 },
+                end: TextLocation { index: 26, line: 0, column: 26 }
             }
         }
     });
@@ -281,64 +286,73 @@ fn it_grabs_let_ident() {
         Token {
             data: TokenData::Keyword,
             text: Cow::Borrowed("let"),
-            location: TextLocation::default()
+            span: Span {
+                start: TextLocation { index: 0, line: 0, column: 0 },
+                end: TextLocation { index: 3, line: 0, column: 3 }
+            }
         },
         Token {
             data: TokenData::Ident,
             text: Cow::Borrowed("x"),
-            location: TextLocation {
-                index: 4,
-                line: 0,
-                column: 4
+            span: Span {
+                start: TextLocation { index: 4, line: 0, column: 4 },
+                end: TextLocation { index: 5, line: 0, column: 5 }
             }
         },
         Token {
             data: TokenData::EOF,
             text: Cow::Borrowed(""),
-            location: TextLocation {
-                index: 5,
-                line: 0,
-                column: 5
+            span: Span {
+                start: TextLocation { index: 5, line: 0, column: 5 },
+                end: TextLocation { index: 5, line: 0, column: 5 }
             }
         }
     });
 }
 
 #[test]
-fn it_grabs_float_literal() {
+fn it_grabs_int_literal() {
     let input = "224";
     let mut tokenizer = make_tokenizer(input);
     match_tokens!(tokenizer {
         Token {
-            data: TokenData::NumberLiteral(224f64),
+            data: TokenData::IntLiteral(224),
             text: Cow::Borrowed("224"),
-            location: TextLocation::default()
+            span: Span {
+                start: TextLocation { index: 0, line: 0, column: 0 },
+                end: TextLocation { index: 3, line: 0, column: 3 }
+            }
         },
         Token {
             data: TokenData::EOF,
             text: Cow::Borrowed(""),
-            location: TextLocation {
-                index: 3,
-                line: 0,
-                column: 3
+            span: Span {
+                start: TextLocation { index: 3, line: 0, column: 3 },
+                end: TextLocation { index: 3, line: 0, column: 3 }
             }
         }
     });
+}
+
+#[test]
+fn it_grabs_float_literal() {
     let input = "2.4";
     let mut tokenizer = make_tokenizer(input);
     match_tokens!(tokenizer {
         Token {
             data: TokenData::NumberLiteral(2.4f64),
             text: Cow::Borrowed("2.4"),
-            location: TextLocation::default()
+            span: Span {
+                start: TextLocation { index: 0, line: 0, column: 0 },
+                end: TextLocation { index: 3, line: 0, column: 3 }
+            }
         },
         Token {
             data: TokenData::EOF,
             text: Cow::Borrowed(""),
-            location: TextLocation {
-                index: 3,
-                line: 0,
-                column: 3
+            span: Span {
+                start: TextLocation { index: 3, line: 0, column: 3 },
+                end: TextLocation { index: 3, line: 0, column: 3 }
             }
         }
     });
@@ -348,15 +362,17 @@ fn it_grabs_float_literal() {
         Token {
             data: TokenData::NumberLiteral(2e4f64),
             text: Cow::Borrowed("2e4"),
-            location: TextLocation::default()
+            span: Span {
+                start: TextLocation { index: 0, line: 0, column: 0 },
+                end: TextLocation { index: 3, line: 0, column: 3 }
+            }
         },
         Token {
             data: TokenData::EOF,
             text: Cow::Borrowed(""),
-            location: TextLocation {
-                index: 3,
-                line: 0,
-                column: 3
+            span: Span {
+                start: TextLocation { index: 3, line: 0, column: 3 },
+                end: TextLocation { index: 3, line: 0, column: 3 }
             }
         }
     });
@@ -366,15 +382,177 @@ fn it_grabs_float_literal() {
         Token {
             data: TokenData::NumberLiteral(2.4e4f64),
             text: Cow::Borrowed("2.4e4"),
-            location: TextLocation::default()
+            span: Span {
+                start: TextLocation { index: 0, line: 0, column: 0 },
+                end: TextLocation { index: 5, line: 0, column: 5 }
+            }
         },
         Token {
             data: TokenData::EOF,
             text: Cow::Borrowed(""),
-            location: TextLocation {
-                index: 5,
-                line: 0,
-                column: 5
+            span: Span {
+                start: TextLocation { index: 5, line: 0, column: 5 },
+                end: TextLocation { index: 5, line: 0, column: 5 }
+            }
+        }
+    });
+}
+
+#[test]
+fn it_grabs_string_literal() {
+    let input = r#""hello \"world\"\n\t\u{1F980}" x"#;
+    let mut tokenizer = make_tokenizer(input);
+    match_tokens!(tokenizer {
+        Token {
+            data: TokenData::StringLiteral("hello \"world\"\n\t\u{1F980}".to_string()),
+            text: Cow::Borrowed(r#""hello \"world\"\n\t\u{1F980}""#),
+            span: Span {
+                start: TextLocation { index: 0, line: 0, column: 0 },
+                end: TextLocation { index: 30, line: 0, column: 30 }
+            }
+        },
+        Token {
+            data: TokenData::Ident,
+            text: Cow::Borrowed("x"),
+            span: Span {
+                start: TextLocation { index: 31, line: 0, column: 31 },
+                end: TextLocation { index: 32, line: 0, column: 32 }
+            }
+        }
+    });
+}
+
+#[test]
+fn it_reports_unterminated_string_literal() {
+    let input = "\"abc\nx";
+    let mut tokenizer = make_tokenizer(input);
+    match_tokens!(tokenizer {
+        Token {
+            data: TokenData::Error(Cow::Borrowed("Unterminated string literal")),
+            text: Cow::Borrowed("\"abc"),
+            span: Span {
+                start: TextLocation { index: 0, line: 0, column: 0 },
+                end: TextLocation { index: 4, line: 0, column: 4 }
+            }
+        },
+        Token {
+            data: TokenData::Ident,
+            text: Cow::Borrowed("x"),
+            span: Span {
+                start: TextLocation { index: 5, line: 1, column: 0 },
+                end: TextLocation { index: 6, line: 1, column: 1 }
+            }
+        }
+    });
+}
+
+#[test]
+fn it_reports_bad_string_escapes() {
+    let input = r#""a\qb" "\u{110000}""#;
+    let mut tokenizer = make_tokenizer(input);
+    match_tokens!(tokenizer {
+        Token {
+            data: TokenData::Error(Cow::Borrowed("Unknown escape sequence `\\q`")),
+            text: Cow::Borrowed(r#""a\qb""#),
+            span: Span {
+                start: TextLocation { index: 0, line: 0, column: 0 },
+                end: TextLocation { index: 6, line: 0, column: 6 }
+            }
+        },
+        Token {
+            data: TokenData::Error(Cow::Borrowed("Invalid unicode character `\\u{110000}`")),
+            text: Cow::Borrowed(r#""\u{110000}""#),
+            span: Span {
+                start: TextLocation { index: 7, line: 0, column: 7 },
+                end: TextLocation { index: 19, line: 0, column: 19 }
+            }
+        }
+    });
+}
+
+#[test]
+fn it_grabs_ranges_after_numbers() {
+    let input = "0..n 1.5..=2";
+    let mut tokenizer = make_tokenizer(input);
+    match_tokens!(tokenizer {
+        Token {
+            data: TokenData::IntLiteral(0),
+            text: Cow::Borrowed("0"),
+            span: Span {
+                start: TextLocation { index: 0, line: 0, column: 0 },
+                end: TextLocation { index: 1, line: 0, column: 1 }
+            }
+        },
+        Token {
+            data: TokenData::Symbol,
+            text: Cow::Borrowed(".."),
+            span: Span {
+                start: TextLocation { index: 1, line: 0, column: 1 },
+                end: TextLocation { index: 3, line: 0, column: 3 }
+            }
+        },
+        Token {
+            data: TokenData::Ident,
+            text: Cow::Borrowed("n"),
+            span: Span {
+                start: TextLocation { index: 3, line: 0, column: 3 },
+                end: TextLocation { index: 4, line: 0, column: 4 }
+            }
+        },
+        Token {
+            data: TokenData::NumberLiteral(1.5f64),
+            text: Cow::Borrowed("1.5"),
+            span: Span {
+                start: TextLocation { index: 5, line: 0, column: 5 },
+                end: TextLocation { index: 8, line: 0, column: 8 }
+            }
+        },
+        Token {
+            data: TokenData::Symbol,
+            text: Cow::Borrowed("..="),
+            span: Span {
+                start: TextLocation { index: 8, line: 0, column: 8 },
+                end: TextLocation { index: 11, line: 0, column: 11 }
+            }
+        },
+        Token {
+            data: TokenData::IntLiteral(2),
+            text: Cow::Borrowed("2"),
+            span: Span {
+                start: TextLocation { index: 11, line: 0, column: 11 },
+                end: TextLocation { index: 12, line: 0, column: 12 }
+            }
+        }
+    });
+}
+
+#[test]
+fn it_grabs_bool_literals() {
+    let input = "true false trueish";
+    let mut tokenizer = make_tokenizer(input);
+    match_tokens!(tokenizer {
+        Token {
+            data: TokenData::BoolLiteral(true),
+            text: Cow::Borrowed("true"),
+            span: Span {
+                start: TextLocation { index: 0, line: 0, column: 0 },
+                end: TextLocation { index: 4, line: 0, column: 4 }
+            }
+        },
+        Token {
+            data: TokenData::BoolLiteral(false),
+            text: Cow::Borrowed("false"),
+            span: Span {
+                start: TextLocation { index: 5, line: 0, column: 5 },
+                end: TextLocation { index: 10, line: 0, column: 10 }
+            }
+        },
+        Token {
+            data: TokenData::Ident,
+            text: Cow::Borrowed("trueish"),
+            span: Span {
+                start: TextLocation { index: 11, line: 0, column: 11 },
+                end: TextLocation { index: 18, line: 0, column: 18 }
             }
         }
     });
@@ -389,28 +567,25 @@ fn it_ignores_line_comment() {
         Token {
             data: TokenData::Keyword,
             text: Cow::Borrowed("let"),
-            location: TextLocation {
-                index: 10,
-                line: 1,
-                column: 0
+            span: Span {
+                start: TextLocation { index: 10, line: 1, column: 0 },
+                end: TextLocation { index: 13, line: 1, column: 3 }
             }
         },
         Token {
             data: TokenData::Ident,
             text: Cow::Borrowed("x"),
-            location: TextLocation {
-                index: 14,
-                line: 1,
-                column: 4
+            span: Span {
+                start: TextLocation { index: 14, line: 1, column: 4 },
+                end: TextLocation { index: 15, line: 1, column: 5 }
             }
         },
         Token {
             data: TokenData::EOF,
             text: Cow::Borrowed(""),
-            location: TextLocation {
-                index: 15,
-                line: 1,
-                column: 5
+            span: Span {
+                start: TextLocation { index: 15, line: 1, column: 5 },
+                end: TextLocation { index: 15, line: 1, column: 5 }
             }
         }
     });
@@ -427,127 +602,297 @@ fn it_tokenizes_complex_input() {
         Token {
             data: TokenData::Keyword,
             text: Cow::Borrowed("let"),
-            location: TextLocation {
-                index: 0,
-                line: 0,
-                column: 0
+            span: Span {
+                start: TextLocation { index: 0, line: 0, column: 0 },
+                end: TextLocation { index: 3, line: 0, column: 3 }
             }
         },
         Token {
             data: TokenData::Ident,
             text: Cow::Borrowed("x"),
-            location: TextLocation {
-                index: 4,
-                line: 0,
-                column: 4
+            span: Span {
+                start: TextLocation { index: 4, line: 0, column: 4 },
+                end: TextLocation { index: 5, line: 0, column: 5 }
             }
         },
         Token {
             data: TokenData::Symbol,
             text: Cow::Borrowed("="),
-            location: TextLocation {
-                index: 6,
-                line: 0,
-                column: 6
+            span: Span {
+                start: TextLocation { index: 6, line: 0, column: 6 },
+                end: TextLocation { index: 7, line: 0, column: 7 }
             }
         },
         Token {
             data: TokenData::Ident,
             text: Cow::Borrowed("y"),
-            location: TextLocation {
-                index: 8,
-                line: 0,
-                column: 8
+            span: Span {
+                start: TextLocation { index: 8, line: 0, column: 8 },
+                end: TextLocation { index: 9, line: 0, column: 9 }
             }
         },
         Token {
             data: TokenData::Ident,
             text: Cow::Borrowed("y"),
-            location: TextLocation {
-                index: 10,
-                line: 0,
-                column: 10
+            span: Span {
+                start: TextLocation { index: 10, line: 0, column: 10 },
+                end: TextLocation { index: 11, line: 0, column: 11 }
             }
         },
         Token {
             data: TokenData::Symbol,
             text: Cow::Borrowed("+="),
-            location: TextLocation {
-                index: 12,
-                line: 0,
-                column: 12
+            span: Span {
+                start: TextLocation { index: 12, line: 0, column: 12 },
+                end: TextLocation { index: 14, line: 0, column: 14 }
             }
         },
         Token {
             data: TokenData::NumberLiteral(55e7f64),
             text: Cow::Borrowed("55e7"),
-            location: TextLocation {
-                index: 15,
-                line: 0,
-                column: 15
+            span: Span {
+                start: TextLocation { index: 15, line: 0, column: 15 },
+                end: TextLocation { index: 19, line: 0, column: 19 }
             }
         },
         Token {
             data: TokenData::Keyword,
             text: Cow::Borrowed("return"),
-            location: TextLocation {
-                index: 22,
-                line: 1,
-                column: 0
+            span: Span {
+                start: TextLocation { index: 22, line: 1, column: 0 },
+                end: TextLocation { index: 28, line: 1, column: 6 }
             }
         },
         Token {
             data: TokenData::Ident,
             text: Cow::Borrowed("y"),
-            location: TextLocation {
-                index: 29,
-                line: 1,
-                column: 7
+            span: Span {
+                start: TextLocation { index: 29, line: 1, column: 7 },
+                end: TextLocation { index: 30, line: 1, column: 8 }
             }
         },
         Token {
             data: TokenData::Symbol,
             text: Cow::Borrowed("%"),
-            location: TextLocation {
-                index: 31,
-                line: 1,
-                column: 9
+            span: Span {
+                start: TextLocation { index: 31, line: 1, column: 9 },
+                end: TextLocation { index: 32, line: 1, column: 10 }
             }
         },
         Token {
             data: TokenData::Ident,
             text: Cow::Borrowed("x"),
-            location: TextLocation {
-                index: 33,
-                line: 1,
-                column: 11
+            span: Span {
+                start: TextLocation { index: 33, line: 1, column: 11 },
+                end: TextLocation { index: 34, line: 1, column: 12 }
             }
         },
         Token {
             data: TokenData::Symbol,
             text: Cow::Borrowed("+"),
-            location: TextLocation {
-                index: 35,
-                line: 1,
-                column: 13
+            span: Span {
+                start: TextLocation { index: 35, line: 1, column: 13 },
+                end: TextLocation { index: 36, line: 1, column: 14 }
             }
         },
         Token {
             data: TokenData::NumberLiteral(224.5f64),
             text: Cow::Borrowed("224.5"),
-            location: TextLocation {
-                index: 37,
-                line: 1,
-                column: 15
+            span: Span {
+                start: TextLocation { index: 37, line: 1, column: 15 },
+                end: TextLocation { index: 42, line: 1, column: 20 }
+            }
+        },
+        Token {
+            data: TokenData::EOF,
+            text: Cow::Borrowed(""),
+            span: Span {
+                start: TextLocation { index: 42, line: 1, column: 20 },
+                end: TextLocation { index: 42, line: 1, column: 20 }
+            }
+        }
+    });
+}
+
+#[test]
+fn it_reports_unknown_character() {
+    let input = "x ? y";
+    let mut tokenizer = make_tokenizer(input);
+    match_tokens!(tokenizer {
+        Token {
+            data: TokenData::Ident,
+            text: Cow::Borrowed("x"),
+            span: Span {
+                start: TextLocation { index: 0, line: 0, column: 0 },
+                end: TextLocation { index: 1, line: 0, column: 1 }
+            }
+        },
+        Token {
+            data: TokenData::Error(Cow::Borrowed("Unknown character `?`")),
+            text: Cow::Borrowed("?"),
+            span: Span {
+                start: TextLocation { index: 2, line: 0, column: 2 },
+                end: TextLocation { index: 3, line: 0, column: 3 }
+            }
+        },
+        Token {
+            data: TokenData::Ident,
+            text: Cow::Borrowed("y"),
+            span: Span {
+                start: TextLocation { index: 4, line: 0, column: 4 },
+                end: TextLocation { index: 5, line: 0, column: 5 }
             }
         },
         Token {
             data: TokenData::EOF,
             text: Cow::Borrowed(""),
-            location: TextLocation {
-                index: 42,
-                line: 1,
-                column: 20
+            span: Span {
+                start: TextLocation { index: 5, line: 0, column: 5 },
+                end: TextLocation { index: 5, line: 0, column: 5 }
+            }
+        }
+    });
+}
+
+#[test]
+fn it_reports_unknown_symbol() {
+    let input = "$x";
+    let mut tokenizer = make_tokenizer(input);
+    match_tokens!(tokenizer {
+        Token {
+            data: TokenData::Error(Cow::Borrowed("Unknown symbol `$`")),
+            text: Cow::Borrowed("$"),
+            span: Span {
+                start: TextLocation { index: 0, line: 0, column: 0 },
+                end: TextLocation { index: 1, line: 0, column: 1 }
+            }
+        },
+        Token {
+            data: TokenData::Ident,
+            text: Cow::Borrowed("x"),
+            span: Span {
+                start: TextLocation { index: 1, line: 0, column: 1 },
+                end: TextLocation { index: 2, line: 0, column: 2 }
+            }
+        },
+        Token {
+            data: TokenData::EOF,
+            text: Cow::Borrowed(""),
+            span: Span {
+                start: TextLocation { index: 2, line: 0, column: 2 },
+                end: TextLocation { index: 2, line: 0, column: 2 }
+            }
+        }
+    });
+}
+
+#[test]
+fn it_reports_hanging_carriage_return() {
+    let input = "x\r";
+    let mut tokenizer = make_tokenizer(input);
+    match_tokens!(tokenizer {
+        Token {
+            data: TokenData::Ident,
+            text: Cow::Borrowed("x"),
+            span: Span {
+                start: TextLocation { index: 0, line: 0, column: 0 },
+                end: TextLocation { index: 1, line: 0, column: 1 }
+            }
+        },
+        Token {
+            data: TokenData::Error(Cow::Borrowed("Hanging `\\r` at EOF")),
+            text: Cow::Borrowed("\r"),
+            span: Span {
+                start: TextLocation { index: 1, line: 0, column: 1 },
+                end: TextLocation { index: 2, line: 0, column: 2 }
+            }
+        },
+        Token {
+            data: TokenData::EOF,
+            text: Cow::Borrowed(""),
+            span: Span {
+                start: TextLocation { index: 2, line: 0, column: 2 },
+                end: TextLocation { index: 2, line: 0, column: 2 }
+            }
+        }
+    });
+}
+
+#[test]
+fn it_reports_invalid_control_sequence() {
+    let input = "x\r\ty";
+    let mut tokenizer = make_tokenizer(input);
+    match_tokens!(tokenizer {
+        Token {
+            data: TokenData::Ident,
+            text: Cow::Borrowed("x"),
+            span: Span {
+                start: TextLocation { index: 0, line: 0, column: 0 },
+                end: TextLocation { index: 1, line: 0, column: 1 }
+            }
+        },
+        Token {
+            data: TokenData::Error(Cow::Borrowed("Invalid control sequence `\\r\\t`")),
+            text: Cow::Borrowed("\r"),
+            span: Span {
+                start: TextLocation { index: 1, line: 0, column: 1 },
+                end: TextLocation { index: 2, line: 0, column: 2 }
+            }
+        },
+        Token {
+            data: TokenData::Ident,
+            text: Cow::Borrowed("y"),
+            span: Span {
+                start: TextLocation { index: 3, line: 0, column: 3 },
+                end: TextLocation { index: 4, line: 0, column: 4 }
+            }
+        }
+    });
+}
+
+#[test]
+fn it_reports_invalid_float_literal() {
+    let input = "2e";
+    let mut tokenizer = make_tokenizer(input);
+    match_tokens!(tokenizer {
+        Token {
+            data: TokenData::Error(Cow::Borrowed("Invalid number literal `2e`")),
+            text: Cow::Borrowed("2e"),
+            span: Span {
+                start: TextLocation { index: 0, line: 0, column: 0 },
+                end: TextLocation { index: 2, line: 0, column: 2 }
+            }
+        },
+        Token {
+            data: TokenData::EOF,
+            text: Cow::Borrowed(""),
+            span: Span {
+                start: TextLocation { index: 2, line: 0, column: 2 },
+                end: TextLocation { index: 2, line: 0, column: 2 }
+            }
+        }
+    });
+}
+
+#[test]
+fn it_continues_from_a_starting_location() {
+    let start = TextLocation { index: 20, line: 3, column: 0 };
+    let mut tokenizer = IterTokenizer::starting_at("x\ny".chars(), start);
+    match_tokens!(tokenizer {
+        Token {
+            data: TokenData::Ident,
+            text: Cow::Borrowed("x"),
+            span: Span {
+                start: TextLocation { index: 20, line: 3, column: 0 },
+                end: TextLocation { index: 21, line: 3, column: 1 }
+            }
+        },
+        Token {
+            data: TokenData::Ident,
+            text: Cow::Borrowed("y"),
+            span: Span {
+                start: TextLocation { index: 22, line: 4, column: 0 },
+                end: TextLocation { index: 23, line: 4, column: 1 }
             }
         }
     });
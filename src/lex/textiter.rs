@@ -10,10 +10,47 @@ pub struct TextLocation {
     pub column: usize
 }
 
+/// Structure representing the extent of some text, such as a token or an
+/// expression.
+///
+/// `end` is the location just after the last character.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash, Default)]
+pub struct Span {
+    pub start: TextLocation,
+    pub end: TextLocation
+}
+impl Span {
+    pub fn new(start: TextLocation, end: TextLocation) -> Span {
+        Span { start: start, end: end }
+    }
+    /// Create an empty span at the given location.
+    pub fn at(location: TextLocation) -> Span {
+        Span::new(location, location)
+    }
+    /// Extend this span to the end of `other`, if `other` ends after it.
+    pub fn to(&self, other: Span) -> Span {
+        if other.end.index > self.end.index {
+            Span::new(self.start, other.end)
+        }
+        else {
+            *self
+        }
+    }
+    /// Number of characters in the span
+    pub fn len(&self) -> usize {
+        self.end.index - self.start.index
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
 /// A specialized iterator (for tokenizing) which also implements `peek()`
 /// and keeps track of its location.
 pub trait TextIter : Iterator {
     fn peek(&mut self) -> Option<char>;
+    /// Look at the character after the one given by `peek()`.
+    fn peek_second(&mut self) -> Option<char>;
     fn get_location(&self) -> TextLocation;
 }
 
@@ -22,6 +59,8 @@ pub trait TextIter : Iterator {
 pub struct PeekTextIter<T> where T: Iterator<Item=char> {
     /// Iterator which does most of the work
     iter: Peekable<T>,
+    /// Character taken from `iter` in order to peek past it
+    taken: Option<char>,
     /// Current line in the source
     current_line: usize,
     /// Current column in the source
@@ -33,15 +72,39 @@ impl<T: Iterator<Item=char>> PeekTextIter<T> {
     pub fn new(iter: Peekable<T>) -> PeekTextIter<T> {
         PeekTextIter {
             iter: iter,
+            taken: None,
             current_line: 0,
             current_column: 0,
             current_char: 0
         }
     }
+    /// Create a `PeekTextIter` whose first character is at `location`,
+    /// for text which continues an earlier source.
+    pub fn starting_at(iter: Peekable<T>, location: TextLocation) -> PeekTextIter<T> {
+        PeekTextIter {
+            iter: iter,
+            taken: None,
+            current_line: location.line,
+            current_column: location.column,
+            current_char: location.index
+        }
+    }
 }
 
 impl<T: Iterator<Item=char>> TextIter for PeekTextIter<T> {
     fn peek(&mut self) -> Option<char> {
+        if self.taken.is_some() {
+            return self.taken
+        }
+        self.iter.peek().cloned()
+    }
+    fn peek_second(&mut self) -> Option<char> {
+        if self.taken.is_none() {
+            self.taken = self.iter.next();
+            if self.taken.is_none() {
+                return None
+            }
+        }
         self.iter.peek().cloned()
     }
     fn get_location(&self) -> TextLocation {
@@ -57,7 +120,10 @@ impl<T: Iterator<Item=char>> Iterator for PeekTextIter<T> {
     type Item = char;
 
     fn next(&mut self) -> Option<char> {
-        let result = self.iter.next();
+        let result = match self.taken.take() {
+            Some(taken) => Some(taken),
+            None => self.iter.next()
+        };
         self.current_char = self.current_char.saturating_add(1);
         match result {
             Some('\n') => {
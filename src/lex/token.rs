@@ -7,22 +7,29 @@ use std::fmt::{Display, Formatter};
 use std::fmt::Result as FmtResult;
 use std::ops::Range;
 
-use lex::{TextLocation, CowStr};
+use lex::{TextLocation, Span, CowStr};
 
 /// A token returned by the tokenizer.
 ///
 /// Each token has a definite
 #[derive(Debug, PartialEq, Clone, Default)]
 pub struct Token {
-    /// Location of the token in a file
-    pub location: TextLocation,
-    /// Text of the token at that location
+    /// Extent of the token in a file
+    pub span: Span,
+    /// Text of the token at that span
     pub text: CowStr,
     /// Additional data (type/literal) provided by the lexer
     pub data: TokenData
 }
 
 impl Token {
+    /// Get the location of the start of the token
+    pub fn get_location(&self) -> TextLocation {
+        self.span.start
+    }
+    pub fn get_span(&self) -> Span {
+        self.span
+    }
     pub fn get_text(&self) -> &str {
         &self.text
     }
@@ -34,7 +41,7 @@ impl Token {
         Token {
             text: text.into(),
             data: TokenData::Symbol,
-            location: location
+            span: Span::at(location)
         }
     }
     #[inline]
@@ -42,7 +49,7 @@ impl Token {
         Token {
             text: text.into(),
             data: TokenData::Keyword,
-            location: location
+            span: Span::at(location)
         }
     }
     #[inline]
@@ -50,7 +57,7 @@ impl Token {
         Token {
             text: text.into(),
             data: TokenData::Ident,
-            location: location
+            span: Span::at(location)
         }
     }
 
@@ -59,7 +66,7 @@ impl Token {
         Token {
             text: Cow::Borrowed(""),
             data: TokenData::BeginBock,
-            location: location
+            span: Span::at(location)
         }
     }
 
@@ -68,7 +75,7 @@ impl Token {
         Token {
             text: Cow::Borrowed(""),
             data: TokenData::EndBlock,
-            location: location
+            span: Span::at(location)
         }
     }
 
@@ -77,7 +84,18 @@ impl Token {
         Token {
             text: Cow::Borrowed(""),
             data: TokenData::EOF,
-            location: location
+            span: Span::at(location)
+        }
+    }
+
+    #[inline]
+    pub fn new_error<T: Into<CowStr>, M: Into<CowStr>>(text: T,
+                                                       location: TextLocation,
+                                                       message: M) -> Token {
+        Token {
+            text: text.into(),
+            data: TokenData::Error(message.into()),
+            span: Span::at(location)
         }
     }
 }
@@ -93,8 +111,14 @@ impl Eq for Token { }
 /// Token enum - tokens are pretty simple, mostly dependent on string matching.
 #[derive(Debug, Clone, PartialEq)]
 pub enum TokenData {
-    /// Token is a numeric literal
+    /// Token is a floating point literal
     NumberLiteral(f64),
+    /// Token is an integer literal
+    IntLiteral(i64),
+    /// Token is a string literal, with escape sequences applied
+    StringLiteral(String),
+    /// Token is a `true` or `false` literal
+    BoolLiteral(bool),
 
     /// Token is some name
     Ident,
@@ -106,6 +130,8 @@ pub enum TokenData {
     BeginBock,
     /// Outdendation of block
     EndBlock,
+    /// Text which could not be tokenized, with a description of the problem
+    Error(CowStr),
     /// Token is an EOF
     EOF
 }
@@ -116,11 +142,15 @@ impl TokenData {
         use self::TokenData::*;
         match *self {
             NumberLiteral(_) => TokenType::Literal,
+            IntLiteral(_) => TokenType::Literal,
+            StringLiteral(_) => TokenType::Literal,
+            BoolLiteral(_) => TokenType::Literal,
             Ident => TokenType::Ident,
             Keyword => TokenType::Keyword,
             Symbol => TokenType::Symbol,
             BeginBock => TokenType::BeginBlock,
             EndBlock => TokenType::EndBlock,
+            Error(_) => TokenType::Error,
             EOF => TokenType::EOF
         }
     }
@@ -149,6 +179,8 @@ pub enum TokenType {
     /// Token is a begin/end block
     BeginBlock,
     EndBlock,
+    /// Token could not be lexed
+    Error,
     /// Token is an EOF
     EOF
 }
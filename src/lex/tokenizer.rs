@@ -10,7 +10,7 @@ use std::str::Chars;
 
 use unicode_categories::UnicodeCategories;
 
-use lex::{tokens, TextLocation,
+use lex::{tokens, TextLocation, Span,
           TokenizerSymbolRule, CowStr,
           Token, TokenData, TokenType,
           TextIter, PeekTextIter};
@@ -26,7 +26,7 @@ pub fn char_is_symbol(ch: char) -> bool {
     ch == '(' || ch == ')' ||
     ch == '-' || ch == '*' ||
     ch == ',' || ch == ':' ||
-    ch == '!' ||
+    ch == '!' || ch == '.' ||
     ch.is_symbol()
 }
 
@@ -99,11 +99,25 @@ impl<I: Iterator<Item=char>> IterTokenizer<I> {
         }
     }
 
+    /// Creates a tokenizer for text which continues an earlier source,
+    /// such as an entry in a REPL, whose first character is at `location`.
+    pub fn starting_at(input: I, location: TextLocation) -> IterTokenizer<I> {
+        IterTokenizer {
+            keywords: tokens::default_keywords(),
+            symbols: tokens::default_symbols(),
+            expected_indent_length: 0usize,
+            expected_indent_spaces: true,
+            tokenizer_state: TokenizerState::LookingForNewline,
+            indent_size_stack: vec![0usize],
+            iter: PeekTextIter::starting_at(input.peekable(), location)
+        }
+    }
+
     /// Gets the next token from the tokenizer
     pub fn next(&mut self) -> Token {
         trace!(">Calling next on {:?}, peeked {:?}",
             self.tokenizer_state, self.iter.peek());
-        match self.tokenizer_state {
+        let mut token = match self.tokenizer_state {
             TokenizerState::LookingForIndent =>
                 self.next_indent(),
             TokenizerState::LookingForNewline =>
@@ -112,7 +126,13 @@ impl<I: Iterator<Item=char>> IterTokenizer<I> {
                 self.next_eof(),
             TokenizerState::EmittingOutdents =>
                 self.next_outdent()
+        };
+        // Tokens are created at their start. The text of the token has been
+        // consumed now, so it ends here. Indentation and EOF tokens are empty.
+        if !token.text.is_empty() {
+            token.span.end = self.iter.get_location();
         }
+        token
     }
 
     /// Emit remaining `BlockEnd` and `EOF` tokens
@@ -255,17 +275,19 @@ impl<I: Iterator<Item=char>> IterTokenizer<I> {
         // We handle \r first, then look at the following \n.
         // TODO warn on mixed \r\n and \n
         if peek == '\r' {
+            let location = self.iter.get_location();
             self.iter.next(); // comsume \r
             // Give an error for \r at EOF
             if self.iter.peek().is_none() {
-                // TODO error here
-                panic!("Hanging `\\r` at EOF, {:?}", self.iter.get_location());
+                return Token::new_error("\r", location, "Hanging `\\r` at EOF")
             }
             // Peek for the \n
             let expected_newline = self.iter.peek().expect("Already peeked");
             if expected_newline != '\n' {
-                // TODO need to format i.e. `\t` -> `\\t` here...
-                panic!("Invalid control sequence `\\r{}`", expected_newline);
+                // The char after the `\r` is left to be tokenized normally.
+                let message = format!("Invalid control sequence `\\r{}`",
+                    expected_newline.escape_default());
+                return Token::new_error("\r", location, message)
             }
             peek = expected_newline; // peeked \n here
         }
@@ -278,13 +300,18 @@ impl<I: Iterator<Item=char>> IterTokenizer<I> {
             self.next_indent() // Mutually recursive for emtpy lines
         }
         else if peek.is_number() {
-            self.parse_float_literal()
+            self.parse_number_literal()
+        } else if peek == '"' {
+            self.parse_string_literal()
         } else if peek == '_' || peek.is_letter() {
             self.parse_keyword_or_ident()
         } else if char_is_symbol(peek) {
             self.parse_symbol()
         } else {
-            panic!("Unknown character `{:?}` in next_line", peek);
+            let location = self.iter.get_location();
+            self.iter.next();
+            let message = format!("Unknown character `{}`", peek.escape_default());
+            Token::new_error(peek.to_string(), location, message)
         }
     }
 
@@ -321,8 +348,11 @@ impl<I: Iterator<Item=char>> IterTokenizer<I> {
             match symbol_type {
                 // No symbol matched - we started out bad or peeked too far
                 None => {
-                    if sym.len() == 1 {
-                        panic!("Couldn't find symbol {:?}", sym);
+                    if sym.chars().count() == 1 {
+                        // Skip the unknown symbol so tokenizing can continue
+                        self.iter.next();
+                        let message = format!("Unknown symbol `{}`", sym);
+                        return Token::new_error(sym, location, message)
                     } else {
                         sym.pop();
                         match self.symbols.get(&Cow::Borrowed(&*sym)).cloned() {
@@ -334,7 +364,8 @@ impl<I: Iterator<Item=char>> IterTokenizer<I> {
                             },
                             // We stepped past a partial token but did not complete it
                             Some(Partial) => {
-                                panic!("Could not complete partial token {:?}", sym);
+                                let message = format!("Could not complete symbol `{}`", sym);
+                                return Token::new_error(sym, location, message)
                             }
                         }
                     }
@@ -361,62 +392,178 @@ impl<I: Iterator<Item=char>> IterTokenizer<I> {
         let location = self.iter.get_location();
         let is_kw = self.take_while_ident(&mut token_string);
         if is_kw && self.keywords.get(&Cow::Borrowed(&*token_string)).is_some() {
+            // `true` and `false` are reserved, but are given to the parser as literals
+            if token_string == tokens::True || token_string == tokens::False {
+                let value = token_string == tokens::True;
+                return Token {
+                    span: Span::at(location),
+                    text: Cow::Owned(token_string),
+                    data: TokenData::BoolLiteral(value)
+                }
+            }
             Token::new_keyword(token_string, location)
         } else {
             Token::new_ident(token_string, location)
         }
     }
 
-    /// Parse a floating point literal
-    fn parse_float_literal(&mut self) -> Token {
+    /// Parse a number literal.
+    ///
+    /// Numbers without a fraction or an exponent are integers.
+    fn parse_number_literal(&mut self) -> Token {
         let mut token_string = String::new();
         let location = self.iter.get_location();
         self.take_while(char::is_number, &mut token_string);
+        let next = self.iter.peek().unwrap_or(' ');
+        let is_integer = (next != '.' || self.iter.peek_second() == Some('.'))
+            && next.to_lowercase().collect::<String>() != "e";
+        if is_integer {
+            return self.int_literal_token(token_string, location)
+        }
         // First part of number done. Is it a decimal?
-        if self.iter.peek().unwrap_or(' ') == '.' {
+        // `0..n` is a range, so don't take a `.` followed by another.
+        if self.iter.peek().unwrap_or(' ') == '.' && self.iter.peek_second() != Some('.') {
             // Push the decmial point
             token_string.push(self.iter.next().expect("Checked expect"));
             if !self.iter.peek().unwrap_or(' ').is_number() {
                 // Actually, let's not
                 token_string.pop();
-                let parsed: f64 = token_string.parse()
-                    .expect("Couldn't parse float");
-                return Token {
-                    location: location,
-                    text: Cow::Owned(token_string),
-                    data: TokenData::NumberLiteral(parsed)
-                }
+                return self.float_literal_token(token_string, location)
             }
             self.take_while(char::is_number, &mut token_string);
         }
         if self.iter.peek().unwrap_or(' ').to_lowercase().collect::<String>() != "e" {
-            let parsed: f64 = token_string.parse()
-                .expect("Couldn't parse float");
-            return Token {
-                location: location,
-                text: Cow::Owned(token_string),
-                data: TokenData::NumberLiteral(parsed)
-            }
+            return self.float_literal_token(token_string, location)
         }
         token_string.push(self.iter.next().expect("Checked expect"));
         // Need numbers after the E
         if !self.iter.peek().unwrap_or(' ').is_number() {
-            let parsed: f64 = token_string.parse()
-                .expect("Couldn't parse float");
-            return Token {
-                location: location,
+            return self.float_literal_token(token_string, location)
+        }
+        self.take_while(char::is_number, &mut token_string);
+        self.float_literal_token(token_string, location)
+    }
+
+    /// Create an `IntLiteral` token from the given text, or an error
+    /// token if the number doesn't fit in an `i64`.
+    fn int_literal_token(&self, token_string: String, location: TextLocation) -> Token {
+        match token_string.parse::<i64>() {
+            Ok(parsed) => Token {
+                span: Span::at(location),
+                text: Cow::Owned(token_string),
+                data: TokenData::IntLiteral(parsed)
+            },
+            Err(_) => {
+                let message = format!("Invalid number literal `{}`", token_string);
+                Token::new_error(token_string, location, message)
+            }
+        }
+    }
+
+    /// Create a `NumberLiteral` token from the given text, or an error
+    /// token if the text is not a valid float.
+    fn float_literal_token(&self, token_string: String, location: TextLocation) -> Token {
+        match token_string.parse::<f64>() {
+            Ok(parsed) => Token {
+                span: Span::at(location),
                 text: Cow::Owned(token_string),
                 data: TokenData::NumberLiteral(parsed)
+            },
+            Err(_) => {
+                let message = format!("Invalid number literal `{}`", token_string);
+                Token::new_error(token_string, location, message)
             }
         }
-        self.take_while(char::is_number, &mut token_string);
-        let parsed: f64 = token_string.parse()
-            .expect("Couldn't parse float");
-        return Token {
-            location: location,
-            text: Cow::Owned(token_string),
-            data: TokenData::NumberLiteral(parsed)
+    }
+
+    /// Parse a double-quoted string literal
+    ///
+    /// Supports the escape sequences `\n`, `\r`, `\t`, `\0`, `\\`, `\"`
+    /// and `\u{...}` with up to six hex digits. String literals cannot
+    /// span multiple lines.
+    fn parse_string_literal(&mut self) -> Token {
+        let location = self.iter.get_location();
+        let mut token_string = String::new();
+        let mut value = String::new();
+        // Report the first bad escape after the whole string has been consumed
+        let mut error: Option<String> = None;
+        token_string.push(self.iter.next().expect("Checked expect"));
+        loop {
+            let next = match self.iter.peek() {
+                Some(ch) if ch != '\r' && ch != '\n' => ch,
+                _ => {
+                    let message = "Unterminated string literal";
+                    return Token::new_error(token_string, location, message)
+                }
+            };
+            self.iter.next();
+            token_string.push(next);
+            match next {
+                '"' => break,
+                '\\' => {
+                    let escape = match self.iter.peek() {
+                        Some(ch) if ch != '\r' && ch != '\n' => ch,
+                        _ => {
+                            let message = "Unterminated string literal";
+                            return Token::new_error(token_string, location, message)
+                        }
+                    };
+                    self.iter.next();
+                    token_string.push(escape);
+                    match escape {
+                        'n' => value.push('\n'),
+                        'r' => value.push('\r'),
+                        't' => value.push('\t'),
+                        '0' => value.push('\0'),
+                        '\\' => value.push('\\'),
+                        '"' => value.push('"'),
+                        'u' => match self.parse_unicode_escape(&mut token_string) {
+                            Ok(ch) => value.push(ch),
+                            Err(message) => { error = error.or(Some(message)); }
+                        },
+                        other => {
+                            let message = format!("Unknown escape sequence `\\{}`",
+                                other.escape_default());
+                            error = error.or(Some(message));
+                        }
+                    }
+                },
+                other => value.push(other)
+            }
+        }
+        match error {
+            Some(message) => Token::new_error(token_string, location, message),
+            None => Token {
+                span: Span::at(location),
+                text: Cow::Owned(token_string),
+                data: TokenData::StringLiteral(value)
+            }
+        }
+    }
+
+    /// Parse the `{XXXX}` part of a `\u{XXXX}` escape sequence.
+    ///
+    /// Stops at the closing brace, a quote, or the end of the line
+    /// so the rest of the string literal can still be tokenized.
+    fn parse_unicode_escape(&mut self, acc: &mut String) -> Result<char, String> {
+        if self.iter.peek() != Some('{') {
+            return Err("Expected `{` after `\\u`".to_string())
+        }
+        acc.push(self.iter.next().expect("Checked expect"));
+        let mut digits = String::new();
+        self.take_while(|ch| ch.is_digit(16), &mut digits);
+        acc.push_str(&digits);
+        if self.iter.peek() != Some('}') {
+            return Err(format!("Expected `}}` to close `\\u{{{}`", digits))
+        }
+        acc.push(self.iter.next().expect("Checked expect"));
+        if digits.is_empty() || digits.len() > 6 {
+            return Err(format!("Invalid unicode escape `\\u{{{}}}`", digits))
         }
+        let code = u32::from_str_radix(&digits, 16)
+            .expect("Checked hex digits");
+        ::std::char::from_u32(code).ok_or_else(||
+            format!("Invalid unicode character `\\u{{{}}}`", digits))
     }
 
     /// Continue taking characters while a condition is met
@@ -66,6 +66,8 @@ declare_tokens! {
         Arrow: "->"; Complete,
         Comma: ","; Complete,
         Colon: ":"; Complete,
+        DoubleDot: ".."; CompletePrefix,
+        DoubleDotEquals: "..="; Complete,
     }
     symparts {
         "//"; CompletePrefix, // Comments hack, allows // and /// to be parsed.
@@ -74,6 +76,7 @@ declare_tokens! {
         "<<<<"; Partial,
         "<<<<<"; Partial,
         "!"; Partial,
+        "."; Partial,
     }
     keywords {
         Let: "let",
@@ -83,5 +86,17 @@ declare_tokens! {
         If: "if",
         Else: "else",
         Fn: "fn",
+        True: "true",
+        False: "false",
+        And: "and",
+        Or: "or",
+        Not: "not",
+        While: "while",
+        Loop: "loop",
+        Break: "break",
+        Continue: "continue",
+        For: "for",
+        In: "in",
+        By: "by",
     }
 }
@@ -9,11 +9,14 @@ extern crate maplit;
 extern crate unicode_categories;
 extern crate iron_llvm;
 extern crate llvm_sys;
+#[cfg(test)]
+extern crate wasmi;
 
 pub mod lex;
 pub mod parse;
 pub mod compile;
 pub mod run;
+pub mod diagnostics;
 
 #[cfg(test)]
 mod tests;
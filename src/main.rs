@@ -0,0 +1,473 @@
+//! Command line driver for protosnirk.
+//!
+//! ```text
+//! protosnirk check [--error-format=human|json] <file>
+//! protosnirk run [--error-format=human|json] [-O0|-O1|-O2|-O3|-Os] <file>
+//! protosnirk build [--error-format=human|json] [-O0|-O1|-O2|-O3|-Os]
+//!                  [--emit=exe|obj|llvm-ir|llvm-bc]
+//!                  [--target=<triple>] [--target-cpu=<cpu>] [--target-features=<features>]
+//!                  [-o <output>] <file>
+//! protosnirk repl
+//! protosnirk --explain <code>
+//! ```
+//!
+//! `check` reports any errors and warnings in a file, `run` JIT compiles it
+//! and prints the result of its `main` function, and `build` compiles it to
+//! `<output>`: an executable which prints the result of `main`, an object
+//! file, or LLVM IR as text or bitcode. The output defaults to the file
+//! name with the extension of what's built. `build` compiles for the
+//! machine it's run on unless a `--target` triple is given; when linking for
+//! another target, `$CC` should name a linker for it. For
+//! `wasm32-unknown-unknown`, `build` links a WebAssembly module with
+//! `wasm-ld` which exports each function instead of running `main`. `run`
+//! and `build` optimize at the `-O` level given, or not at all by default.
+//! `repl` starts an interactive prompt.
+
+extern crate protosnirk;
+
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufRead, Read, Write};
+use std::path::Path;
+use std::process;
+
+use protosnirk::lex::IterTokenizer;
+use protosnirk::parse::{ASTVisitor, Parser, SymbolTable, FnType};
+use protosnirk::parse::ast::{Item, Unit};
+use protosnirk::compile::{ModuleCompiler, SimpleModuleProvider, Emitter, EmitError, OptLevel};
+use protosnirk::compile::{add_entry_point, default_triple, verify_module, wasm_exports};
+use protosnirk::compile::{write_ir, write_bitcode};
+use protosnirk::run::{LLVMJIT, MCJIT, Value, CallError, Repl, needs_more_input};
+use protosnirk::diagnostics::{Diagnostic, ErrorCode, JsonEmitter, Renderer, Severity};
+
+
+const USAGE: &'static str = "\
+Usage: protosnirk check [--error-format=human|json] <file>
+       protosnirk run [--error-format=human|json] [-O0|-O1|-O2|-O3|-Os] <file>
+       protosnirk build [--error-format=human|json] [-O0|-O1|-O2|-O3|-Os]
+                        [--emit=exe|obj|llvm-ir|llvm-bc]
+                        [--target=<triple>] [--target-cpu=<cpu>] [--target-features=<features>]
+                        [-o <output>] <file>
+       protosnirk repl
+       protosnirk --explain <code>";
+
+/// The command succeeded
+const EXIT_SUCCESS: i32 = 0;
+/// The file had errors, or couldn't be run
+const EXIT_ERRORS: i32 = 1;
+/// The driver was used wrongly, or a file couldn't be read or written
+const EXIT_USAGE: i32 = 2;
+
+/// How diagnostics are written out
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum ErrorFormat {
+    /// Rendered with snippets of the source, for people
+    Human,
+    /// One JSON object per line, for editors and other tools
+    Json
+}
+impl ErrorFormat {
+    fn from_name(name: &str) -> Option<ErrorFormat> {
+        match name {
+            "human" => Some(ErrorFormat::Human),
+            "json" => Some(ErrorFormat::Json),
+            _ => None
+        }
+    }
+}
+
+/// What `build` writes out
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Emit {
+    /// An executable which prints the result of `main`
+    Exe,
+    /// An object file
+    Obj,
+    /// Textual LLVM IR
+    LlvmIr,
+    /// LLVM bitcode
+    LlvmBc
+}
+impl Emit {
+    fn from_name(name: &str) -> Option<Emit> {
+        match name {
+            "exe" => Some(Emit::Exe),
+            "obj" => Some(Emit::Obj),
+            "llvm-ir" => Some(Emit::LlvmIr),
+            "llvm-bc" => Some(Emit::LlvmBc),
+            _ => None
+        }
+    }
+    /// Get the extension of the file which is written by default
+    fn extension(self) -> &'static str {
+        match self {
+            Emit::Exe => "",
+            Emit::Obj => "o",
+            Emit::LlvmIr => "ll",
+            Emit::LlvmBc => "bc"
+        }
+    }
+}
+
+/// The machine `build` compiles for
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct Target {
+    /// The target triple, or `None` for the machine the driver is run on
+    triple: Option<String>,
+    cpu: String,
+    features: String
+}
+impl Target {
+    fn native() -> Target {
+        Target { triple: None, cpu: String::new(), features: String::new() }
+    }
+
+    fn is_wasm(&self) -> bool {
+        self.triple.as_ref().map_or(false, |triple| triple.starts_with("wasm32"))
+    }
+
+    fn emitter(&self, level: OptLevel) -> Result<Emitter, EmitError> {
+        let triple = self.triple.clone().unwrap_or_else(default_triple);
+        Emitter::new(&triple, &self.cpu, &self.features, level)
+    }
+}
+
+/// What the driver was asked to do with a file
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Command {
+    Check,
+    Run,
+    Build,
+    Repl
+}
+impl Command {
+    fn from_name(name: &str) -> Option<Command> {
+        match name {
+            "check" => Some(Command::Check),
+            "run" => Some(Command::Run),
+            "build" => Some(Command::Build),
+            "repl" => Some(Command::Repl),
+            _ => None
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    process::exit(run(&args));
+}
+
+/// Run the driver with the given arguments, returning the exit code.
+fn run(args: &[String]) -> i32 {
+    let mut args = args.iter();
+    let command = match args.next().map(|arg| &**arg) {
+        Some("--explain") => return explain(args.next().map(|code| &**code)),
+        Some(name) => match Command::from_name(name) {
+            Some(command) => command,
+            None => return usage(&format!("Unknown command {}", name))
+        },
+        None => return usage("No command given")
+    };
+    if command == Command::Repl {
+        return match args.next() {
+            Some(arg) => usage(&format!("Unexpected argument {}", arg)),
+            None => repl()
+        }
+    }
+    let mut format = ErrorFormat::Human;
+    let mut file_name = None;
+    let mut output = None;
+    let mut emit = Emit::Exe;
+    let mut target = Target::native();
+    let mut level = OptLevel::O0;
+    while let Some(arg) = args.next() {
+        if arg.starts_with("--error-format=") {
+            match ErrorFormat::from_name(&arg["--error-format=".len()..]) {
+                Some(found) => format = found,
+                None => return usage(&format!("Unknown error format {}", arg))
+            }
+        }
+        else if arg.starts_with("-O") && command != Command::Check {
+            match OptLevel::from_name(&arg["-O".len()..]) {
+                Some(found) => level = found,
+                None => return usage(&format!("Unknown optimization level {}", arg))
+            }
+        }
+        else if arg.starts_with("--emit=") && command == Command::Build {
+            match Emit::from_name(&arg["--emit=".len()..]) {
+                Some(found) => emit = found,
+                None => return usage(&format!("Unknown output kind {}", arg))
+            }
+        }
+        else if arg.starts_with("--target=") && command == Command::Build {
+            target.triple = Some(arg["--target=".len()..].to_string());
+        }
+        else if arg.starts_with("--target-cpu=") && command == Command::Build {
+            target.cpu = arg["--target-cpu=".len()..].to_string();
+        }
+        else if arg.starts_with("--target-features=") && command == Command::Build {
+            target.features = arg["--target-features=".len()..].to_string();
+        }
+        else if arg == "-o" && command == Command::Build {
+            match args.next() {
+                Some(name) => output = Some(name.clone()),
+                None => return usage("-o needs a file name")
+            }
+        }
+        else if arg.starts_with("-") {
+            return usage(&format!("Unknown option {}", arg))
+        }
+        else if file_name.is_none() {
+            file_name = Some(arg.clone());
+        }
+        else {
+            return usage("Only one file can be given")
+        }
+    }
+    let file_name = match file_name {
+        Some(file_name) => file_name,
+        None => return usage("No file given")
+    };
+    let (unit, table) = match check(&file_name, format) {
+        Ok(checked) => checked,
+        Err(code) => return code
+    };
+    match command {
+        Command::Check => EXIT_SUCCESS,
+        Command::Run => run_main(&file_name, level, format, unit, table),
+        Command::Build => {
+            let output = output.unwrap_or_else(|| default_output(&file_name, emit, &target));
+            build(&file_name, &output, emit, &target, level, format, unit, table)
+        },
+        Command::Repl => unreachable!("The REPL doesn't take a file")
+    }
+}
+
+/// Check a file, reporting its errors and warnings.
+///
+/// Returns the verified program, or the exit code if it couldn't be checked.
+fn check(file_name: &str, format: ErrorFormat) -> Result<(Unit, SymbolTable), i32> {
+    let mut source = String::new();
+    if let Err(error) = File::open(file_name).and_then(|mut file| file.read_to_string(&mut source)) {
+        writeln!(io::stderr(), "Could not read {}: {}", file_name, error).unwrap();
+        return Err(EXIT_USAGE)
+    }
+    let tokenizer = IterTokenizer::new(source.chars());
+    let (diagnostics, checked) = match Parser::new(tokenizer).parse_unit() {
+        Ok(program) => {
+            let (unit, table, errors) = program.decompose();
+            (Diagnostic::from_collector(&errors), Some((unit, table)))
+        },
+        Err(error) => (Diagnostic::from_parse_error(&error), None)
+    };
+    let output = match format {
+        ErrorFormat::Human => Renderer::new(file_name, &source, false).render_all(&diagnostics),
+        ErrorFormat::Json => JsonEmitter::new(file_name, &source).emit_all(&diagnostics)
+    };
+    write!(io::stderr(), "{}", output).unwrap();
+    if diagnostics.iter().any(|diagnostic| diagnostic.get_severity() == Severity::Error) {
+        return Err(EXIT_ERRORS)
+    }
+    checked.ok_or(EXIT_ERRORS)
+}
+
+/// JIT compile a checked program and print the result of its `main` function.
+fn run_main(file_name: &str, level: OptLevel, format: ErrorFormat,
+            unit: Unit, table: SymbolTable) -> i32 {
+    let jit = MCJIT::new(module_name(file_name), level);
+    let mut compiler = ModuleCompiler::new(table, jit);
+    compiler.check_unit(&unit);
+    let (mut jit, _context, table) = compiler.decompose();
+    if let Err(error) = verify_module(&jit) {
+        return report_emit_error(file_name, &error, format)
+    }
+    match jit.call(&table, "main", &[]) {
+        Ok(Value::Empty) => EXIT_SUCCESS,
+        Ok(result) => {
+            println!("{}", result);
+            EXIT_SUCCESS
+        },
+        Err(CallError::UnknownFunction(_)) => {
+            writeln!(io::stderr(), "{} does not have a main function", file_name).unwrap();
+            EXIT_ERRORS
+        },
+        Err(error) => {
+            writeln!(io::stderr(), "Could not run main: {}", error).unwrap();
+            EXIT_ERRORS
+        }
+    }
+}
+
+/// Compile a checked program and write it to `output`.
+fn build(file_name: &str, output: &str, emit: Emit, target: &Target, level: OptLevel,
+         format: ErrorFormat, unit: Unit, table: SymbolTable) -> i32 {
+    let emitter = match target.emitter(level) {
+        Ok(emitter) => emitter,
+        Err(error) => return report_emit_error(file_name, &error, format)
+    };
+    // WebAssembly modules export their functions rather than running main
+    let return_type = if emit == Emit::Exe && !emitter.is_wasm() {
+        match main_type(&unit, &table) {
+            Some(ref main_type) if !main_type.get_args().is_empty() => {
+                writeln!(io::stderr(), "main should not take any arguments").unwrap();
+                return EXIT_ERRORS
+            },
+            Some(main_type) => Some(main_type.get_return().clone()),
+            None => {
+                writeln!(io::stderr(), "{} does not have a main function", file_name).unwrap();
+                return EXIT_ERRORS
+            }
+        }
+    }
+    else {
+        None
+    };
+    let mut provider = SimpleModuleProvider::new(&module_name(file_name), level);
+    emitter.prepare_module(&mut provider);
+    let mut compiler = ModuleCompiler::new(table, provider);
+    compiler.check_unit(&unit);
+    let (mut provider, _context, _table) = compiler.decompose();
+    if let Some(ref return_type) = return_type {
+        add_entry_point(&mut provider, return_type);
+    }
+    let output_path = Path::new(output);
+    let object_name = format!("{}.o", output);
+    let emitted = match emit {
+        Emit::LlvmIr => write_ir(&provider, output_path),
+        Emit::LlvmBc => write_bitcode(&provider, output_path),
+        Emit::Obj => emitter.emit_object(&provider, output_path),
+        Emit::Exe => emitter.emit_object(&provider, Path::new(&object_name)).and_then(|()| {
+            let linked = if emitter.is_wasm() {
+                emitter.link_wasm(Path::new(&object_name), output_path, &wasm_exports(&unit))
+            }
+            else {
+                emitter.link(Path::new(&object_name), output_path)
+            };
+            let _ = fs::remove_file(&object_name);
+            linked
+        })
+    };
+    match emitted {
+        Ok(()) => EXIT_SUCCESS,
+        Err(error) => report_emit_error(file_name, &error, format)
+    }
+}
+
+/// Report a program which couldn't be compiled or written out.
+///
+/// Invalid modules are reported as diagnostics, in the chosen format.
+fn report_emit_error(file_name: &str, error: &EmitError, format: ErrorFormat) -> i32 {
+    let diagnostic = match error.get_diagnostic() {
+        Some(diagnostic) => diagnostic,
+        None => {
+            writeln!(io::stderr(), "{}", error).unwrap();
+            return match *error {
+                EmitError::CouldNotWrite { .. } | EmitError::UnknownTarget { .. } => EXIT_USAGE,
+                _ => EXIT_ERRORS
+            }
+        }
+    };
+    let output = match format {
+        ErrorFormat::Human => Renderer::new(file_name, "", false).render(&diagnostic),
+        ErrorFormat::Json => JsonEmitter::new(file_name, "").emit_all(&[diagnostic])
+    };
+    write!(io::stderr(), "{}", output).unwrap();
+    EXIT_ERRORS
+}
+
+/// Get the type of a checked program's `main` function.
+fn main_type(unit: &Unit, table: &SymbolTable) -> Option<FnType> {
+    unit.get_items().iter()
+        .filter_map(|item| match *item {
+            Item::FnDeclaration(ref decl) if decl.get_name().get_name() == "main" =>
+                Some(table[&decl.get_name().get_index()].get_type().clone().expect_fn()),
+            _ => None
+        })
+        .next()
+}
+
+/// Get the file `build` writes to by default.
+///
+/// Executables are named after the file without its extension, unless it
+/// doesn't have one. WebAssembly modules are given the `.wasm` extension.
+fn default_output(file_name: &str, emit: Emit, target: &Target) -> String {
+    let path = Path::new(file_name);
+    let extension = if emit == Emit::Exe && target.is_wasm() { "wasm" } else { emit.extension() };
+    let output = path.with_extension(extension);
+    if output == path {
+        path.with_extension("out").to_string_lossy().into_owned()
+    }
+    else {
+        output.to_string_lossy().into_owned()
+    }
+}
+
+/// Read entries from stdin and evaluate them until the input ends.
+fn repl() -> i32 {
+    let mut repl = Repl::new();
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    let mut entry = String::new();
+    loop {
+        print!("{}", if entry.is_empty() { ">> " } else { ".. " });
+        io::stdout().flush().unwrap();
+        match lines.next() {
+            Some(Ok(line)) => {
+                entry.push_str(&line);
+                entry.push('\n');
+                if needs_more_input(&entry) {
+                    continue
+                }
+            },
+            Some(Err(error)) => {
+                writeln!(io::stderr(), "Could not read input: {}", error).unwrap();
+                return EXIT_USAGE
+            },
+            None => {
+                println!();
+                if entry.is_empty() {
+                    return EXIT_SUCCESS
+                }
+            }
+        }
+        let output = repl.eval(&entry);
+        entry.clear();
+        write!(io::stderr(), "{}", output.diagnostics).unwrap();
+        if let Some(result) = output.result {
+            if !result.is_empty() {
+                println!("{}", result);
+            }
+        }
+    }
+}
+
+/// Get the name of the LLVM module for a file.
+fn module_name(file_name: &str) -> String {
+    Path::new(file_name).file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| file_name.to_string())
+}
+
+/// Print the explanation of an error code.
+fn explain(code: Option<&str>) -> i32 {
+    let code = match code {
+        Some(code) => code,
+        None => return usage("--explain needs an error code")
+    };
+    match ErrorCode::from_code(code) {
+        Some(error_code) => {
+            print!("{}", error_code.explain());
+            EXIT_SUCCESS
+        },
+        None => {
+            writeln!(io::stderr(), "{} is not an error code", code).unwrap();
+            EXIT_USAGE
+        }
+    }
+}
+
+/// Report a problem with the arguments.
+fn usage(problem: &str) -> i32 {
+    writeln!(io::stderr(), "{}\n{}", problem, USAGE).unwrap();
+    EXIT_USAGE
+}
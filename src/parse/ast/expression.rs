@@ -3,9 +3,10 @@
 //! Expression values are used in the `Expression` and `Statement` contexts.
 //! They are usually emitted as asm instructions operating on variables.
 
-use lex::{Token, TokenType, TokenData};
+use lex::{Token, TokenType, TokenData, Span};
 use parse::{ParseResult, ParseError, ExpectedNextType};
-use parse::ast::{Statement, Identifier, Operator, Block};
+use parse::ast::{Statement, Identifier, Operator, Block, TypeExpression};
+use parse::types::Type;
 
 /// Expression types
 #[derive(Debug, PartialEq, Clone)]
@@ -64,6 +65,21 @@ impl Expression {
             other => Err(ParseError::ExpectedLValue(other))
         }
     }
+    /// Get the extent of this expression in the source.
+    ///
+    /// Parentheses around an expression are not included.
+    pub fn span(&self) -> Span {
+        match *self {
+            Expression::Literal(ref literal) => literal.token.get_span(),
+            Expression::VariableRef(ref ident) => ident.get_token().get_span(),
+            Expression::BinaryOp(ref bin_op) => bin_op.span(),
+            Expression::UnaryOp(ref unary_op) => unary_op.span(),
+            Expression::IfExpression(ref if_expr) => if_expr.span(),
+            Expression::FnCall(ref fn_call) => fn_call.span(),
+            Expression::Assignment(ref assign) => assign.span(),
+            Expression::Declaration(ref decl) => decl.span()
+        }
+    }
 }
 
 /// Literal value
@@ -79,12 +95,37 @@ impl Literal {
             token: token
         }
     }
-    pub fn get_value(&self) -> f64 {
+    pub fn get_value(&self) -> LiteralValue {
         match self.token.data {
-            TokenData::NumberLiteral(num) => num,
+            TokenData::NumberLiteral(num) => LiteralValue::Float(num),
+            TokenData::IntLiteral(num) => LiteralValue::Int(num),
+            TokenData::StringLiteral(ref text) => LiteralValue::String(text.clone()),
+            TokenData::BoolLiteral(value) => LiteralValue::Bool(value),
             ref bad => panic!("Invalid token {:?} owned by Literal", bad)
         }
     }
+    pub fn get_type(&self) -> Type {
+        match self.token.data {
+            TokenData::NumberLiteral(_) => Type::Float,
+            TokenData::IntLiteral(_) => Type::Int,
+            TokenData::StringLiteral(_) => Type::String,
+            TokenData::BoolLiteral(_) => Type::Bool,
+            ref bad => panic!("Invalid token {:?} owned by Literal", bad)
+        }
+    }
+}
+
+/// Value represented by a literal
+#[derive(Debug, PartialEq, Clone)]
+pub enum LiteralValue {
+    /// Floating point literal
+    Float(f64),
+    /// Integer literal
+    Int(i64),
+    /// String literal, with escapes applied
+    String(String),
+    /// `true` or `false`
+    Bool(bool)
 }
 
 /// Maths style binary operations (may be split up later)
@@ -114,6 +155,9 @@ impl BinaryOperation {
     pub fn get_right(&self) -> &Expression {
         &self.right
     }
+    pub fn span(&self) -> Span {
+        self.left.span().to(self.right.span())
+    }
 }
 
 /// Unary operation
@@ -138,6 +182,9 @@ impl UnaryOperation {
     pub fn get_inner(&self) -> &Expression {
         &self.expression
     }
+    pub fn span(&self) -> Span {
+        self.op_token.get_span().to(self.expression.span())
+    }
 }
 
 /// Variable declaration
@@ -146,11 +193,22 @@ pub struct Declaration {
     pub mutable: bool,
     pub token: Token,
     pub ident: Identifier,
+    pub type_expr: Option<TypeExpression>,
     pub value: Box<Expression>
 }
 impl Declaration {
-    pub fn new(token: Token, mutable: bool, ident: Identifier, value: Box<Expression>) -> Self {
-        Declaration { token: token, mutable: mutable, ident: ident, value: value }
+    pub fn new(token: Token,
+               mutable: bool,
+               ident: Identifier,
+               type_expr: Option<TypeExpression>,
+               value: Box<Expression>) -> Self {
+        Declaration {
+            token: token,
+            mutable: mutable,
+            ident: ident,
+            type_expr: type_expr,
+            value: value
+        }
     }
     pub fn get_name(&self) -> &str {
         &self.ident.get_name()
@@ -164,9 +222,16 @@ impl Declaration {
     pub fn get_ident(&self) -> &Identifier {
         &self.ident
     }
+    /// Get the type annotation, if one was given
+    pub fn get_type_expr(&self) -> Option<&TypeExpression> {
+        self.type_expr.as_ref()
+    }
     pub fn get_token(&self) -> &Token {
         &self.token
     }
+    pub fn span(&self) -> Span {
+        self.token.get_span().to(self.value.span())
+    }
 }
 
 /// An identifier is assigned to a value
@@ -185,6 +250,9 @@ impl Assignment {
     pub fn get_rvalue(&self) -> &Expression {
         &self.rvalue
     }
+    pub fn span(&self) -> Span {
+        self.lvalue.get_token().get_span().to(self.rvalue.span())
+    }
 }
 
 /// Inline if expression using `=>`
@@ -217,6 +285,9 @@ impl IfExpression {
     pub fn get_else(&self) -> &Expression {
         &self.else_expr
     }
+    pub fn span(&self) -> Span {
+        self.if_token.get_span().to(self.else_expr.span())
+    }
 }
 
 /// Represents invocation of a function
@@ -224,22 +295,21 @@ impl IfExpression {
 pub struct FnCall {
     lvalue: Identifier,
     paren_token: Token,
-    args: FnCallArgs
+    args: FnCallArgs,
+    close_token: Token
 }
 
 impl FnCall {
-    pub fn new(lvalue: Identifier, token: Token, args: FnCallArgs) -> FnCall {
-        FnCall { lvalue: lvalue, paren_token: token, args: args }
+    pub fn new(lvalue: Identifier, token: Token, args: FnCallArgs, close_token: Token) -> FnCall {
+        FnCall { lvalue: lvalue, paren_token: token, args: args, close_token: close_token }
     }
-    pub fn named(lvalue: Identifier, token: Token, args: Vec<CallArgument>) -> FnCall {
-        FnCall { lvalue: lvalue, paren_token: token, args: FnCallArgs::Arguments(args) }
+    pub fn named(lvalue: Identifier, token: Token,
+                 args: Vec<CallArgument>, close_token: Token) -> FnCall {
+        FnCall::new(lvalue, token, FnCallArgs::Arguments(args), close_token)
     }
-    pub fn single_expr(lvalue: Identifier, token: Token, arg: Expression) -> FnCall {
-        FnCall {
-            lvalue: lvalue,
-            paren_token: token,
-            args: FnCallArgs::SingleExpr(Box::new(arg))
-        }
+    pub fn single_expr(lvalue: Identifier, token: Token,
+                       arg: Expression, close_token: Token) -> FnCall {
+        FnCall::new(lvalue, token, FnCallArgs::SingleExpr(Box::new(arg)), close_token)
     }
     pub fn get_name(&self) -> &Identifier {
         &self.lvalue
@@ -253,6 +323,13 @@ impl FnCall {
     pub fn get_args(&self) -> &FnCallArgs {
         &self.args
     }
+    /// Get the `)` token which ends the call
+    pub fn get_close_token(&self) -> &Token {
+        &self.close_token
+    }
+    pub fn span(&self) -> Span {
+        self.lvalue.get_token().get_span().to(self.close_token.get_span())
+    }
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -4,8 +4,8 @@
 //! -- namely the imort item `use`, and declarations such as `class`,
 //! `enum`, `struct`.
 
-use lex::{Token};
-use parse::ast::{Identifier, Block};
+use lex::{Token, Span};
+use parse::ast::{Identifier, Block, TypeExpression};
 
 // This will expand greatly in the future, but for now it's a solid way
 // to have an "enty point" in the compiler (and allow nested blocks)
@@ -22,7 +22,9 @@ pub struct Unit {
 /// Items exported from a protosnirk program
 #[derive(Debug, Clone, PartialEq)]
 pub enum Item {
-    FnDeclaration(FnDeclaration)
+    FnDeclaration(FnDeclaration),
+    /// An item which could not be parsed, starting at the given token
+    Error(Token)
 }
 
 impl Unit {
@@ -35,22 +37,37 @@ impl Unit {
     }
 }
 
+impl Item {
+    /// Get the extent of this item in the source.
+    pub fn span(&self) -> Span {
+        match *self {
+            Item::FnDeclaration(ref fn_declaration) => fn_declaration.span(),
+            Item::Error(ref token) => token.get_span()
+        }
+    }
+}
+
 /// Declaration of a function
 #[derive(Debug, Clone, PartialEq)]
 pub struct FnDeclaration {
     fn_token: Token,
     name: Identifier,
-    arg_list: Vec<Identifier>, // No types here yet :/
+    arg_list: Vec<(Identifier, Option<TypeExpression>)>,
+    return_type: Option<TypeExpression>,
     block: Block
 }
 impl FnDeclaration {
     /// Create a new FnDeclaration
-    pub fn new(fn_token: Token, name: Identifier, arg_list: Vec<Identifier>, block: Block)
-               -> FnDeclaration {
+    pub fn new(fn_token: Token,
+               name: Identifier,
+               arg_list: Vec<(Identifier, Option<TypeExpression>)>,
+               return_type: Option<TypeExpression>,
+               block: Block) -> FnDeclaration {
         FnDeclaration {
             fn_token: fn_token,
             name: name,
             arg_list: arg_list,
+            return_type: return_type,
             block: block
         }
     }
@@ -63,11 +80,18 @@ impl FnDeclaration {
         &self.name
     }
     /// Get the prototype of the function
-    pub fn get_args(&self) -> &Vec<Identifier> {
+    pub fn get_args(&self) -> &Vec<(Identifier, Option<TypeExpression>)> {
         &self.arg_list
     }
+    /// Get the declared return type, if there is one
+    pub fn get_return_type(&self) -> Option<&TypeExpression> {
+        self.return_type.as_ref()
+    }
     /// Get the block inside the function
     pub fn get_block(&self) -> &Block {
         &self.block
     }
+    pub fn span(&self) -> Span {
+        self.fn_token.get_span().to(self.block.span())
+    }
 }
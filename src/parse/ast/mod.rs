@@ -2,15 +2,17 @@ mod expression;
 mod item;
 mod stmt;
 mod operator;
+mod type_expr;
 
 pub use self::expression::*;
 pub use self::item::*;
 pub use self::stmt::*;
+pub use self::type_expr::*;
 pub use self::operator::Operator;
 
 use std::cell::RefCell;
 
-use lex::Token;
+use lex::{Token, Span};
 use parse::verify::scope::ScopeIndex;
 
 /// Basic identifier type
@@ -70,10 +72,29 @@ impl Block {
         }
         return false
     }
+    /// Whether every path through the block ends in a `return`.
+    pub fn always_returns(&self) -> bool {
+        self.statements.iter().any(Statement::always_returns)
+    }
+    /// Whether the block can `break` out of the loop it's in.
+    ///
+    /// Breaks inside of nested loops leave those loops instead.
+    pub fn breaks_loop(&self) -> bool {
+        self.statements.iter().any(Statement::breaks_loop)
+    }
     pub fn get_index(&self) -> &ScopeIndex {
         &self.partial_index
     }
     pub fn get_stmts(&self) -> &[Statement] {
         &self.statements
     }
+    /// Get the extent of the statements in this block.
+    ///
+    /// Empty blocks have an empty span at the start of the file.
+    pub fn span(&self) -> Span {
+        match (self.statements.first(), self.statements.last()) {
+            (Some(first), Some(last)) => first.span().to(last.span()),
+            _ => Span::default()
+        }
+    }
 }
@@ -26,6 +26,30 @@ pub enum Operator {
     LessThanEquals,
     /// Greater than equals test
     GreaterThanEquals,
+    /// Short-circuiting logical and
+    And,
+    /// Short-circuiting logical or
+    Or,
+    /// Logical negation
+    Not,
     /// Custom operator
     Custom
 }
+impl Operator {
+    /// Whether this operator compares its operands, producing a `Bool`
+    pub fn is_comparison(&self) -> bool {
+        match *self {
+            Operator::Equality | Operator::NonEquality |
+            Operator::LessThan | Operator::GreaterThan |
+            Operator::LessThanEquals | Operator::GreaterThanEquals => true,
+            _ => false
+        }
+    }
+    /// Whether this operator works on `Bool`s, producing a `Bool`
+    pub fn is_logical(&self) -> bool {
+        match *self {
+            Operator::And | Operator::Or | Operator::Not => true,
+            _ => false
+        }
+    }
+}
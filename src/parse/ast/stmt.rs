@@ -4,7 +4,7 @@
 /// such as loop constructs. They are usually not accepted in as many places as
 /// `Expression`s are because of their ability to use indentation.
 
-use lex::{CowStr, Token, TokenData, TokenType};
+use lex::{tokens, CowStr, Span, Token, TokenData, TokenType};
 use parse::ast::{Expression, Block, Identifier};
 
 /// Statement representation
@@ -13,8 +13,15 @@ pub enum Statement {
     Expression(Expression),
     Return(Return),
     DoBlock(DoBlock),
-    IfBlock(IfBlock)
-    // match, loop, while, for
+    IfBlock(IfBlock),
+    WhileLoop(WhileLoop),
+    ForLoop(ForLoop),
+    Loop(Loop),
+    Break(Break),
+    Continue(Continue),
+    /// A statement which could not be parsed, starting at the given token
+    Error(Token)
+    // match
 }
 impl Statement {
     pub fn has_value(&self) -> bool {
@@ -22,7 +29,59 @@ impl Statement {
             Statement::Expression(ref inner) => inner.has_value(),
             Statement::DoBlock(ref inner) => inner.has_value(),
             Statement::Return(ref return_) => return_.has_value(),
-            Statement::IfBlock(ref if_) => if_.has_value()
+            Statement::IfBlock(ref if_) => if_.has_value(),
+            // Loops don't produce values yet
+            Statement::WhileLoop(_) | Statement::ForLoop(_) | Statement::Loop(_)
+                | Statement::Break(_) | Statement::Continue(_) => false,
+            Statement::Error(_) => false
+        }
+    }
+    /// Whether every path through the statement ends in a `return`.
+    ///
+    /// A `loop` which it can't `break` out of can only be left by returning.
+    pub fn always_returns(&self) -> bool {
+        match *self {
+            Statement::Return(_) => true,
+            Statement::DoBlock(ref do_block) => do_block.get_block().always_returns(),
+            Statement::IfBlock(ref if_block) => match if_block.get_else() {
+                Some(&(_, ref else_block)) => else_block.always_returns()
+                    && if_block.get_conditionals().iter()
+                        .all(|conditional| conditional.get_block().always_returns()),
+                None => false
+            },
+            Statement::Loop(ref loop_) => !loop_.get_block().breaks_loop(),
+            _ => false
+        }
+    }
+    /// Whether the statement can `break` out of the loop it's in.
+    pub fn breaks_loop(&self) -> bool {
+        match *self {
+            Statement::Break(_) => true,
+            Statement::DoBlock(ref do_block) => do_block.get_block().breaks_loop(),
+            Statement::IfBlock(ref if_block) => {
+                if_block.get_conditionals().iter()
+                    .any(|conditional| conditional.get_block().breaks_loop())
+                || if_block.get_else().map_or(false, |&(_, ref else_block)| else_block.breaks_loop())
+            },
+            _ => false
+        }
+    }
+    /// Get the extent of this statement in the source.
+    ///
+    /// A statement which couldn't be parsed only covers the token it
+    /// started with.
+    pub fn span(&self) -> Span {
+        match *self {
+            Statement::Expression(ref inner) => inner.span(),
+            Statement::Return(ref return_) => return_.span(),
+            Statement::DoBlock(ref do_block) => do_block.span(),
+            Statement::IfBlock(ref if_block) => if_block.span(),
+            Statement::WhileLoop(ref while_) => while_.span(),
+            Statement::ForLoop(ref for_) => for_.span(),
+            Statement::Loop(ref loop_) => loop_.span(),
+            Statement::Break(ref break_) => break_.token.get_span(),
+            Statement::Continue(ref continue_) => continue_.token.get_span(),
+            Statement::Error(ref token) => token.get_span()
         }
     }
 }
@@ -48,6 +107,12 @@ impl Return {
     pub fn get_value(&self) -> &Option<Box<Expression>> {
         &self.value
     }
+    pub fn span(&self) -> Span {
+        match self.value {
+            Some(ref value) => self.token.get_span().to(value.span()),
+            None => self.token.get_span()
+        }
+    }
 }
 
 /// Do <block> statement.
@@ -66,6 +131,9 @@ impl DoBlock {
     pub fn get_block(&self) -> &Block {
         &self.block
     }
+    pub fn span(&self) -> Span {
+        self.do_token.get_span().to(self.block.span())
+    }
 }
 
 /// if <condition> <block>
@@ -132,6 +200,16 @@ impl IfBlock {
         }
         self.else_block.as_ref().unwrap().1.has_value()
     }
+    pub fn span(&self) -> Span {
+        let start = self.conditionals[0].if_token.get_span();
+        match self.else_block {
+            Some((_, ref else_block)) => start.to(else_block.span()),
+            None => {
+                let last = self.conditionals.last().expect("Checked non-empty");
+                start.to(last.block.span())
+            }
+        }
+    }
 }
 
 impl Conditional {
@@ -152,3 +230,145 @@ impl Conditional {
         self.block.has_value()
     }
 }
+
+/// while <condition> <block>
+///
+/// The condition is checked before each run of the block.
+#[derive(Debug, PartialEq, Clone)]
+pub struct WhileLoop {
+    pub while_token: Token,
+    pub condition: Expression,
+    pub block: Block
+}
+impl WhileLoop {
+    pub fn new(while_token: Token, condition: Expression, block: Block) -> WhileLoop {
+        WhileLoop {
+            while_token: while_token,
+            condition: condition,
+            block: block
+        }
+    }
+    pub fn get_token(&self) -> &Token {
+        &self.while_token
+    }
+    pub fn get_condition(&self) -> &Expression {
+        &self.condition
+    }
+    pub fn get_block(&self) -> &Block {
+        &self.block
+    }
+    pub fn span(&self) -> Span {
+        self.while_token.get_span().to(self.block.span())
+    }
+}
+
+/// for <ident> in <start>..<end> [by <step>] <block>
+///
+/// Counts the loop variable up from `start` to `end`, which is included
+/// if the range was given with `..=`. The loop variable is immutable and
+/// only visible inside the block.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ForLoop {
+    pub for_token: Token,
+    pub ident: Identifier,
+    pub start: Expression,
+    pub range_token: Token,
+    pub end: Expression,
+    pub step: Option<Expression>,
+    pub block: Block
+}
+impl ForLoop {
+    pub fn new(for_token: Token,
+               ident: Identifier,
+               start: Expression,
+               range_token: Token,
+               end: Expression,
+               step: Option<Expression>,
+               block: Block) -> ForLoop {
+        ForLoop {
+            for_token: for_token,
+            ident: ident,
+            start: start,
+            range_token: range_token,
+            end: end,
+            step: step,
+            block: block
+        }
+    }
+    pub fn get_token(&self) -> &Token {
+        &self.for_token
+    }
+    pub fn get_ident(&self) -> &Identifier {
+        &self.ident
+    }
+    pub fn get_start(&self) -> &Expression {
+        &self.start
+    }
+    pub fn get_end(&self) -> &Expression {
+        &self.end
+    }
+    /// Whether the range includes its end, i.e. `0..=n`
+    pub fn is_inclusive(&self) -> bool {
+        self.range_token.get_text() == tokens::DoubleDotEquals
+    }
+    pub fn get_step(&self) -> Option<&Expression> {
+        self.step.as_ref()
+    }
+    pub fn get_block(&self) -> &Block {
+        &self.block
+    }
+    pub fn span(&self) -> Span {
+        self.for_token.get_span().to(self.block.span())
+    }
+}
+
+/// loop <block>
+///
+/// Runs the block until a `break` or `return`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Loop {
+    pub loop_token: Token,
+    pub block: Block
+}
+impl Loop {
+    pub fn new(loop_token: Token, block: Block) -> Loop {
+        Loop { loop_token: loop_token, block: block }
+    }
+    pub fn get_token(&self) -> &Token {
+        &self.loop_token
+    }
+    pub fn get_block(&self) -> &Block {
+        &self.block
+    }
+    pub fn span(&self) -> Span {
+        self.loop_token.get_span().to(self.block.span())
+    }
+}
+
+/// `break` out of the innermost loop
+#[derive(Debug, PartialEq, Clone)]
+pub struct Break {
+    pub token: Token
+}
+impl Break {
+    pub fn new(token: Token) -> Break {
+        Break { token: token }
+    }
+    pub fn get_token(&self) -> &Token {
+        &self.token
+    }
+}
+
+/// `continue` to the next iteration of the innermost loop
+#[derive(Debug, PartialEq, Clone)]
+pub struct Continue {
+    pub token: Token
+}
+impl Continue {
+    pub fn new(token: Token) -> Continue {
+        Continue { token: token }
+    }
+    pub fn get_token(&self) -> &Token {
+        &self.token
+    }
+}
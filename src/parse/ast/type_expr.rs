@@ -0,0 +1,37 @@
+//! Types as they are written in source code.
+//!
+//! These are resolved to `parse::types::Type`s by the verifier.
+
+use lex::Token;
+
+/// A type annotation, such as the `Float` in `let x: Float = 0`.
+#[derive(Debug, PartialEq, Clone)]
+pub enum TypeExpression {
+    /// A type referred to by its name
+    Named(NamedTypeExpression)
+}
+impl TypeExpression {
+    /// Get the token to use when reporting errors about this type
+    pub fn get_token(&self) -> &Token {
+        match *self {
+            TypeExpression::Named(ref named) => named.get_token()
+        }
+    }
+}
+
+/// A type referred to by name, such as `Bool`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct NamedTypeExpression {
+    pub token: Token
+}
+impl NamedTypeExpression {
+    pub fn new(token: Token) -> NamedTypeExpression {
+        NamedTypeExpression { token: token }
+    }
+    pub fn get_name(&self) -> &str {
+        &self.token.text
+    }
+    pub fn get_token(&self) -> &Token {
+        &self.token
+    }
+}
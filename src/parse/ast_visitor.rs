@@ -58,7 +58,24 @@ pub trait ASTVisitor {
             },
             Statement::IfBlock(ref block) => {
                 self.check_if_block(block)
-            }
+            },
+            Statement::WhileLoop(ref while_) => {
+                self.check_while_loop(while_)
+            },
+            Statement::ForLoop(ref for_) => {
+                self.check_for_loop(for_)
+            },
+            Statement::Loop(ref loop_) => {
+                self.check_loop(loop_)
+            },
+            Statement::Break(ref break_) => {
+                self.check_break(break_)
+            },
+            Statement::Continue(ref continue_) => {
+                self.check_continue(continue_)
+            },
+            // Syntax errors have already been reported by the parser
+            Statement::Error(_) => { }
         }
     }
 
@@ -66,7 +83,8 @@ pub trait ASTVisitor {
         match *item {
             Item::FnDeclaration(ref decl) => {
                 self.check_fn_declaration(decl)
-            }
+            },
+            Item::Error(_) => { }
         }
     }
 
@@ -136,6 +154,34 @@ pub trait ASTVisitor {
         }
     }
 
+    fn check_while_loop(&mut self, while_: &WhileLoop) {
+        self.check_expression(while_.get_condition());
+        self.check_block(while_.get_block());
+    }
+
+    fn check_for_loop(&mut self, for_: &ForLoop) {
+        self.check_expression(for_.get_start());
+        self.check_expression(for_.get_end());
+        if let Some(step) = for_.get_step() {
+            self.check_expression(step);
+        }
+        self.check_block(for_.get_block());
+    }
+
+    fn check_loop(&mut self, loop_: &Loop) {
+        self.check_block(loop_.get_block());
+    }
+
+    #[inline]
+    #[allow(unused_variables)]
+    fn check_break(&mut self, break_: &Break) {
+    }
+
+    #[inline]
+    #[allow(unused_variables)]
+    fn check_continue(&mut self, continue_: &Continue) {
+    }
+
     fn check_if_expr(&mut self, if_expr: &IfExpression) {
         self.check_expression(if_expr.get_condition());
         self.check_expression(if_expr.get_true_expr());
@@ -1,7 +1,7 @@
 //! Error handling in parsers
 
 use lex::{CowStr, Token, TokenType};
-use parse::ast::{Expression};
+use parse::ast::{Expression, Unit};
 use parse::verify::ErrorCollector;
 
 /// Result given from main and expression parsers
@@ -27,8 +27,19 @@ pub enum ParseError {
         text: CowStr,
         token_type: TokenType
     },
-    EOF,
-    LazyString(String)
+    /// The tokenizer could not lex the given token
+    LexError(Token),
+    /// Syntax errors which the parser recovered from, with the partial
+    /// unit that it was still able to parse
+    SyntaxErrors {
+        unit: Unit,
+        errors: Vec<ParseError>
+    },
+    /// A token which can't be used where it was found
+    UnexpectedToken(Token),
+    /// An `else if` mixing inline and block `if` syntax
+    InlineElseIf(Token),
+    EOF
 }
 
 /// Information of what the parser was expecting to get
@@ -15,5 +15,6 @@ pub use self::parser::{Parser, IndentationRule};
 pub use self::program::Program;
 pub use self::ast_visitor::ASTVisitor;
 
-pub use self::verify::{VerifyError, ErrorCollector};
+pub use self::verify::{VerifyError, ErrorCollector, Definitions, Symbol, Source};
 pub use self::verify::scope::{ScopeIndex, SymbolTable};
+pub use self::types::{Type, FnType, Intrinsic};
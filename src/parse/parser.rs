@@ -8,11 +8,11 @@ use std::collections::{HashMap, VecDeque};
 use std::rc::Rc;
 use std::cell::Cell;
 
-use lex::{CowStr, Token, TokenType, TokenData, Tokenizer};
+use lex::{tokens, CowStr, Token, TokenType, TokenData, Tokenizer, TextLocation};
 use parse::{Program, ParseError, ParseResult};
 use parse::ast::*;
 use parse::symbol::*;
-use parse::verify::Verifier;
+use parse::verify::{Verifier, Definitions};
 
 /// Parser object which parses things
 pub struct Parser<T: Tokenizer> {
@@ -31,7 +31,11 @@ pub struct Parser<T: Tokenizer> {
     /// Mapping of tokens to applied operators
     token_operators: HashMap<(TokenType, CowStr), Operator>,
     /// Allows the parser to skip over unneeded indentation
-    indent_rules: Vec<IndentationRule>
+    indent_rules: Vec<IndentationRule>,
+    /// Syntax errors which the parser has recovered from
+    errors: Vec<ParseError>,
+    /// Location of the last token which was consumed
+    last_location: TextLocation
 }
 
 impl<T: Tokenizer> Parser<T> {
@@ -59,14 +63,16 @@ impl<T: Tokenizer> Parser<T> {
     // line
     pub fn peek_is_newline(&mut self, current: &Token) -> bool {
         let (indent, peeked) = self.peek_indented();
-        indent || peeked.location.line > current.location.line
+        indent || peeked.get_location().line > current.get_location().line
     }
 
     /// Consumes the next token from the tokenizer.
     pub fn consume(&mut self) -> Token {
         self.look_ahead(1usize);
-        self.lookahead.pop_back()
-            .expect("Unable to queue token via lookahead for consume")
+        let token = self.lookahead.pop_back()
+            .expect("Unable to queue token via lookahead for consume");
+        self.last_location = token.get_location();
+        token
     }
 
     /// Consume the next token, returning whether the given rule has been
@@ -142,7 +148,10 @@ impl<T: Tokenizer> Parser<T> {
     pub fn consume_type(&mut self, expected_type: TokenType) -> Result<Token, ParseError> {
         trace!("Consuming type {:?}", expected_type);
         let token = self.consume();
-        if token.data.get_type() != expected_type {
+        if token.data.get_type() == TokenType::Error && expected_type != TokenType::Error {
+            Err(ParseError::LexError(token))
+        }
+        else if token.data.get_type() != expected_type {
             Err(ParseError::ExpectedToken {
                 expected: expected_type,
                 got: token.into()
@@ -216,7 +225,11 @@ impl<T: Tokenizer> Parser<T> {
         }
         else if token.data.get_type() == TokenType::EndBlock {
             trace!("Received end block mid-parse");
-            return Err(ParseError::LazyString("Unexpected EndBlock".to_string()))
+            return Err(ParseError::UnexpectedToken(token))
+        }
+        else if token.data.get_type() == TokenType::Error {
+            trace!("Received a lexer error mid-parse");
+            return Err(ParseError::LexError(token))
         }
         else if token.data.get_type() == TokenType::Ident {
             trace!("Parsing an identifier, using the identifier parser");
@@ -232,7 +245,7 @@ impl<T: Tokenizer> Parser<T> {
         }
         else {
             trace!("Could not find a parser!");
-            return Err(ParseError::LazyString(format!("Unexpected token {:?}", token)))
+            return Err(ParseError::UnexpectedToken(token))
         }
         let mut left = try!(prefix.parse(self, token));
         trace!("Parsed left expression: {:?}", left);
@@ -258,7 +271,29 @@ impl<T: Tokenizer> Parser<T> {
 
     /// Parse a single statement.
     ///
+    /// If the statement has a syntax error, the error is saved, the rest of
+    /// the statement is skipped, and a `Statement::Error` is returned in its
+    /// place. `Err` is only returned if there are no more tokens.
     pub fn statement(&mut self) -> Result<Statement, ParseError> {
+        let start = self.peek().clone();
+        if start.data.get_type() == TokenType::EOF {
+            return Err(ParseError::EOF)
+        }
+        let rule_count = self.indent_rules.len();
+        match self.statement_inner() {
+            Ok(statement) => Ok(statement),
+            Err(error) => {
+                trace!("Recovering from statement error {:?}", error);
+                self.errors.push(error);
+                self.indent_rules.truncate(rule_count);
+                self.skip_statement(&start);
+                Ok(Statement::Error(start))
+            }
+        }
+    }
+
+    /// Parse a statement, stopping at the first error.
+    fn statement_inner(&mut self) -> Result<Statement, ParseError> {
         let mut found_parser: Option<Rc<PrefixParser<Statement, T> + 'static>> = None;
         let peek_data = (self.next_type(), Cow::Owned(self.peek().text.to_string()));
         if let Some(stmt_parser) = self.stmt_prefix_parsers.get(&(peek_data.0, Cow::Borrowed(&*peek_data.1))) {
@@ -280,6 +315,7 @@ impl<T: Tokenizer> Parser<T> {
     /// to support `Indent` and `Outdent` tokens for begin/end blocks.
     ///
     /// Block parsing assumes the `BeginBlock` token has already been consumed.
+    /// Statements with syntax errors are left in the block as `Statement::Error`s.
     pub fn block(&mut self) -> Result<Block, ParseError> {
         let mut found = Vec::new();
         loop {
@@ -298,7 +334,30 @@ impl<T: Tokenizer> Parser<T> {
     }
 
     /// Parse an item from a program (a function definition)
+    ///
+    /// If the item has a syntax error, the error is saved and the parser skips
+    /// ahead to the next `fn`, returning an `Item::Error`. `Err` is only returned
+    /// if there are no more tokens.
     pub fn item(&mut self) -> Result<Item, ParseError> {
+        let start = self.peek().clone();
+        if start.data.get_type() == TokenType::EOF {
+            return Err(ParseError::EOF)
+        }
+        let rule_count = self.indent_rules.len();
+        match self.item_inner() {
+            Ok(item) => Ok(item),
+            Err(error) => {
+                trace!("Recovering from item error {:?}", error);
+                self.errors.push(error);
+                self.indent_rules.truncate(rule_count);
+                self.skip_item(&start);
+                Ok(Item::Error(start))
+            }
+        }
+    }
+
+    /// Parse an item, stopping at the first error.
+    fn item_inner(&mut self) -> Result<Item, ParseError> {
         let mut found_parser: Option<Rc<PrefixParser<Item, T> + 'static>> = None;
         let peek_data = (self.next_type(), Cow::Owned(self.peek().text.to_string()));
         if let Some(item_parser) = self.item_parsers.get(&(peek_data.0, Cow::Borrowed(&*peek_data.1))) {
@@ -310,8 +369,10 @@ impl<T: Tokenizer> Parser<T> {
                 let token = self.consume();
                 parser.parse(self, token)
             },
+            None if peek_data.0 == TokenType::Error =>
+                Err(ParseError::LexError(self.consume())),
             None =>
-                Err(ParseError::LazyString(format!("Unexpeted item token `{}`", &peek_data.1)))
+                Err(ParseError::UnexpectedToken(self.consume()))
         }
     }
 
@@ -330,6 +391,22 @@ impl<T: Tokenizer> Parser<T> {
         }
     }
 
+    /// Parse a type annotation, such as `Float`.
+    pub fn type_expr(&mut self) -> Result<TypeExpression, ParseError> {
+        let token = self.consume();
+        trace!("Getting a type from {}", token);
+        if token.data.get_type() == TokenType::Ident {
+            Ok(TypeExpression::Named(NamedTypeExpression::new(token)))
+        } else if token.data.get_type() == TokenType::Error {
+            Err(ParseError::LexError(token))
+        } else {
+            Err(ParseError::ExpectedToken {
+                expected: TokenType::Ident,
+                got: token
+            })
+        }
+    }
+
     /// Gets the operator registered for the given token.
     pub fn operator(&self, token_type: TokenType, text: &CowStr) -> Result<Operator, ParseError> {
         use std::ops::Deref;
@@ -366,6 +443,9 @@ impl<T: Tokenizer> Parser<T> {
             (Symbol, tokens::DoubleEquals) => BinOpExprSymbol::with_precedence(Precedence::Equality),
             (Symbol, tokens::NotEquals) => BinOpExprSymbol::with_precedence(Precedence::Equality),
 
+            (Keyword, tokens::And) => BinOpExprSymbol::with_precedence(Precedence::And),
+            (Keyword, tokens::Or) => BinOpExprSymbol::with_precedence(Precedence::Or),
+
             (Symbol, tokens::PlusEquals) => Rc::new(AssignOpParser { }) as Rc<InfixParser<Expression, T>>,
             (Symbol, tokens::MinusEquals) => Rc::new(AssignOpParser { }) as Rc<InfixParser<Expression, T>>,
             (Symbol, tokens::StarEquals) => Rc::new(AssignOpParser { }) as Rc<InfixParser<Expression, T>>,
@@ -378,6 +458,7 @@ impl<T: Tokenizer> Parser<T> {
             (Keyword, tokens::If) => Rc::new(IfExpressionParser { }) as Rc<PrefixParser<Expression, T>>,
 
             (Symbol, tokens::Minus) => UnaryOpExprSymbol::with_precedence(Precedence::NumericPrefix),
+            (Keyword, tokens::Not) => UnaryOpExprSymbol::with_precedence(Precedence::NotKeyword),
             (Symbol, tokens::LeftParen) => Rc::new(ParensParser { }) as Rc<PrefixParser<Expression, T>>,
         ];
         let stmt_prefix_map: HashMap<(TokenType, CowStr), Rc<PrefixParser<Statement, T> + 'static>> =
@@ -385,6 +466,11 @@ impl<T: Tokenizer> Parser<T> {
             (Keyword, tokens::Return) => Rc::new(ReturnParser { }) as Rc<PrefixParser<Statement, T>>,
             (Keyword, tokens::Do) => Rc::new(DoBlockParser { }) as Rc<PrefixParser<Statement, T>>,
             (Keyword, tokens::If) => Rc::new(IfBlockParser { }) as Rc<PrefixParser<Statement, T>>,
+            (Keyword, tokens::While) => Rc::new(WhileLoopParser { }) as Rc<PrefixParser<Statement, T>>,
+            (Keyword, tokens::For) => Rc::new(ForLoopParser { }) as Rc<PrefixParser<Statement, T>>,
+            (Keyword, tokens::Loop) => Rc::new(LoopParser { }) as Rc<PrefixParser<Statement, T>>,
+            (Keyword, tokens::Break) => Rc::new(BreakParser { }) as Rc<PrefixParser<Statement, T>>,
+            (Keyword, tokens::Continue) => Rc::new(ContinueParser { }) as Rc<PrefixParser<Statement, T>>,
         ];
         let item_prefix_map: HashMap<(TokenType, CowStr), Rc<PrefixParser<Item, T> + 'static>> =
         hashmap![
@@ -406,7 +492,10 @@ impl<T: Tokenizer> Parser<T> {
             (Symbol, tokens::RightAngle) => Operator::GreaterThan,
             (Symbol, tokens::GreaterThanEquals) => Operator::GreaterThan,
             (Symbol, tokens::DoubleEquals) => Operator::Equality,
-            (Symbol, tokens::NotEquals) => Operator::NonEquality
+            (Symbol, tokens::NotEquals) => Operator::NonEquality,
+            (Keyword, tokens::And) => Operator::And,
+            (Keyword, tokens::Or) => Operator::Or,
+            (Keyword, tokens::Not) => Operator::Not
         ];
 
         Parser {
@@ -417,25 +506,124 @@ impl<T: Tokenizer> Parser<T> {
             expr_prefix_parsers: expr_prefix_map,
             expr_infix_parsers: expr_infix_map,
             token_operators: operator_map,
-            indent_rules: Vec::new()
+            indent_rules: Vec::new(),
+            errors: Vec::new(),
+            last_location: TextLocation::default()
         }
     }
 
-    /// Parse a program and verify it for errors
-    pub fn parse_unit(&mut self) -> Result<Program, ParseError> {
+    /// Parse all of the items in the source.
+    ///
+    /// Parsing continues past syntax errors, so the `Unit` may contain
+    /// `Item::Error`s and `Statement::Error`s. The errors themselves are
+    /// available from `get_errors`.
+    pub fn unit(&mut self) -> Unit {
         let mut items = Vec::with_capacity(10);
-        while self.next_type() != TokenType::EOF {
-            let item = try!(self.item());
+        while let Ok(item) = self.item() {
             trace!("Parsed an item");
             items.push(item);
         }
         trace!("Parsed {} items", items.len());
-        let unit = Unit::new(items);
+        Unit::new(items)
+    }
+
+    /// Get the syntax errors which the parser has recovered from so far.
+    pub fn get_errors(&self) -> &[ParseError] {
+        &self.errors
+    }
+
+    /// Take the syntax errors which the parser has recovered from so far.
+    pub fn take_errors(&mut self) -> Vec<ParseError> {
+        ::std::mem::replace(&mut self.errors, Vec::new())
+    }
+
+    /// Parse a program and verify it for errors
+    ///
+    /// If there are any syntax errors they are all returned along with
+    /// the partially parsed unit, without verifying it.
+    pub fn parse_unit(&mut self) -> Result<Program, ParseError> {
+        let unit = self.unit();
         trace!("Parsed unit {:#?}", unit);
+        if !self.errors.is_empty() {
+            let errors = self.take_errors();
+            return Err(ParseError::SyntaxErrors { unit: unit, errors: errors })
+        }
         let program = Verifier { }.verify_unit(unit);
         program.map_err(|errors| ParseError::VerifierError { collection: errors })
     }
 
+    /// Parse a program and verify it along with symbols accepted earlier,
+    /// such as the previous entries of a REPL.
+    pub fn parse_unit_incremental(&mut self, definitions: &Definitions) -> Result<Program, ParseError> {
+        let unit = self.unit();
+        trace!("Parsed unit {:#?}", unit);
+        if !self.errors.is_empty() {
+            let errors = self.take_errors();
+            return Err(ParseError::SyntaxErrors { unit: unit, errors: errors })
+        }
+        let program = Verifier { }.verify_incremental(unit, definitions);
+        program.map_err(|errors| ParseError::VerifierError { collection: errors })
+    }
+
+    /// Skip the rest of a statement which had a syntax error.
+    ///
+    /// Stops at the next line, the end of the current block, or a `fn`.
+    /// Blocks indented inside the statement are skipped entirely.
+    fn skip_statement(&mut self, start: &Token) {
+        let error_line = self.last_location.line;
+        self.skip_past(start);
+        let mut depth = 0usize;
+        loop {
+            let (next_type, next_line, is_fn) = {
+                let next = self.peek();
+                (next.data.get_type(), next.get_location().line,
+                 next.data.get_type() == TokenType::Keyword && next.get_text() == tokens::Fn)
+            };
+            match next_type {
+                TokenType::EOF => return,
+                TokenType::BeginBlock => depth += 1,
+                TokenType::EndBlock if depth == 0 => return,
+                TokenType::EndBlock => depth -= 1,
+                _ if depth == 0 && (is_fn || next_line > error_line) => return,
+                _ => {}
+            }
+            self.consume();
+        }
+    }
+
+    /// Skip the rest of an item which had a syntax error, up to the next `fn`.
+    fn skip_item(&mut self, start: &Token) {
+        self.skip_past(start);
+        let mut depth = 0usize;
+        loop {
+            let (next_type, is_fn) = {
+                let next = self.peek();
+                (next.data.get_type(),
+                 next.data.get_type() == TokenType::Keyword && next.get_text() == tokens::Fn)
+            };
+            match next_type {
+                TokenType::EOF => return,
+                TokenType::BeginBlock => depth += 1,
+                TokenType::EndBlock if depth > 0 => depth -= 1,
+                _ if depth == 0 && is_fn => return,
+                _ => {}
+            }
+            self.consume();
+        }
+    }
+
+    /// Make sure the token a failed parse started on has been consumed,
+    /// so that recovering always makes progress.
+    fn skip_past(&mut self, start: &Token) {
+        let at_start = {
+            let next = self.peek();
+            next.get_location() == start.get_location() && next.data.get_type() != TokenType::EOF
+        };
+        if at_start {
+            self.consume();
+        }
+    }
+
     /// Get the current precedence
     fn current_precedence(&mut self) -> Precedence {
         use std::ops::Deref;
@@ -56,7 +56,7 @@ mod tests {
             .. Default::default()
         };
         let five_token = Token {
-            data: TokenData::NumberLiteral(5f64),
+            data: TokenData::IntLiteral(5),
             .. Default::default()
         };
         let lvalue = Expression::VariableRef(lvalue_ident.clone());
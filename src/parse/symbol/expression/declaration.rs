@@ -13,6 +13,9 @@ use parse::symbol::{PrefixParser, Precedence};
 /// ```text
 /// let mut            x          =         6 + 3
 /// ^:.  ^:mutable  ->name:name (skip) ->value:expression
+///
+/// let x     :      Float    = 6
+///     ^name ^skip  ->type
 /// ```
 #[derive(Debug)]
 pub struct DeclarationParser { }
@@ -28,13 +31,21 @@ impl<T: Tokenizer> PrefixParser<Expression, T> for DeclarationParser {
         trace!("Found mutability: {}", is_mutable);
         let name = try!(parser.lvalue());
         trace!("Got name {:?}", name);
+        let type_expr = if parser.peek().get_text() == tokens::Colon {
+            parser.consume();
+            Some(try!(parser.type_expr()))
+        }
+        else {
+            None
+        };
         try!(parser.consume_name(TokenType::Symbol, tokens::Equals));
         trace!("Consumed =, parsing rvalue");
         // TODO allow for block here
         let value_expr = try!(parser.expression(Precedence::Min));
         let value = try!(value_expr.expect_value());
         trace!("Got rvalue {:?}", value);
-        Ok(Expression::Declaration(Declaration::new(token, is_mutable, name, Box::new(value))))
+        Ok(Expression::Declaration(
+            Declaration::new(token, is_mutable, name, type_expr, Box::new(value))))
     }
 }
 
@@ -43,8 +54,9 @@ mod tests {
     use std::borrow::Cow;
     use std::cell::RefCell;
 
-    use lex::{Token, TokenData, TokenType, TextLocation};
-    use parse::ast::{Declaration, Expression, Statement, Block, Literal, Identifier};
+    use lex::{Token, TokenData, TokenType, TextLocation, Span};
+    use parse::ast::{Declaration, Expression, Statement, Block, Literal, Identifier,
+                     TypeExpression, NamedTypeExpression};
     use parse::symbol::{PrefixParser, DeclarationParser};
     use parse::ScopeIndex;
     use parse::tests as parse_tests;
@@ -52,26 +64,29 @@ mod tests {
     const LET_TOKEN: Token = Token {
         data: TokenData::Keyword,
         text: Cow::Borrowed("let"),
-        location: TextLocation {
-            column: 0, line: 0, index: 0
+        span: Span {
+            start: TextLocation { index: 0, line: 0, column: 0 },
+            end: TextLocation { index: 3, line: 0, column: 3 }
         }
     };
 
     const X_TOKEN: Token = Token {
         data: TokenData::Ident,
         text: Cow::Borrowed("x"),
-        location: TextLocation {
-            column: 0, line: 0, index: 0
+        span: Span {
+            start: TextLocation { index: 0, line: 0, column: 0 },
+            end: TextLocation { index: 1, line: 0, column: 1 }
         }
     };
 
 
     const LITERAL_ZERO: Expression = Expression::Literal(Literal {
         token: Token {
-            data: TokenData::NumberLiteral(0f64),
+            data: TokenData::IntLiteral(0),
             text: Cow::Borrowed("0"),
-            location: TextLocation {
-                column: 0, line: 0, index: 0
+            span: Span {
+                start: TextLocation { index: 0, line: 0, column: 0 },
+                end: TextLocation { index: 1, line: 0, column: 1 }
             }
         }
     });
@@ -83,7 +98,7 @@ mod tests {
             index: RefCell::new(ScopeIndex::default()),
             token: X_TOKEN.clone() // Not looking at token here?
         };
-        let expected = Declaration::new(LET_TOKEN.clone(), false, ident, Box::new(LITERAL_ZERO.clone()));
+        let expected = Declaration::new(LET_TOKEN.clone(), false, ident, None, Box::new(LITERAL_ZERO.clone()));
         let parsed = DeclarationParser { }.parse(&mut parser, LET_TOKEN.clone()).unwrap();
         parse_tests::expression_match(&Expression::Declaration(expected), &parsed);
     }
@@ -95,8 +110,34 @@ mod tests {
             index: RefCell::new(ScopeIndex::default()),
             token: X_TOKEN.clone() // Not looking at token here?
         };
-        let expected = Declaration::new(LET_TOKEN.clone(), true, ident, Box::new(LITERAL_ZERO.clone()));
+        let expected = Declaration::new(LET_TOKEN.clone(), true, ident, None, Box::new(LITERAL_ZERO.clone()));
         let parsed = DeclarationParser { }.parse(&mut parser, LET_TOKEN.clone()).unwrap();
         parse_tests::expression_match(&Expression::Declaration(expected), &parsed);
     }
+
+    #[test]
+    fn it_parses_let_var_with_type() {
+        let mut parser = parse_tests::parser("x: Bool = 0");
+        let ident = Identifier {
+            index: RefCell::new(ScopeIndex::default()),
+            token: X_TOKEN.clone()
+        };
+        let parsed = DeclarationParser { }.parse(&mut parser, LET_TOKEN.clone()).unwrap();
+        let expected = Declaration::new(LET_TOKEN.clone(), false, ident,
+            Some(TypeExpression::Named(NamedTypeExpression::new(Token {
+                data: TokenData::Ident,
+                text: Cow::Borrowed("Bool"),
+                span: Span {
+                    start: TextLocation { index: 3, line: 0, column: 3 },
+                    end: TextLocation { index: 7, line: 0, column: 7 }
+                }
+            }))),
+            Box::new(LITERAL_ZERO.clone()));
+        parse_tests::expression_match(&Expression::Declaration(expected.clone()), &parsed);
+        match parsed {
+            Expression::Declaration(ref decl) =>
+                assert_eq!(decl.get_type_expr(), expected.get_type_expr()),
+            other => panic!("Expected a declaration, got {:#?}", other)
+        }
+    }
 }
@@ -25,9 +25,10 @@ impl<T: Tokenizer> InfixParser<Expression, T> for FnCallParser {
 
         let mut called_args = Vec::new();
         let mut arg_name = true;
+        let close_token;
         loop {
             if parser.peek().get_text() == tokens::RightParen {
-                parser.consume();
+                close_token = parser.consume();
                 trace!("Function call complete");
                 break
             }
@@ -50,10 +51,9 @@ impl<T: Tokenizer> InfixParser<Expression, T> for FnCallParser {
                 // TODO need to give better errors/handle multiple exprs
                 // being written
                 else {
-                    try!(parser.consume_name_indented(TokenType::Symbol,
-                                                      tokens::RightParen,
-                                                      IndentationRule::NegateDeindent));
-                    let fn_call = FnCall::single_expr(lvalue, token, arg);
+                    let (_, close_token) = try!(parser.consume_name_indented(
+                        TokenType::Symbol, tokens::RightParen, IndentationRule::NegateDeindent));
+                    let fn_call = FnCall::single_expr(lvalue, token, arg, close_token);
                     return Ok(Expression::FnCall(fn_call))
                 }
                 arg_name = false;
@@ -65,7 +65,7 @@ impl<T: Tokenizer> InfixParser<Expression, T> for FnCallParser {
                 arg_name = true;
             }
         }
-        let call = FnCall::named(lvalue, token, called_args);
+        let call = FnCall::named(lvalue, token, called_args, close_token);
         Ok(Expression::FnCall(call))
     }
 
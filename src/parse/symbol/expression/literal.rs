@@ -11,12 +11,16 @@ use parse::ast::*;
 /// ```text
 /// 34
 /// ^literal
+///
+/// "text"
+/// ^literal
 /// ```
 pub struct LiteralParser { }
 impl<T: Tokenizer> PrefixParser<Expression, T> for LiteralParser {
     fn parse(&self, _parser: &mut Parser<T>, token: Token) -> ParseResult<Expression> {
         match token.data {
-            TokenData::NumberLiteral(_) =>
+            TokenData::NumberLiteral(_) | TokenData::IntLiteral(_) | TokenData::StringLiteral(_)
+                | TokenData::BoolLiteral(_) =>
                 Ok(Expression::Literal(Literal::new(token))),
             _ => Err(ParseError::ExpectedToken {
                     expected: TokenType::Literal,
@@ -37,7 +41,7 @@ mod tests {
     fn it_parses_literal_number() {
         let mut parser = parse_tests::parser("5");
         let expected_token = Token {
-            data: TokenData::NumberLiteral(5f64),
+            data: TokenData::IntLiteral(5),
             .. Default::default()
         };
         let expected = Expression::Literal(Literal::new(expected_token.clone()));
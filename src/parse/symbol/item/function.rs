@@ -16,7 +16,10 @@ use parse::symbol::{PrefixParser, Precedence, AssignmentParser, InfixParser};
 ///
 /// fn foo (bar, baz, \+ bliz) -> int \- \+ stmt* \-
 /// fn foo(arg1, arg2, argn) => expr
+/// fn foo(arg1: Float, arg2: Bool) -> Float => expr
 /// ```
+///
+/// Parameter and return types are optional.
 #[derive(Debug, PartialEq, Clone)]
 pub struct FnDeclarationParser { }
 impl<T: Tokenizer> PrefixParser<Item, T> for FnDeclarationParser {
@@ -41,7 +44,14 @@ impl<T: Tokenizer> PrefixParser<Item, T> for FnDeclarationParser {
             if arg_name {
                 parser.apply_indentation(IndentationRule::NegateDeindent);
                 let name = try!(parser.lvalue());
-                args.push(name);
+                let arg_type = if parser.peek().get_text() == tokens::Colon {
+                    parser.consume();
+                    Some(try!(parser.type_expr()))
+                }
+                else {
+                    None
+                };
+                args.push((name, arg_type));
                 arg_name = false;
             }
             // comma
@@ -52,7 +62,13 @@ impl<T: Tokenizer> PrefixParser<Item, T> for FnDeclarationParser {
                 arg_name = true;
             }
         }
-        // TODO `->` result type
+        let return_type = if parser.peek().get_text() == tokens::Arrow {
+            parser.consume();
+            Some(try!(parser.type_expr()))
+        }
+        else {
+            None
+        };
 
         // Inline fn syntax
         let block = if parser.peek().get_text() == tokens::InlineArrow {
@@ -66,7 +82,7 @@ impl<T: Tokenizer> PrefixParser<Item, T> for FnDeclarationParser {
             try!(parser.block())
         };
 
-        let decl = FnDeclaration::new(token, name, args, block);
+        let decl = FnDeclaration::new(token, name, args, return_type, block);
         Ok(Item::FnDeclaration(decl))
     }
 }
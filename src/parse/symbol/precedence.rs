@@ -11,6 +11,12 @@ pub enum Precedence {
     Return,
     /// Assignment and declaration statements
     Assign,
+    /// The `or` operator
+    Or,
+    /// The `and` operator
+    And,
+    /// The `not` keyword, which binds looser than comparisons
+    NotKeyword,
     ///  The `==` and `!=` operators
     Equality,
     /// Less than and greater than
@@ -23,8 +29,6 @@ pub enum Precedence {
     Modulo,
     /// Negate or positive operator
     NumericPrefix,
-    /// The `not` keyword
-    NotKeyword,
     /// Parens binder, used for both prefix and infix fns
     Paren,
     /// Extra value on the end
@@ -70,6 +74,13 @@ mod test {
         assert_eq!(mem::size_of::<Precedence>(), mem::size_of::<u8>());
     }
 
+    #[test]
+    fn it_binds_logical_operators_loosely() {
+        assert!(Precedence::Or < Precedence::And);
+        assert!(Precedence::And < Precedence::NotKeyword);
+        assert!(Precedence::NotKeyword < Precedence::Equality);
+    }
+
     #[test]
     fn it_makes_a_bigger_precedence() {
         let min = Precedence::Min;
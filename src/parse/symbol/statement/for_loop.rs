@@ -0,0 +1,95 @@
+//! Counted for loop parser.
+
+use lex::{tokens, Token, Tokenizer, TokenType};
+use parse::ast::*;
+use parse::{Parser, ParseError, ParseResult};
+use parse::symbol::{PrefixParser, Precedence};
+
+/// Parses for loops over numeric ranges.
+///
+/// # Examples
+/// ```text
+/// for   i      in    expr  ..    expr  [by expr]  \+ stmt* \-
+///  ^take ^ident ^take ^expr ^take ^expr ^take ^expr    ^block
+///
+/// for i in 0..=n by 2
+/// ```
+#[derive(Debug)]
+pub struct ForLoopParser { }
+impl<T: Tokenizer> PrefixParser<Statement, T> for ForLoopParser {
+    fn parse(&self, parser: &mut Parser<T>, token: Token) -> ParseResult<Statement> {
+        debug_assert!(token.get_text() == tokens::For,
+            "Invalid token {:?} in ForLoopParser", token);
+        let ident = try!(parser.lvalue());
+        try!(parser.consume_name(TokenType::Keyword, tokens::In));
+        trace!("Parsing range of for loop over {}", ident.get_name());
+        let start = try!(parser.expression(Precedence::Min));
+        let range_token = parser.consume();
+        if range_token.get_text() != tokens::DoubleDot
+            && range_token.get_text() != tokens::DoubleDotEquals {
+            return Err(ParseError::ExpectedToken {
+                expected: TokenType::Symbol,
+                got: range_token
+            })
+        }
+        let end = try!(parser.expression(Precedence::Min));
+        let step = if parser.peek().get_text() == tokens::By {
+            parser.consume();
+            Some(try!(parser.expression(Precedence::Min)))
+        }
+        else {
+            None
+        };
+        try!(parser.consume_type(TokenType::BeginBlock));
+        let block = try!(parser.block());
+        Ok(Statement::ForLoop(ForLoop::new(token, ident, start, range_token, end, step, block)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use lex::{Token, TokenData};
+    use parse::ast::*;
+    use parse::tests as parse_tests;
+
+    fn number(value: i64) -> Expression {
+        Expression::Literal(Literal::new(Token {
+            data: TokenData::IntLiteral(value),
+            .. Default::default()
+        }))
+    }
+
+    #[test]
+    fn it_parses_inclusive_range_with_step() {
+        let mut parser = parse_tests::parser("for i in 0..=10 by 2\n    i");
+        let ident = Identifier::new(Token {
+            data: TokenData::Ident,
+            text: Cow::Borrowed("i"),
+            .. Default::default()
+        });
+        let range_token = Token {
+            data: TokenData::Symbol,
+            text: Cow::Borrowed("..="),
+            .. Default::default()
+        };
+        let block = Block::new(vec![Statement::Expression(Expression::VariableRef(ident.clone()))]);
+        let expected = Statement::ForLoop(ForLoop::new(Token::default(), ident,
+            number(0), range_token, number(10), Some(number(2)), block));
+        parse_tests::statement_match(&expected, &parser.statement().unwrap());
+    }
+
+    #[test]
+    fn it_parses_exclusive_range() {
+        let mut parser = parse_tests::parser("for i in 0..n\n    i");
+        let stmt = parser.statement().unwrap();
+        match stmt {
+            Statement::ForLoop(ref for_loop) => {
+                assert!(!for_loop.is_inclusive());
+                assert!(for_loop.get_step().is_none());
+            },
+            other => panic!("Expected a for loop, got {:#?}", other)
+        }
+    }
+}
@@ -34,8 +34,7 @@ impl<T: Tokenizer> PrefixParser<Statement, T> for IfBlockParser {
             trace!("Parsed infix if true expr");
             try!(parser.consume_name(TokenType::Keyword, tokens::Else));
             if parser.peek().get_text() == tokens::If {
-                let error = "Cannot have an `else if` via inline if expression";
-                return Err(ParseError::LazyString(error.to_string()))
+                return Err(ParseError::InlineElseIf(parser.consume()))
             }
             let else_expr = try!(parser.expression(Precedence::Min));
             trace!("Parsed infix if false expr");
@@ -72,8 +71,7 @@ impl<T: Tokenizer> PrefixParser<Statement, T> for IfBlockParser {
                 let if_token = parser.consume();
                 let else_if_condition = try!(parser.expression(Precedence::Min));
                 if parser.peek().get_text() == tokens::InlineArrow {
-                    let error = "Cannot have an inline `else if` via if block";
-                    return Err(ParseError::LazyString(error.to_string()))
+                    return Err(ParseError::InlineElseIf(parser.consume()))
                 }
                 // Peel off begin block of else if
                 try!(parser.consume_type(TokenType::BeginBlock));
@@ -84,9 +82,7 @@ impl<T: Tokenizer> PrefixParser<Statement, T> for IfBlockParser {
                 conditionals.push(else_if_conditional);
             }
             else {
-                return Err(ParseError::LazyString(format!(
-                    "Got unexpected token {:?} after an else", parser.peek()
-                )));
+                return Err(ParseError::UnexpectedToken(parser.consume()))
             }
         }
     }
@@ -0,0 +1,25 @@
+//! Infinite `loop` parser.
+
+use lex::{tokens, Token, Tokenizer, TokenType};
+use parse::ast::*;
+use parse::{Parser, ParseResult};
+use parse::symbol::PrefixParser;
+
+/// Parses `loop` blocks, which run until a `break` or `return`.
+///
+/// # Examples
+/// ```text
+/// loop  \+    stmt*
+/// ^take ^take ^block
+/// ```
+#[derive(Debug)]
+pub struct LoopParser { }
+impl<T: Tokenizer> PrefixParser<Statement, T> for LoopParser {
+    fn parse(&self, parser: &mut Parser<T>, token: Token) -> ParseResult<Statement> {
+        debug_assert!(token.get_text() == tokens::Loop,
+            "Invalid token {:?} in LoopParser", token);
+        try!(parser.consume_type(TokenType::BeginBlock));
+        let block = try!(parser.block());
+        Ok(Statement::Loop(Loop::new(token, block)))
+    }
+}
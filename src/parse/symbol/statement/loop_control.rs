@@ -0,0 +1,30 @@
+//! `break` and `continue` statement parsers.
+
+use lex::{tokens, Token, Tokenizer};
+use parse::ast::*;
+use parse::{Parser, ParseResult};
+use parse::symbol::PrefixParser;
+
+/// Parses `break` statements.
+///
+/// Whether the `break` is inside a loop is left to the verifier.
+#[derive(Debug)]
+pub struct BreakParser { }
+impl<T: Tokenizer> PrefixParser<Statement, T> for BreakParser {
+    fn parse(&self, _parser: &mut Parser<T>, token: Token) -> ParseResult<Statement> {
+        debug_assert!(token.get_text() == tokens::Break,
+            "Invalid token {:?} in BreakParser", token);
+        Ok(Statement::Break(Break::new(token)))
+    }
+}
+
+/// Parses `continue` statements.
+#[derive(Debug)]
+pub struct ContinueParser { }
+impl<T: Tokenizer> PrefixParser<Statement, T> for ContinueParser {
+    fn parse(&self, _parser: &mut Parser<T>, token: Token) -> ParseResult<Statement> {
+        debug_assert!(token.get_text() == tokens::Continue,
+            "Invalid token {:?} in ContinueParser", token);
+        Ok(Statement::Continue(Continue::new(token)))
+    }
+}
@@ -1,7 +1,15 @@
 mod do_block;
 mod return_stmt;
 mod if_block;
+mod while_loop;
+mod for_loop;
+mod loop_block;
+mod loop_control;
 
 pub use self::do_block::DoBlockParser;
 pub use self::return_stmt::ReturnParser;
 pub use self::if_block::IfBlockParser;
+pub use self::while_loop::WhileLoopParser;
+pub use self::for_loop::ForLoopParser;
+pub use self::loop_block::LoopParser;
+pub use self::loop_control::{BreakParser, ContinueParser};
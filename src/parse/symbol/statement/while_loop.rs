@@ -0,0 +1,54 @@
+//! While loop parser.
+
+use lex::{tokens, Token, Tokenizer, TokenType};
+use parse::ast::*;
+use parse::{Parser, ParseResult};
+use parse::symbol::{PrefixParser, Precedence};
+
+/// Parses while loops.
+///
+/// # Examples
+/// ```text
+/// while expr \+ stmt* \-
+///  ^take ^expr ^block
+/// ```
+#[derive(Debug)]
+pub struct WhileLoopParser { }
+impl<T: Tokenizer> PrefixParser<Statement, T> for WhileLoopParser {
+    fn parse(&self, parser: &mut Parser<T>, token: Token) -> ParseResult<Statement> {
+        debug_assert!(token.get_text() == tokens::While,
+            "Invalid token {:?} in WhileLoopParser", token);
+        trace!("Parsing condition of while loop");
+        let condition = try!(parser.expression(Precedence::Min));
+        try!(parser.consume_type(TokenType::BeginBlock));
+        let block = try!(parser.block());
+        Ok(Statement::WhileLoop(WhileLoop::new(token, condition, block)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use lex::{Token, TokenData};
+    use parse::ast::*;
+    use parse::tests as parse_tests;
+
+    #[test]
+    fn it_parses_while_loop() {
+        let mut parser = parse_tests::parser("while x\n    break");
+        let keyword = |text| Token {
+            data: TokenData::Keyword,
+            text: Cow::Borrowed(text),
+            .. Default::default()
+        };
+        let condition = Expression::VariableRef(Identifier::new(Token {
+            data: TokenData::Ident,
+            text: Cow::Borrowed("x"),
+            .. Default::default()
+        }));
+        let block = Block::new(vec![Statement::Break(Break::new(keyword("break")))]);
+        let expected = Statement::WhileLoop(WhileLoop::new(keyword("while"), condition, block));
+        parse_tests::statement_match(&expected, &parser.statement().unwrap());
+    }
+}
@@ -3,7 +3,7 @@ use std::str::Chars;
 
 use lex::{Token, TokenData, TextLocation, Tokenizer, IterTokenizer};
 use lex::tests::make_tokenizer;
-use parse::{Parser};
+use parse::{Parser, ParseError, Definitions, Type, FnType};
 use parse::symbol::{self, Precedence};
 use parse::ast::*;
 
@@ -38,7 +38,7 @@ pub fn expression_match(expected: &Expression, got: &Expression) {
     match (expected, got) {
         (&Expression::Literal(ref lit), &Expression::Literal(ref lit2)) => {
             assert_eq!(lit.get_value(), lit2.get_value(),
-                "Expression mismatch in literals: expected {}, got {}",
+                "Expression mismatch in literals: expected {:?}, got {:?}",
                 lit.get_value(), lit2.get_value());
         },
         (&Expression::VariableRef(ref var), &Expression::VariableRef(ref var2)) => {
@@ -142,6 +142,37 @@ pub fn statement_match(expected: &Statement, got: &Statement) {
                 }
             }
         },
+        (&Statement::WhileLoop(ref left), &Statement::WhileLoop(ref right)) => {
+            println!("Checking while loops");
+            expression_match(left.get_condition(), right.get_condition());
+            block_match(left.get_block(), right.get_block());
+        },
+        (&Statement::ForLoop(ref left), &Statement::ForLoop(ref right)) => {
+            println!("Checking for loops");
+            assert!(left.get_ident().get_name() == right.get_ident().get_name()
+                    && left.is_inclusive() == right.is_inclusive(),
+                "For loop mismatch:\nExpected: {:#?}\nGot: {:#?}", left, right);
+            expression_match(left.get_start(), right.get_start());
+            expression_match(left.get_end(), right.get_end());
+            match (left.get_step(), right.get_step()) {
+                (Some(left_step), Some(right_step)) => expression_match(left_step, right_step),
+                (None, None) => { },
+                (ref left_step, ref right_step) => {
+                    panic!("For loop steps did not match:\nExpected {:#?}\nGot {:#?}",
+                        left_step, right_step);
+                }
+            }
+            block_match(left.get_block(), right.get_block());
+        },
+        (&Statement::Loop(ref left), &Statement::Loop(ref right)) => {
+            println!("Checking loop blocks");
+            block_match(left.get_block(), right.get_block());
+        },
+        (&Statement::Break(_), &Statement::Break(_)) => { },
+        (&Statement::Continue(_), &Statement::Continue(_)) => { },
+        (&Statement::Error(ref left), &Statement::Error(ref right)) => {
+            token_eq(left.clone(), right.clone());
+        },
         (ref left, ref right) => {
             panic!("Statements did not match:\nExpected {:#?}\nGot: {:#?}",
                 left, right);
@@ -159,6 +190,148 @@ pub fn statement_match(expected: &Statement, got: &Statement) {
     }
 }
 
+fn ident_expr(name: &'static str) -> Expression {
+    Expression::VariableRef(Identifier::new(Token {
+        data: TokenData::Ident,
+        text: Cow::Borrowed(name),
+        .. Default::default()
+    }))
+}
+
+fn keyword_token(text: &'static str) -> Token {
+    Token {
+        data: TokenData::Keyword,
+        text: Cow::Borrowed(text),
+        .. Default::default()
+    }
+}
+
+#[test]
+fn it_parses_logical_operators_by_precedence() {
+    let mut parser = parser("not a == b and c or d");
+    let equals = Expression::BinaryOp(BinaryOperation::new(Operator::Equality,
+        Token { data: TokenData::Symbol, text: Cow::Borrowed("=="), .. Default::default() },
+        Box::new(ident_expr("a")), Box::new(ident_expr("b"))));
+    let not = Expression::UnaryOp(UnaryOperation::new(Operator::Not,
+        keyword_token("not"), Box::new(equals)));
+    let and = Expression::BinaryOp(BinaryOperation::new(Operator::And,
+        keyword_token("and"), Box::new(not), Box::new(ident_expr("c"))));
+    let expected = Expression::BinaryOp(BinaryOperation::new(Operator::Or,
+        keyword_token("or"), Box::new(and), Box::new(ident_expr("d"))));
+    let got = parser.expression(Precedence::Min).unwrap();
+    expression_match(&expected, &got);
+}
+
+#[test]
+fn it_tracks_spans_of_expressions() {
+    let mut parser = parser("foo(a, b: c + 10)");
+    let got = parser.expression(Precedence::Min).unwrap();
+    let span = got.span();
+    assert_eq!(span.start, TextLocation { index: 0, line: 0, column: 0 });
+    assert_eq!(span.end, TextLocation { index: 17, line: 0, column: 17 });
+    match got {
+        Expression::FnCall(ref call) => match *call.get_args() {
+            FnCallArgs::Arguments(ref args) => {
+                let arg_span = args[1].get_expr().expect("Argument has a value").span();
+                assert_eq!((arg_span.start.index, arg_span.end.index), (10, 16));
+            },
+            ref other => panic!("Expected named arguments, got {:#?}", other)
+        },
+        ref other => panic!("Expected a function call, got {:#?}", other)
+    }
+}
+
+#[test]
+fn it_tracks_spans_of_items() {
+    let mut parser = parser("fn f()\n    let x = 1\n    x + 2\n");
+    let unit = parser.unit();
+    let span = unit.get_items()[0].span();
+    assert_eq!(span.start, TextLocation { index: 0, line: 0, column: 0 });
+    assert_eq!(span.end, TextLocation { index: 30, line: 2, column: 9 });
+}
+
+#[test]
+fn it_verifies_units_incrementally() {
+    let (unit, table, _errors) = parser("fn double(x: Int) -> Int => x * 2")
+        .parse_unit().unwrap().decompose();
+    let double_index = match unit.get_items()[0] {
+        Item::FnDeclaration(ref decl) => decl.get_name().get_index(),
+        ref other => panic!("Expected fn double, got {:#?}", other)
+    };
+    let mut definitions = Definitions::new();
+    definitions.define(table[&double_index].clone());
+    let global = Token { data: TokenData::Ident, text: Cow::Borrowed("g"), .. Default::default() };
+    definitions.define_global(global, false, Type::Int);
+
+    let program = parser("fn quad(x) => double(x: double(x)) + g")
+        .parse_unit_incremental(&definitions)
+        .expect("Could not use earlier definitions");
+    let (unit, table, errors) = program.decompose();
+    assert!(errors.get_warnings().is_empty(), "Unexpected warnings {:#?}", errors.get_warnings());
+    let quad_index = match unit.get_items()[0] {
+        Item::FnDeclaration(ref decl) => decl.get_name().get_index(),
+        ref other => panic!("Expected fn quad, got {:#?}", other)
+    };
+    assert!(quad_index != double_index);
+    assert!(quad_index != definitions.get("g").unwrap().get_index().clone());
+    assert_eq!(table[&quad_index].get_type(),
+        &Type::Fn(FnType::new(Box::new(Type::Int), vec![("x".into(), Type::Int)])));
+
+    match parser("fn double(y) => y").parse_unit_incremental(&definitions) {
+        Err(ParseError::VerifierError { .. }) => { },
+        other => panic!("Expected double to be already declared, got {:#?}", other)
+    }
+}
+
+#[test]
+fn it_verifies_calls_to_host_functions() {
+    let mut definitions = Definitions::new();
+    definitions.define_fn("square",
+        FnType::new(Box::new(Type::Float), vec![("x".into(), Type::Float)]));
+    let program = parser("fn area(r) => 3.14 * square(x: r)")
+        .parse_unit_incremental(&definitions)
+        .expect("Could not call host function");
+    let (unit, table, _errors) = program.decompose();
+    let area_index = match unit.get_items()[0] {
+        Item::FnDeclaration(ref decl) => decl.get_name().get_index(),
+        ref other => panic!("Expected fn area, got {:#?}", other)
+    };
+    assert_eq!(table[&area_index].get_type(),
+        &Type::Fn(FnType::new(Box::new(Type::Float), vec![("r".into(), Type::Float)])));
+
+    match parser("fn f() => square(y: 1.0)").parse_unit_incremental(&definitions) {
+        Err(ParseError::VerifierError { .. }) => { },
+        other => panic!("Expected square not to take y, got {:#?}", other)
+    }
+}
+
+#[test]
+fn it_recovers_from_syntax_errors() {
+    let mut parser = parser("fn f()\n    let = 1\n    let x = 2\n    x\nfn g(a b) => a\nfn h() => 1");
+    let unit = parser.unit();
+    assert_eq!(parser.get_errors().len(), 2,
+        "Expected two syntax errors, got {:#?}", parser.get_errors());
+    let items = unit.get_items();
+    assert_eq!(items.len(), 3, "Expected three items, got {:#?}", items);
+    match items[0] {
+        Item::FnDeclaration(ref decl) => {
+            let stmts = decl.get_block().get_stmts();
+            assert_eq!(stmts.len(), 3, "Expected three statements, got {:#?}", stmts);
+            statement_match(&Statement::Error(keyword_token("let")), &stmts[0]);
+            statement_match(&Statement::Expression(ident_expr("x")), &stmts[2]);
+        },
+        ref other => panic!("Expected fn f, got {:#?}", other)
+    }
+    match items[1] {
+        Item::Error(ref token) => token_eq(keyword_token("fn"), token.clone()),
+        ref other => panic!("Expected an error in place of fn g, got {:#?}", other)
+    }
+    match items[2] {
+        Item::FnDeclaration(ref decl) => assert_eq!(decl.get_name().get_name(), "h"),
+        ref other => panic!("Expected fn h, got {:#?}", other)
+    }
+}
+
 #[test]
 fn parse_example() {
     let inputs = &[
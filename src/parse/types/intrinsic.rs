@@ -0,0 +1,38 @@
+//! Functions which are built into protosnirk
+
+use parse::types::{Type, FnType};
+
+/// A function provided by the compiler instead of being declared in source.
+///
+/// Intrinsics take one argument, which doesn't need to be named:
+/// `toFloat(count)` converts `count` whatever its name is.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Intrinsic {
+    /// `toFloat`: convert an `Int` to a `Float`
+    ToFloat,
+    /// `toInt`: convert a `Float` to an `Int`, rounding towards zero
+    ToInt
+}
+impl Intrinsic {
+    /// Get the intrinsic with the given name, if there is one.
+    pub fn from_name(name: &str) -> Option<Intrinsic> {
+        match name {
+            "toFloat" => Some(Intrinsic::ToFloat),
+            "toInt" => Some(Intrinsic::ToInt),
+            _ => None
+        }
+    }
+    pub fn get_name(self) -> &'static str {
+        match self {
+            Intrinsic::ToFloat => "toFloat",
+            Intrinsic::ToInt => "toInt"
+        }
+    }
+    pub fn get_type(self) -> FnType {
+        let (arg, result) = match self {
+            Intrinsic::ToFloat => (Type::Int, Type::Float),
+            Intrinsic::ToInt => (Type::Float, Type::Int)
+        };
+        FnType::new(Box::new(result), vec![("value".to_string(), arg)])
+    }
+}
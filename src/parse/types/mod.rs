@@ -1,3 +1,7 @@
 mod types;
+mod substitution;
+mod intrinsic;
 
 pub use self::types::*;
+pub use self::substitution::Substitution;
+pub use self::intrinsic::Intrinsic;
@@ -0,0 +1,148 @@
+//! Unification of types containing type variables.
+
+use std::collections::HashMap;
+
+use parse::types::{Type, FnType};
+
+/// The types which type variables have been unified with.
+///
+/// Type variables are given out by `fresh()` to stand in for types
+/// which haven't been written, and are bound to other types by `unify()`
+/// as the types of the expressions using them are found.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Substitution {
+    bindings: HashMap<usize, Type>,
+    next_variable: usize
+}
+impl Substitution {
+    pub fn new() -> Substitution {
+        Substitution::default()
+    }
+
+    /// Create a new, unbound type variable.
+    pub fn fresh(&mut self) -> Type {
+        let variable = self.next_variable;
+        self.next_variable += 1;
+        Type::Variable(variable)
+    }
+
+    /// Replace bound type variables in the given type with their types.
+    ///
+    /// Unbound type variables are left in the result.
+    pub fn resolve(&self, type_: &Type) -> Type {
+        match *type_ {
+            Type::Variable(variable) => match self.bindings.get(&variable) {
+                Some(bound) => self.resolve(bound),
+                None => type_.clone()
+            },
+            Type::Fn(ref fn_type) => {
+                let args = fn_type.get_args().iter()
+                    .map(|&(ref name, ref arg)| (name.clone(), self.resolve(arg)))
+                    .collect();
+                Type::Fn(FnType::new(Box::new(self.resolve(fn_type.get_return())), args))
+            },
+            ref other => other.clone()
+        }
+    }
+
+    /// Resolve the type, replacing any unbound variables with `default`.
+    pub fn resolve_or(&self, type_: &Type, default: &Type) -> Type {
+        match self.resolve(type_) {
+            Type::Variable(_) => default.clone(),
+            Type::Fn(fn_type) => {
+                let args = fn_type.get_args().iter()
+                    .map(|&(ref name, ref arg)| (name.clone(), self.resolve_or(arg, default)))
+                    .collect();
+                let return_type = self.resolve_or(fn_type.get_return(), default);
+                Type::Fn(FnType::new(Box::new(return_type), args))
+            },
+            other => other
+        }
+    }
+
+    /// Make the two types equal by binding the type variables in them.
+    ///
+    /// Returns `false` if the types can't be made equal, in which case
+    /// the variables in them may have been partially bound.
+    pub fn unify(&mut self, left: &Type, right: &Type) -> bool {
+        let left = self.resolve(left);
+        let right = self.resolve(right);
+        match (left, right) {
+            (Type::Variable(left_var), Type::Variable(right_var)) => {
+                if left_var != right_var {
+                    self.bindings.insert(left_var, Type::Variable(right_var));
+                }
+                true
+            },
+            (Type::Variable(variable), other) | (other, Type::Variable(variable)) => {
+                if self.occurs(variable, &other) {
+                    return false
+                }
+                self.bindings.insert(variable, other);
+                true
+            },
+            (Type::Fn(left_fn), Type::Fn(right_fn)) => {
+                if left_fn.get_args().len() != right_fn.get_args().len() {
+                    return false
+                }
+                for (&(ref left_name, ref left_arg), &(ref right_name, ref right_arg)) in
+                        left_fn.get_args().iter().zip(right_fn.get_args()) {
+                    if left_name != right_name || !self.unify(left_arg, right_arg) {
+                        return false
+                    }
+                }
+                self.unify(left_fn.get_return(), right_fn.get_return())
+            },
+            (left, right) => left == right
+        }
+    }
+
+    /// Whether the type variable appears in the (resolved) type.
+    fn occurs(&self, variable: usize, type_: &Type) -> bool {
+        match *type_ {
+            Type::Variable(other) => other == variable,
+            Type::Fn(ref fn_type) => {
+                fn_type.get_args().iter().any(|&(_, ref arg)| self.occurs(variable, arg))
+                    || self.occurs(variable, fn_type.get_return())
+            },
+            _ => false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use parse::types::{Type, FnType};
+    use super::Substitution;
+
+    #[test]
+    fn it_binds_variables() {
+        let mut substitution = Substitution::new();
+        let first = substitution.fresh();
+        let second = substitution.fresh();
+        assert!(substitution.unify(&first, &second));
+        assert!(substitution.unify(&second, &Type::Bool));
+        assert_eq!(substitution.resolve(&first), Type::Bool);
+        assert!(!substitution.unify(&first, &Type::Float));
+    }
+
+    #[test]
+    fn it_unifies_function_types() {
+        let mut substitution = Substitution::new();
+        let arg = substitution.fresh();
+        let ret = substitution.fresh();
+        let generic = Type::Fn(FnType::new(Box::new(ret.clone()), vec![("a".into(), arg.clone())]));
+        let concrete = Type::Fn(FnType::new(Box::new(Type::Float),
+                                            vec![("a".into(), Type::String)]));
+        assert!(substitution.unify(&generic, &concrete));
+        assert_eq!(substitution.resolve(&generic), concrete);
+    }
+
+    #[test]
+    fn it_does_not_bind_variables_to_themselves() {
+        let mut substitution = Substitution::new();
+        let variable = substitution.fresh();
+        let recursive = Type::Fn(FnType::new(Box::new(variable.clone()), vec![]));
+        assert!(!substitution.unify(&variable, &recursive));
+    }
+}
@@ -1,18 +1,48 @@
 //! Definition of types in protosnirk
 
 use std::collections::HashMap;
+use std::fmt;
 
 /// Representation of types in protosnirk
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum Type {
     /// `()`
     Empty,
-    /// Standard type for now
+    /// Floating point number, the default for numbers
     Float,
+    /// 64-bit signed integer
+    Int,
+    /// Immutable text, such as a string literal
+    String,
+    /// `true` or `false`, the result of comparisons
+    Bool,
     /// Function - only used in declarations
-    Fn(FnType)
+    Fn(FnType),
+    /// Placeholder for a type which is still being inferred
+    Variable(usize)
 }
 impl Type {
+    /// Get the type with the given name, as used in type annotations.
+    pub fn from_name(name: &str) -> Option<Type> {
+        match name {
+            "Float" => Some(Type::Float),
+            "Int" => Some(Type::Int),
+            "String" => Some(Type::String),
+            "Bool" => Some(Type::Bool),
+            _ => None
+        }
+    }
+    /// Whether arithmetic can be done on values of this type.
+    pub fn is_numeric(&self) -> bool {
+        *self == Type::Float || *self == Type::Int
+    }
+    /// Whether values of this type can be checked for equality.
+    ///
+    /// `String`s are pointers in compiled code, so comparing them would
+    /// compare their addresses rather than their text.
+    pub fn is_comparable(&self) -> bool {
+        self.is_numeric() || *self == Type::Bool
+    }
     pub fn expect_fn(self) -> FnType {
         match self {
             Type::Fn(inner) => inner,
@@ -47,3 +77,40 @@ impl FnType {
         return None
     }
 }
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Type::Empty => write!(f, "()"),
+            Type::Float => write!(f, "Float"),
+            Type::Int => write!(f, "Int"),
+            Type::String => write!(f, "String"),
+            Type::Bool => write!(f, "Bool"),
+            Type::Fn(ref fn_type) => write!(f, "{}", fn_type),
+            Type::Variable(index) => write!(f, "?{}", index)
+        }
+    }
+}
+
+impl fmt::Display for FnType {
+    /// Written as the signature of a declaration, `fn(x: Float) -> Float`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let args: Vec<String> = self.args.iter()
+            .map(|&(ref name, ref type_)| format!("{}: {}", name, type_))
+            .collect();
+        write!(f, "fn({}) -> {}", args.join(", "), self.return_type)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Type, FnType};
+
+    #[test]
+    fn it_displays_types_as_written() {
+        let fn_type = FnType::new(Box::new(Type::Float),
+            vec![("x".into(), Type::Float), ("n".into(), Type::Int)]);
+        assert_eq!(Type::Fn(fn_type).to_string(), "fn(x: Float, n: Int) -> Float");
+        assert_eq!(Type::Empty.to_string(), "()");
+    }
+}
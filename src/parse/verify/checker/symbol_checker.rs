@@ -4,18 +4,24 @@ use std::ops::Deref;
 use lex::Token;
 use parse::ASTVisitor;
 use parse::ast::*;
-use parse::verify::{ErrorCollector, VerifyError, Symbol};
+use parse::verify::{ErrorCollector, VerifyError, Symbol, Definitions};
 use parse::verify::scope::{ScopeIndex, SymbolTable, SymbolTableBuilder};
-use parse::types::{Type, FnType};
+use parse::types::{Type, FnType, Intrinsic};
+use diagnostics::ErrorCode;
 
 /// Builds up the symbol table for a parse tree
 /// and reports variable declaration and mutability errors.
+///
+/// Also reports `break` and `continue` outside of loops. Symbols are given
+/// the types they are annotated with, the types of unannotated variables
+/// are filled in by the `TypeChecker`.
 #[derive(Debug, Clone, PartialEq, Default)]
 pub struct SymbolTableChecker {
     symbol_table: SymbolTable,
     table_builder: SymbolTableBuilder,
     current_index: ScopeIndex,
-    errors: ErrorCollector
+    errors: ErrorCollector,
+    loop_depth: usize
 }
 impl SymbolTableChecker {
     pub fn new(errors: ErrorCollector) -> SymbolTableChecker {
@@ -23,12 +29,69 @@ impl SymbolTableChecker {
             symbol_table: SymbolTable::new(),
             table_builder: SymbolTableBuilder::new(),
             current_index: ScopeIndex::default(),
-            errors: errors
+            errors: errors,
+            loop_depth: 0
+        }
+    }
+    /// Create a checker for code which can use symbols accepted by
+    /// earlier runs of the verifier.
+    pub fn with_definitions(definitions: &Definitions, errors: ErrorCollector) -> SymbolTableChecker {
+        let mut table_builder = SymbolTableBuilder::new();
+        table_builder.new_scope();
+        for (name, index) in definitions.get_names() {
+            table_builder.define_global(name.clone(), index.clone());
+        }
+        SymbolTableChecker {
+            symbol_table: definitions.get_symbols().clone(),
+            table_builder: table_builder,
+            current_index: definitions.get_next_index().clone(),
+            errors: errors,
+            loop_depth: 0
         }
     }
     pub fn decompose(self) -> (SymbolTable, ErrorCollector) {
         (self.symbol_table, self.errors)
     }
+
+    /// Check a call to a built in function.
+    ///
+    /// Intrinsics take one argument, which can be given under any name.
+    fn check_intrinsic_call(&mut self, fn_call: &FnCall) {
+        if fn_call.get_args().len() != 1 {
+            let err_text = format!("Function {}: expected 1 args, got {}",
+                fn_call.get_text(), fn_call.get_args().len());
+            let err = VerifyError::new(ErrorCode::WrongArgCount,
+                fn_call.get_token().clone(), vec![], err_text);
+            self.errors.add_error(err);
+        }
+        match *fn_call.get_args() {
+            FnCallArgs::SingleExpr(ref expr) => self.check_expression(expr),
+            FnCallArgs::Arguments(ref args) => {
+                for call_arg in args {
+                    match call_arg.get_expr() {
+                        Some(expr) => self.check_expression(expr),
+                        None => self.check_var_ref(call_arg.get_name())
+                    }
+                }
+            }
+        }
+    }
+
+    /// Get the type named by a type annotation, reporting unknown types.
+    fn resolve_type(&mut self, type_expr: &TypeExpression) -> Option<Type> {
+        match *type_expr {
+            TypeExpression::Named(ref named) => {
+                let found = Type::from_name(named.get_name());
+                if found.is_none() {
+                    let err_text = format!("Unknown type {}", named.get_name());
+                    self.errors.add_error(VerifyError::new(ErrorCode::UnknownType,
+                        named.get_token().clone(), vec![], err_text));
+                }
+                found
+            }
+        }
+    }
+
 }
 impl ASTVisitor for SymbolTableChecker {
     fn check_declaration(&mut self, decl: &Declaration) {
@@ -40,17 +103,21 @@ impl ASTVisitor for SymbolTableChecker {
         if let Some(declared_index) = self.table_builder.get(decl.get_name()).cloned() {
             let declared_at = self.symbol_table[&declared_index].get_declaration().clone();
             // Add previous declaration
-            let references: Vec<Token> = vec![declared_at];
+            let references = vec![(declared_at, "previously declared here")];
             let err_text = format!("Variable {} is already declared", decl.get_name());
-            self.errors.add_error(VerifyError::new(decl.get_ident().get_token().clone(), references, err_text));
+            self.errors.add_error(VerifyError::new(ErrorCode::AlreadyDeclared,
+                decl.get_ident().get_token().clone(), references, err_text));
         } else {
+            let var_type = decl.get_type_expr()
+                .and_then(|expr| self.resolve_type(expr))
+                .unwrap_or(Type::Float);
             let var_index = self.current_index.clone();
             self.current_index.increment();
             trace!("Created index {:?} for declared var {}", var_index, decl.get_name());
             decl.get_ident().set_index(var_index.clone());
             self.table_builder.define_local(decl.get_name().to_string(), var_index.clone());
             self.symbol_table.insert(var_index.clone(),
-                Symbol::from_declaration(decl, var_index));
+                Symbol::from_declaration(decl, var_index, var_type));
         }
     }
 
@@ -64,7 +131,8 @@ impl ASTVisitor for SymbolTableChecker {
         }
         else {
             let err_text = format!("Variable {} was not declared", var_ref.get_name());
-            self.errors.add_error(VerifyError::new(var_ref.token.clone(), vec![], err_text));
+            self.errors.add_error(VerifyError::new(ErrorCode::UndeclaredVariable,
+                var_ref.token.clone(), vec![], err_text));
         }
     }
 
@@ -76,9 +144,10 @@ impl ASTVisitor for SymbolTableChecker {
             if !self.symbol_table[index].is_mutable() {
                 let err_text = format!("Variable {} was not declared mutable", assign.lvalue.get_name());
                 let references = vec![
-                    self.symbol_table[index].get_declaration().clone(),
+                    (self.symbol_table[index].get_declaration().clone(), "declared immutable here"),
                 ];
-                self.errors.add_error(VerifyError::new(assign.lvalue.token.clone(), references, err_text));
+                self.errors.add_error(VerifyError::new(ErrorCode::ReassignImmutable,
+                    assign.lvalue.token.clone(), references, err_text));
             }
             else {
                 self.symbol_table.get_mut(index)
@@ -87,7 +156,8 @@ impl ASTVisitor for SymbolTableChecker {
         }
         else {
             let err_text = format!("Variable {} was not declared", assign.lvalue.get_name());
-            self.errors.add_error(VerifyError::new(assign.lvalue.token.clone(), vec![], err_text));
+            self.errors.add_error(VerifyError::new(ErrorCode::UndeclaredVariable,
+                assign.lvalue.token.clone(), vec![], err_text));
         }
         self.check_expression(&assign.rvalue);
     }
@@ -109,15 +179,24 @@ impl ASTVisitor for SymbolTableChecker {
         if let Some(index) = self.table_builder.get(fn_declaration.get_name().get_name()).cloned() {
             let declared_at = self.symbol_table[&index].get_declaration().clone();
             // Add declaration to error
-            let references = vec![declared_at];
+            let references = vec![(declared_at, "previously declared here")];
             let err_text = format!("Function {} is already declared",
                 fn_declaration.get_name().get_name());
-            self.errors.add_error(VerifyError::new(fn_declaration.get_name().get_token().clone(),
+            self.errors.add_error(VerifyError::new(ErrorCode::AlreadyDeclared,
+                                                   fn_declaration.get_name().get_token().clone(),
                                                    references,
                                                    err_text));
             // _Should_ return here
             // but let's see if checking the function ends up being helpful
         }
+        else if Intrinsic::from_name(fn_declaration.get_name().get_name()).is_some() {
+            let err_text = format!("Function {} is built in",
+                fn_declaration.get_name().get_name());
+            self.errors.add_error(VerifyError::new(ErrorCode::BuiltinRedeclared,
+                                                   fn_declaration.get_name().get_token().clone(),
+                                                   vec![],
+                                                   err_text));
+        }
 
         let fn_index = self.current_index.clone();
         self.current_index.push();
@@ -125,18 +204,22 @@ impl ASTVisitor for SymbolTableChecker {
         // Declared function info
         let mut param_types = Vec::new();
 
-        for param in fn_declaration.get_args() {
+        for &(ref param, ref param_type_expr) in fn_declaration.get_args() {
             trace!("Checking parameter {}", param.get_name());
-            // All parameters are floats for now
-            param_types.push((param.get_name().to_string(), Type::Float));
+            // Unannotated parameters are floats for now
+            let param_type = param_type_expr.as_ref()
+                .and_then(|expr| self.resolve_type(expr))
+                .unwrap_or(Type::Float);
+            param_types.push((param.get_name().to_string(), param_type.clone()));
             // Check standard symbol table for any conflicts.
             // They're probably only present in other param names.
             if let Some(declared_index) = self.table_builder.get(param.get_name()).cloned() {
                 let declared_at = self.symbol_table[&declared_index].get_declaration().clone();
                 // Add previous declaration
-                let references = vec![declared_at];
+                let references = vec![(declared_at, "previously declared here")];
                 let err_text = format!("Argument {} is already declared", param.get_name());
-                self.errors.add_error(VerifyError::new(param.get_token().clone(), references, err_text));
+                self.errors.add_error(VerifyError::new(ErrorCode::AlreadyDeclared,
+                    param.get_token().clone(), references, err_text));
                 // We will keep parsing arg params after registering duplicate
                 continue
             }
@@ -146,10 +229,13 @@ impl ASTVisitor for SymbolTableChecker {
             param.set_index(var_index.clone());
             self.table_builder.define_local(param.get_name().to_string(), var_index.clone());
             self.symbol_table.insert(var_index.clone(),
-                Symbol::from_parameter(param, var_index));
+                Symbol::from_parameter(param, var_index, param_type));
         }
         // Add the function to the symbol table
-        let fn_type = Type::Fn(FnType::new(Box::new(Type::Float), param_types));
+        let return_type = fn_declaration.get_return_type()
+            .and_then(|expr| self.resolve_type(expr))
+            .unwrap_or(Type::Float);
+        let fn_type = Type::Fn(FnType::new(Box::new(return_type), param_types));
         self.table_builder.define_global(fn_declaration.get_name().get_name().into(),
                                          fn_index.clone());
         self.symbol_table.insert(fn_index.clone(),
@@ -170,6 +256,9 @@ impl ASTVisitor for SymbolTableChecker {
 
     fn check_fn_call(&mut self, fn_call: &FnCall) {
         trace!("Checking function call of {}", fn_call.get_name().get_name());
+        if Intrinsic::from_name(fn_call.get_text()).is_some() {
+            return self.check_intrinsic_call(fn_call)
+        }
         // Should also get some better naming conventions here
         if let Some(fn_index) = self.table_builder.get(fn_call.get_text()).cloned() {
             trace!("Found function info of {}", fn_call.get_text());
@@ -184,7 +273,8 @@ impl ASTVisitor for SymbolTableChecker {
                     // This is how Rust does it but we can do better.
                     let err_text = format!("Function {}: expected {} args, got {}",
                         fn_call.get_name().get_name(), declared_len, invoked_len);
-                    let err = VerifyError::new(fn_call.get_token().clone(), vec![], err_text);
+                    let err = VerifyError::new(ErrorCode::WrongArgCount,
+                        fn_call.get_token().clone(), vec![], err_text);
                     self.errors.add_error(err);
                 }
                 match *fn_call.get_args() {
@@ -193,7 +283,8 @@ impl ASTVisitor for SymbolTableChecker {
                             // TODO could also provide references
                             let err_text = format!("Function {}: expected {} args, got 1",
                                 fn_call.get_name().get_name(), declared_len);
-                            let err = VerifyError::new(fn_call.get_token().clone(), vec![], err_text);
+                            let err = VerifyError::new(ErrorCode::WrongArgCount,
+                                fn_call.get_token().clone(), vec![], err_text);
                             self.errors.add_error(err);
                         }
                         self.check_expression(expr);
@@ -219,8 +310,10 @@ impl ASTVisitor for SymbolTableChecker {
                                 if &declared_type != call_type {
                                     let err_text = format!("Expected type {:?} for arg {} of {}, got {:?}",
                                         declared_type, call_arg.get_text(), fn_call.get_text(), call_type);
-                                    let refs = vec![call_arg.get_name().get_token().clone(), fn_info.get_declaration().clone()];
-                                    let err = VerifyError::new(fn_call.get_token().clone(), refs, err_text);
+                                    let refs = vec![(call_arg.get_name().get_token().clone(), "argument given here"),
+                                                    (fn_info.get_declaration().clone(), "function declared here")];
+                                    let err = VerifyError::new(ErrorCode::TypeMismatch,
+                                        fn_call.get_token().clone(), refs, err_text);
                                     self.errors.add_error(err);
                                 }
                                 */
@@ -228,24 +321,102 @@ impl ASTVisitor for SymbolTableChecker {
                             }
                             else {
                                 let err_text = format!("Unknown parameter {}", call_arg.get_text());
-                                let refs = vec![call_arg.get_name().get_token().clone()];
-                                let err = VerifyError::new(fn_call.get_token().clone(), refs, err_text);
+                                let refs = vec![(call_arg.get_name().get_token().clone(), "argument given here")];
+                                let err = VerifyError::new(ErrorCode::UnknownParameter,
+                                    fn_call.get_token().clone(), refs, err_text);
                                 self.errors.add_error(err);
                             }
                         }
                     }
                 }
             }
+            else {
+                let err_text = format!("{} is not a function", fn_call.get_text());
+                let refs = vec![(fn_info.get_declaration().clone(), "declared here")];
+                let err = VerifyError::new(ErrorCode::NotAFunction,
+                    fn_call.get_token().clone(), refs, err_text);
+                self.errors.add_error(err);
+                // The arguments are still checked, so their variables count as used
+                match *fn_call.get_args() {
+                    FnCallArgs::SingleExpr(ref expr) => self.check_expression(expr),
+                    FnCallArgs::Arguments(ref args) => for call_arg in args {
+                        match call_arg.get_expr() {
+                            Some(expr) => self.check_expression(expr),
+                            None => self.check_var_ref(call_arg.get_name())
+                        }
+                    }
+                }
+            }
             self.symbol_table.get_mut(&fn_call.get_name().get_index())
                 .map(Symbol::set_used);
         }
         else {
             let err_text = format!("Unknown function {}", fn_call.get_text());
-            let err = VerifyError::new(fn_call.get_token().clone(), vec![], err_text);
+            let err = VerifyError::new(ErrorCode::UnknownFunction,
+                fn_call.get_token().clone(), vec![], err_text);
             self.errors.add_error(err);
         }
     }
 
+    fn check_while_loop(&mut self, while_: &WhileLoop) {
+        self.check_expression(while_.get_condition());
+        self.loop_depth += 1;
+        self.check_block(while_.get_block());
+        self.loop_depth -= 1;
+    }
+
+    fn check_for_loop(&mut self, for_: &ForLoop) {
+        // The range is checked outside of the loop variable's scope
+        self.check_expression(for_.get_start());
+        self.check_expression(for_.get_end());
+        if let Some(step) = for_.get_step() {
+            self.check_expression(step);
+        }
+
+        self.current_index.push();
+        self.table_builder.new_scope();
+        let ident = for_.get_ident();
+        let var_index = self.current_index.clone();
+        self.current_index.increment();
+        trace!("Created index {:?} for loop var {}", var_index, ident.get_name());
+        ident.set_index(var_index.clone());
+        self.table_builder.define_local(ident.get_name().to_string(), var_index.clone());
+        self.symbol_table.insert(var_index.clone(),
+            Symbol::from_loop_variable(ident, var_index, Type::Float));
+
+        // Like function parameters, the loop variable shares the body's scope
+        self.loop_depth += 1;
+        for stmt in &for_.get_block().statements {
+            self.check_statement(&stmt);
+        }
+        self.loop_depth -= 1;
+        self.table_builder.pop();
+        self.current_index.pop();
+        self.current_index.increment();
+    }
+
+    fn check_loop(&mut self, loop_: &Loop) {
+        self.loop_depth += 1;
+        self.check_block(loop_.get_block());
+        self.loop_depth -= 1;
+    }
+
+    fn check_break(&mut self, break_: &Break) {
+        if self.loop_depth == 0 {
+            let err_text = "Cannot `break` outside of a loop".to_string();
+            self.errors.add_error(VerifyError::new(ErrorCode::LoopControlOutsideLoop,
+                break_.get_token().clone(), vec![], err_text));
+        }
+    }
+
+    fn check_continue(&mut self, continue_: &Continue) {
+        if self.loop_depth == 0 {
+            let err_text = "Cannot `continue` outside of a loop".to_string();
+            self.errors.add_error(VerifyError::new(ErrorCode::LoopControlOutsideLoop,
+                continue_.get_token().clone(), vec![], err_text));
+        }
+    }
+
     fn check_unit(&mut self, unit: &Unit) {
         self.table_builder.new_scope();
         for item in unit.get_items() {
@@ -258,10 +429,14 @@ impl ASTVisitor for SymbolTableChecker {
 mod tests {
     use std::borrow::Cow;
 
-    use lex::{Token, TokenData, TextLocation};
+    use lex::{Token, TokenData, TextLocation, Span};
     use parse::ASTVisitor;
+    use parse::ast::Unit;
     use parse::tests::parser;
     use parse::verify::{ErrorCollector, VerifyError};
+    use parse::verify::Source;
+    use parse::types::Type;
+    use diagnostics::ErrorCode;
     use super::SymbolTableChecker;
 
     #[test]
@@ -273,17 +448,23 @@ mod tests {
         sym_checker.check_block(&block);
         let (_table, verifier) = sym_checker.decompose();
         let expected = vec![
-            VerifyError::new(Token {
-                    location: TextLocation { index: 14, line: 0, column: 14 },
+            VerifyError::new(ErrorCode::AlreadyDeclared, Token {
+                    span: Span {
+                        start: TextLocation { index: 14, line: 0, column: 14 },
+                        end: TextLocation { index: 15, line: 0, column: 15 }
+                    },
                     text: Cow::Borrowed("x"),
                     data: TokenData::Ident
                 },
                 vec![
-                    Token {
-                        location: TextLocation { index: 4, line: 0, column: 4 },
+                    (Token {
+                        span: Span {
+                            start: TextLocation { index: 4, line: 0, column: 4 },
+                            end: TextLocation { index: 5, line: 0, column: 5 }
+                        },
                         text: Cow::Borrowed("x"),
                         data: TokenData::Ident
-                    }
+                    }, "previously declared here")
                 ],
                 "Variable x is already declared".into())
         ];
@@ -299,8 +480,11 @@ mod tests {
         sym_checker.check_block(&block);
         let (_table, verifier) = sym_checker.decompose();
         let expected = vec![
-            VerifyError::new(Token {
-                location: TextLocation { index: 18, line: 0, column: 18 },
+            VerifyError::new(ErrorCode::UndeclaredVariable, Token {
+                span: Span {
+                    start: TextLocation { index: 18, line: 0, column: 18 },
+                    end: TextLocation { index: 19, line: 0, column: 19 }
+                },
                 text: Cow::Borrowed("x"),
                 data: TokenData::Ident
             },
@@ -319,17 +503,23 @@ mod tests {
         sym_checker.check_block(&block);
         let (_table, verifier) = sym_checker.decompose();
         let expected = vec![
-            VerifyError::new(Token {
-                location: TextLocation { index: 18, line: 0, column: 18 },
+            VerifyError::new(ErrorCode::AlreadyDeclared, Token {
+                span: Span {
+                    start: TextLocation { index: 18, line: 0, column: 18 },
+                    end: TextLocation { index: 19, line: 0, column: 19 }
+                },
                 text: Cow::Borrowed("x"),
                 data: TokenData::Ident
             },
             vec![
-                Token {
-                    location: TextLocation { index: 4, line: 0, column: 4 },
+                (Token {
+                    span: Span {
+                        start: TextLocation { index: 4, line: 0, column: 4 },
+                        end: TextLocation { index: 5, line: 0, column: 5 }
+                    },
                     text: Cow::Borrowed("x"),
                     data: TokenData::Ident
-                }
+                }, "previously declared here")
             ],
             "Variable x is already declared".into())
         ];
@@ -345,11 +535,10 @@ mod tests {
         sym_checker.check_block(&block);
         let (_table, verifier) = sym_checker.decompose();
         let expected: Vec<VerifyError> = vec![
-            VerifyError::new(Token {
-                location: TextLocation {
-                    index: 17,
-                    line: 0,
-                    column: 17
+            VerifyError::new(ErrorCode::UndeclaredVariable, Token {
+                span: Span {
+                    start: TextLocation { index: 17, line: 0, column: 17 },
+                    end: TextLocation { index: 18, line: 0, column: 18 }
                 },
                 text: Cow::Borrowed("y"),
                 data: TokenData::Ident
@@ -368,8 +557,11 @@ mod tests {
         sym_checker.check_block(&block);
         let (_table, verifier) = sym_checker.decompose();
         let expected = vec![
-            VerifyError::new(Token {
-                location: TextLocation { index: 14, line: 0, column: 14 },
+            VerifyError::new(ErrorCode::UndeclaredVariable, Token {
+                span: Span {
+                    start: TextLocation { index: 14, line: 0, column: 14 },
+                    end: TextLocation { index: 15, line: 0, column: 15 }
+                },
                 text: Cow::Borrowed("y"),
                 data: TokenData::Ident
             },
@@ -389,11 +581,10 @@ mod tests {
         sym_checker.check_block(&block);
         let (_table, verifier) = sym_checker.decompose();
         let expected: Vec<VerifyError> = vec![
-            VerifyError::new(Token {
-                location: TextLocation {
-                    index: 18,
-                    line: 0,
-                    column: 18
+            VerifyError::new(ErrorCode::UndeclaredVariable, Token {
+                span: Span {
+                    start: TextLocation { index: 18, line: 0, column: 18 },
+                    end: TextLocation { index: 19, line: 0, column: 19 }
                 },
                 text: Cow::Borrowed("y"),
                 data: TokenData::Ident
@@ -413,11 +604,10 @@ mod tests {
         sym_checker.check_block(&block);
         let (_table, verifier) = sym_checker.decompose();
         let expected: Vec<VerifyError> = vec![
-            VerifyError::new(Token {
-                location: TextLocation {
-                    index: 10,
-                    line: 0,
-                    column: 10
+            VerifyError::new(ErrorCode::UndeclaredVariable, Token {
+                span: Span {
+                    start: TextLocation { index: 10, line: 0, column: 10 },
+                    end: TextLocation { index: 11, line: 0, column: 11 }
                 },
                 text: Cow::Borrowed("y"),
                 data: TokenData::Ident
@@ -436,11 +626,10 @@ mod tests {
         sym_checker.check_block(&block);
         let (_table, verifier) = sym_checker.decompose();
         let expected: Vec<VerifyError> = vec![
-            VerifyError::new(Token {
-                location: TextLocation {
-                    index: 10,
-                    line: 0,
-                    column: 10
+            VerifyError::new(ErrorCode::UndeclaredVariable, Token {
+                span: Span {
+                    start: TextLocation { index: 10, line: 0, column: 10 },
+                    end: TextLocation { index: 11, line: 0, column: 11 }
                 },
                 text: Cow::Borrowed("y"),
                 data: TokenData::Ident
@@ -459,8 +648,11 @@ mod tests {
         sym_checker.check_block(&block);
         let (_table, verifier) = sym_checker.decompose();
         let expected = vec![
-            VerifyError::new(Token {
-                location: TextLocation { index: 18, line: 0, column: 18 },
+            VerifyError::new(ErrorCode::UndeclaredVariable, Token {
+                span: Span {
+                    start: TextLocation { index: 18, line: 0, column: 18 },
+                    end: TextLocation { index: 19, line: 0, column: 19 }
+                },
                 text: Cow::Borrowed("y"),
                 data: TokenData::Ident
             },
@@ -479,11 +671,10 @@ mod tests {
         sym_checker.check_block(&block);
         let (_table, verifier) = sym_checker.decompose();
         let expected: Vec<VerifyError> = vec![
-            VerifyError::new(Token {
-                location: TextLocation {
-                    index: 8,
-                    line: 0,
-                    column: 8
+            VerifyError::new(ErrorCode::UndeclaredVariable, Token {
+                span: Span {
+                    start: TextLocation { index: 8, line: 0, column: 8 },
+                    end: TextLocation { index: 9, line: 0, column: 9 }
                 },
                 text: Cow::Borrowed("x"),
                 data: TokenData::Ident
@@ -493,6 +684,159 @@ mod tests {
         assert_eq!(verifier.get_errors(), &*expected);
     }
 
+    #[test]
+    fn it_finds_calls_to_variables() {
+        let mut parser = parser("let f = 1 f(a: 1)");
+        let block = parser.block().unwrap();
+        let errors = ErrorCollector::new();
+        let mut sym_checker = SymbolTableChecker::new(errors);
+        sym_checker.check_block(&block);
+        let (_table, verifier) = sym_checker.decompose();
+        let expected = vec![
+            VerifyError::new(ErrorCode::NotAFunction, Token {
+                span: Span {
+                    start: TextLocation { index: 11, line: 0, column: 11 },
+                    end: TextLocation { index: 12, line: 0, column: 12 }
+                },
+                text: Cow::Borrowed("("),
+                data: TokenData::Symbol
+            },
+            vec![(Token {
+                span: Span {
+                    start: TextLocation { index: 4, line: 0, column: 4 },
+                    end: TextLocation { index: 5, line: 0, column: 5 }
+                },
+                text: Cow::Borrowed("f"),
+                data: TokenData::Ident
+            }, "declared here")],
+            "f is not a function".into())
+        ];
+        assert_eq!(verifier.get_errors(), &*expected);
+    }
+
+    #[test]
+    fn it_finds_break_outside_loop() {
+        let mut parser = parser("let x = 1 break");
+        let block = parser.block().unwrap();
+        let errors = ErrorCollector::new();
+        let mut sym_checker = SymbolTableChecker::new(errors);
+        sym_checker.check_block(&block);
+        let (_table, verifier) = sym_checker.decompose();
+        let expected = vec![
+            VerifyError::new(ErrorCode::LoopControlOutsideLoop, Token {
+                span: Span {
+                    start: TextLocation { index: 10, line: 0, column: 10 },
+                    end: TextLocation { index: 15, line: 0, column: 15 }
+                },
+                text: Cow::Borrowed("break"),
+                data: TokenData::Keyword
+            },
+            vec![],
+            "Cannot `break` outside of a loop".into())
+        ];
+        assert_eq!(verifier.get_errors(), &*expected);
+    }
+
+    #[test]
+    fn it_allows_break_and_continue_in_loops() {
+        let mut parser = parser("let mut x = 1\n\
+        while x < 10\n    \
+            x += 1\n    \
+            continue\n\
+        loop\n    \
+            break\n");
+        let block = parser.block().unwrap();
+        let errors = ErrorCollector::new();
+        let mut sym_checker = SymbolTableChecker::new(errors);
+        sym_checker.check_block(&block);
+        let (_table, verifier) = sym_checker.decompose();
+        assert_eq!(verifier.get_errors(), &*vec![]);
+    }
+
+    #[test]
+    fn it_scopes_loop_variable_to_body() {
+        let mut parser = parser("for i in 0..10\n    i + 1\ni");
+        let block = parser.block().unwrap();
+        let errors = ErrorCollector::new();
+        let mut sym_checker = SymbolTableChecker::new(errors);
+        sym_checker.check_block(&block);
+        let (_table, verifier) = sym_checker.decompose();
+        let expected = vec![
+            VerifyError::new(ErrorCode::UndeclaredVariable, Token {
+                span: Span {
+                    start: TextLocation { index: 25, line: 2, column: 0 },
+                    end: TextLocation { index: 26, line: 2, column: 1 }
+                },
+                text: Cow::Borrowed("i"),
+                data: TokenData::Ident
+            },
+            vec![],
+            "Variable i was not declared".into())
+        ];
+        assert_eq!(verifier.get_errors(), &*expected);
+    }
+
+    #[test]
+    fn it_finds_assignment_to_loop_variable() {
+        let mut parser = parser("for i in 0..10\n    i = 2");
+        let block = parser.block().unwrap();
+        let errors = ErrorCollector::new();
+        let mut sym_checker = SymbolTableChecker::new(errors);
+        sym_checker.check_block(&block);
+        let (_table, verifier) = sym_checker.decompose();
+        let expected = vec![
+            VerifyError::new(ErrorCode::ReassignImmutable, Token {
+                span: Span {
+                    start: TextLocation { index: 19, line: 1, column: 4 },
+                    end: TextLocation { index: 20, line: 1, column: 5 }
+                },
+                text: Cow::Borrowed("i"),
+                data: TokenData::Ident
+            },
+            vec![(Token {
+                span: Span {
+                    start: TextLocation { index: 4, line: 0, column: 4 },
+                    end: TextLocation { index: 5, line: 0, column: 5 }
+                },
+                text: Cow::Borrowed("i"),
+                data: TokenData::Ident
+            }, "declared immutable here")],
+            "Variable i was not declared mutable".into())
+        ];
+        assert_eq!(verifier.get_errors(), &*expected);
+    }
+
+    #[test]
+    fn it_uses_type_annotations() {
+        let mut parser = parser("fn f(a: Bool, b: Quux) -> String => a");
+        let unit = Unit::new(vec![parser.item().unwrap()]);
+        let errors = ErrorCollector::new();
+        let mut sym_checker = SymbolTableChecker::new(errors);
+        sym_checker.check_unit(&unit);
+        let (table, verifier) = sym_checker.decompose();
+        let expected = vec![
+            VerifyError::new(ErrorCode::UnknownType, Token {
+                span: Span {
+                    start: TextLocation { index: 17, line: 0, column: 17 },
+                    end: TextLocation { index: 21, line: 0, column: 21 }
+                },
+                text: Cow::Borrowed("Quux"),
+                data: TokenData::Ident
+            },
+            vec![],
+            "Unknown type Quux".into())
+        ];
+        assert_eq!(verifier.get_errors(), &*expected);
+        let fn_type = table.values()
+            .find(|sym| sym.get_source() == Source::DeclaredFn)
+            .expect("Function was not declared")
+            .get_type().clone()
+            .expect_fn();
+        assert_eq!(fn_type.get_return(), &Type::String);
+        assert_eq!(fn_type.get_args(),
+            &[("a".to_string(), Type::Bool), ("b".to_string(), Type::Float)]);
+    }
+
     #[test]
     fn it_finds_missing_declaration_in_assignop_expression() {
         let mut parser = parser("let x = 0 \n\
@@ -507,16 +851,22 @@ mod tests {
         sym_checker.check_block(&block);
         let (_table, verifier) = sym_checker.decompose();
         let expected: Vec<VerifyError> = vec![
-            VerifyError::new(
+            VerifyError::new(ErrorCode::UndeclaredVariable, 
                 Token {
-                    location: TextLocation { index: 50, line: 4, column: 0 },
+                    span: Span {
+                        start: TextLocation { index: 50, line: 4, column: 0 },
+                        end: TextLocation { index: 51, line: 4, column: 1 }
+                    },
                     text: Cow::Borrowed("t"),
                     data: TokenData::Ident
                 }, vec![],
                 "Variable t was not declared".into()),
-            VerifyError::new(
+            VerifyError::new(ErrorCode::UndeclaredVariable, 
                 Token {
-                    location: TextLocation { index: 50, line: 4, column: 0 },
+                    span: Span {
+                        start: TextLocation { index: 50, line: 4, column: 0 },
+                        end: TextLocation { index: 51, line: 4, column: 1 }
+                    },
                     text: Cow::Borrowed("t"),
                     data: TokenData::Ident
                 }, vec![],
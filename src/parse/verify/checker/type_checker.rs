@@ -0,0 +1,970 @@
+//! Checks that expressions are used with the right types.
+
+use lex::Token;
+use parse::ASTVisitor;
+use parse::ast::*;
+use parse::verify::{ErrorCollector, VerifyError};
+use parse::verify::scope::SymbolTable;
+use parse::types::{Type, FnType, Substitution, Intrinsic};
+use diagnostics::ErrorCode;
+
+/// Computes the type of every expression and reports type mismatches.
+///
+/// This runs after the `SymbolTableChecker`, and infers the types of
+/// variables, parameters and function results which were declared without
+/// a type annotation. Each of those is given a type variable which is
+/// unified with the types it's used with across the whole unit, and the
+/// results are written back into the symbol table. Functions are not
+/// generic, so every call to a function has to agree on its types.
+/// Types which can't be inferred default to `Float`.
+///
+/// Arithmetic works on `Int`s and `Float`s, but both sides of an operator
+/// need to have the same type: there are no implicit conversions. Only
+/// numbers and `Bool`s can be checked for equality.
+///
+/// Types of expressions are `None` when they can't be known because of
+/// an error which has already been reported, so that one mistake doesn't
+/// cause a cascade of errors.
+#[derive(Debug)]
+pub struct TypeChecker<'a> {
+    symbols: &'a mut SymbolTable,
+    errors: &'a mut ErrorCollector,
+    /// Types of the expressions which have been checked
+    expr_types: Vec<Option<Type>>,
+    /// Type of the value of the last block which was checked
+    block_type: Option<Type>,
+    /// Name and type of the function being checked
+    current_fn: Option<(Identifier, FnType)>,
+    /// Types inferred for type variables so far
+    substitution: Substitution,
+    /// Types which have to be numeric but weren't inferred when they were used
+    numeric_types: Vec<(Type, String, Token)>,
+    /// Types which have to be comparable but weren't inferred when they were used
+    comparable_types: Vec<(Type, String, Token)>
+}
+impl<'a> TypeChecker<'a> {
+    pub fn new(symbols: &'a mut SymbolTable, errors: &'a mut ErrorCollector) -> TypeChecker<'a> {
+        TypeChecker {
+            symbols: symbols,
+            errors: errors,
+            expr_types: Vec::new(),
+            block_type: None,
+            current_fn: None,
+            substitution: Substitution::new(),
+            numeric_types: Vec::new(),
+            comparable_types: Vec::new()
+        }
+    }
+
+    /// Give a function's unannotated parameters and result type variables.
+    fn declare_fn(&mut self, fn_declaration: &FnDeclaration) {
+        let declared_type = match self.variable_type(fn_declaration.get_name()) {
+            Some(Type::Fn(fn_type)) => fn_type,
+            _ => return
+        };
+        let mut arg_types = Vec::with_capacity(fn_declaration.get_args().len());
+        for &(ref param, ref type_expr) in fn_declaration.get_args() {
+            let param_type = match (type_expr, self.variable_type(param)) {
+                (&Some(_), Some(annotated)) => annotated,
+                _ => self.substitution.fresh()
+            };
+            if let Some(symbol) = self.symbols.get_mut(&param.get_index()) {
+                symbol.set_type(param_type.clone());
+            }
+            arg_types.push((param.get_name().to_string(), param_type));
+        }
+        let return_type = if fn_declaration.get_return_type().is_some() {
+            declared_type.get_return().clone()
+        }
+        else {
+            self.substitution.fresh()
+        };
+        let fn_type = Type::Fn(FnType::new(Box::new(return_type), arg_types));
+        if let Some(symbol) = self.symbols.get_mut(&fn_declaration.get_name().get_index()) {
+            symbol.set_type(fn_type);
+        }
+    }
+
+    /// Replace the type variables in the symbol table with their inferred types.
+    fn write_inferred_types(&mut self) {
+        for symbol in self.symbols.values_mut() {
+            let inferred = self.substitution.resolve_or(symbol.get_type(), &Type::Float);
+            symbol.set_type(inferred);
+        }
+    }
+
+    /// Report types used in arithmetic which were inferred not to be numbers.
+    ///
+    /// Types which are still unknown will become `Float`s.
+    fn check_numeric_types(&mut self) {
+        for (type_, usage, token) in self.numeric_types.drain(..) {
+            let resolved = self.substitution.resolve(&type_);
+            if let Type::Variable(_) = resolved {
+                continue
+            }
+            if !resolved.is_numeric() {
+                let err_text = format!("Expected a number for {}, got {:?}", usage, resolved);
+                self.errors.add_error(VerifyError::new(ErrorCode::ExpectedNumber, token, vec![], err_text));
+            }
+        }
+    }
+
+    /// Report types checked for equality which were inferred not to be
+    /// comparable.
+    fn check_comparable_types(&mut self) {
+        for (type_, usage, token) in self.comparable_types.drain(..) {
+            let resolved = self.substitution.resolve(&type_);
+            if let Type::Variable(_) = resolved {
+                continue
+            }
+            if !resolved.is_comparable() {
+                let err_text = format!("Expected a number or Bool for {}, got {:?}", usage, resolved);
+                self.errors.add_error(VerifyError::new(ErrorCode::TypeMismatch, token, vec![], err_text));
+            }
+        }
+    }
+
+    /// Check an expression and get its type.
+    fn expression_type(&mut self, expr: &Expression) -> Option<Type> {
+        self.check_expression(expr);
+        self.expr_types.pop()
+            .expect("Did not get the type of an expression")
+    }
+
+    /// Check a statement and get the type of its value, if it has one.
+    fn statement_type(&mut self, stmt: &Statement) -> Option<Type> {
+        match *stmt {
+            Statement::Expression(ref expr) => self.expression_type(expr),
+            Statement::DoBlock(ref do_block) => {
+                self.check_do_block(do_block);
+                self.block_type.take()
+            },
+            Statement::IfBlock(ref if_block) => {
+                self.check_if_block(if_block);
+                self.block_type.take()
+            },
+            ref other => {
+                self.check_statement(other);
+                None
+            }
+        }
+    }
+
+    /// Get the type of a variable from the symbol table.
+    fn variable_type(&self, ident: &Identifier) -> Option<Type> {
+        self.symbols.get(&ident.get_index())
+            .map(|symbol| symbol.get_type().clone())
+    }
+
+    /// Unify `found` with `expected`, reporting an error if they can't be.
+    ///
+    /// `usage` describes where the type was found, i.e. "condition".
+    fn expect_type(&mut self,
+                   found: &Option<Type>,
+                   expected: &Type,
+                   usage: &str,
+                   token: &Token,
+                   references: Vec<(Token, &'static str)>) {
+        if let Some(ref found) = *found {
+            if !self.substitution.unify(found, expected) {
+                let err_text = format!("Expected type {:?} for {}, got {:?}",
+                    self.substitution.resolve(expected), usage, self.substitution.resolve(found));
+                self.errors.add_error(VerifyError::new(ErrorCode::TypeMismatch,
+                    token.clone(), references, err_text));
+            }
+        }
+    }
+
+    /// Report an error if `found` isn't an `Int` or a `Float`.
+    ///
+    /// If its type is still being inferred it's checked at the end of the unit.
+    fn expect_numeric(&mut self, found: &Option<Type>, usage: &str, token: &Token) {
+        if let Some(ref found) = *found {
+            let resolved = self.substitution.resolve(found);
+            if let Type::Variable(_) = resolved {
+                self.numeric_types.push((resolved, usage.to_string(), token.clone()));
+            }
+            else if !resolved.is_numeric() {
+                let err_text = format!("Expected a number for {}, got {:?}", usage, resolved);
+                self.errors.add_error(VerifyError::new(ErrorCode::ExpectedNumber,
+                    token.clone(), vec![], err_text));
+            }
+        }
+    }
+
+    /// Report an error if `found` isn't an `Int`, `Float` or `Bool`.
+    ///
+    /// If its type is still being inferred it's checked at the end of the unit.
+    fn expect_comparable(&mut self, found: &Option<Type>, usage: &str, token: &Token) {
+        if let Some(ref found) = *found {
+            let resolved = self.substitution.resolve(found);
+            if let Type::Variable(_) = resolved {
+                self.comparable_types.push((resolved, usage.to_string(), token.clone()));
+            }
+            else if !resolved.is_comparable() {
+                let err_text = format!("Expected a number or Bool for {}, got {:?}", usage, resolved);
+                self.errors.add_error(VerifyError::new(ErrorCode::TypeMismatch,
+                    token.clone(), vec![], err_text));
+            }
+        }
+    }
+
+    /// Check the argument of a call to a built in function.
+    /// Check the arguments of a call to something which isn't a function,
+    /// which has already been reported, so errors in them are still found.
+    fn check_unknown_fn_call(&mut self, fn_call: &FnCall) {
+        match *fn_call.get_args() {
+            FnCallArgs::SingleExpr(ref expr) => {
+                self.expression_type(expr);
+            },
+            FnCallArgs::Arguments(ref args) => {
+                for expr in args.iter().filter_map(|arg| arg.get_expr()) {
+                    self.expression_type(expr);
+                }
+            }
+        }
+        self.expr_types.push(None);
+    }
+
+    fn check_intrinsic_call(&mut self, intrinsic: Intrinsic, fn_call: &FnCall) {
+        let fn_type = intrinsic.get_type();
+        let arg_type = match *fn_call.get_args() {
+            FnCallArgs::SingleExpr(ref expr) => self.expression_type(expr),
+            FnCallArgs::Arguments(ref args) if args.len() == 1 => match args[0].get_expr() {
+                Some(expr) => self.expression_type(expr),
+                None => self.variable_type(args[0].get_name())
+            },
+            // The wrong number of args has already been reported
+            _ => None
+        };
+        let &(ref name, ref expected) = &fn_type.get_args()[0];
+        let usage = format!("arg {} of {}", name, intrinsic.get_name());
+        self.expect_type(&arg_type, expected, &usage, fn_call.get_token(), vec![]);
+        self.expr_types.push(Some(fn_type.get_return().clone()));
+    }
+}
+impl<'a> ASTVisitor for TypeChecker<'a> {
+    fn check_unit(&mut self, unit: &Unit) {
+        for item in unit.get_items() {
+            self.check_item(item);
+        }
+        self.check_numeric_types();
+        self.check_comparable_types();
+        self.write_inferred_types();
+    }
+
+    fn check_literal(&mut self, literal: &Literal) {
+        self.expr_types.push(Some(literal.get_type()));
+    }
+
+    fn check_var_ref(&mut self, var_ref: &Identifier) {
+        let var_type = self.variable_type(var_ref);
+        self.expr_types.push(var_type);
+    }
+
+    fn check_binary_op(&mut self, bin_op: &BinaryOperation) {
+        let left_type = self.expression_type(bin_op.get_left());
+        let right_type = self.expression_type(bin_op.get_right());
+        let operator = bin_op.get_operator();
+        let usage = format!("operand of `{}`", bin_op.op_token.get_text());
+        let result = match operator {
+            Operator::And | Operator::Or => {
+                self.expect_type(&left_type, &Type::Bool, &usage, &bin_op.op_token, vec![]);
+                self.expect_type(&right_type, &Type::Bool, &usage, &bin_op.op_token, vec![]);
+                Some(Type::Bool)
+            },
+            // Two numbers or `Bool`s of the same type can be checked for equality
+            Operator::Equality | Operator::NonEquality => {
+                self.expect_comparable(&left_type, &usage, &bin_op.op_token);
+                if let Some(ref expected) = left_type {
+                    self.expect_type(&right_type, expected, &usage, &bin_op.op_token, vec![]);
+                }
+                else {
+                    self.expect_comparable(&right_type, &usage, &bin_op.op_token);
+                }
+                Some(Type::Bool)
+            },
+            // Arithmetic and ordering need two numbers of the same type
+            _ => {
+                self.expect_numeric(&left_type, &usage, &bin_op.op_token);
+                if let Some(ref expected) = left_type {
+                    self.expect_type(&right_type, expected, &usage, &bin_op.op_token, vec![]);
+                }
+                else {
+                    self.expect_numeric(&right_type, &usage, &bin_op.op_token);
+                }
+                if operator.is_comparison() {
+                    Some(Type::Bool)
+                }
+                else {
+                    left_type.or(right_type)
+                }
+            }
+        };
+        self.expr_types.push(result);
+    }
+
+    fn check_unary_op(&mut self, unary_op: &UnaryOperation) {
+        let inner_type = self.expression_type(unary_op.get_inner());
+        let usage = format!("operand of `{}`", unary_op.op_token.get_text());
+        if *unary_op.get_operator() == Operator::Not {
+            self.expect_type(&inner_type, &Type::Bool, &usage, &unary_op.op_token, vec![]);
+            self.expr_types.push(Some(Type::Bool));
+        }
+        else {
+            self.expect_numeric(&inner_type, &usage, &unary_op.op_token);
+            self.expr_types.push(inner_type);
+        }
+    }
+
+    fn check_if_expr(&mut self, if_expr: &IfExpression) {
+        let condition_type = self.expression_type(if_expr.get_condition());
+        self.expect_type(&condition_type, &Type::Bool, "condition", if_expr.get_token(), vec![]);
+        let true_type = self.expression_type(if_expr.get_true_expr());
+        let else_type = self.expression_type(if_expr.get_else());
+        if let Some(ref expected) = true_type {
+            self.expect_type(&else_type, expected, "else branch", if_expr.get_token(), vec![]);
+        }
+        self.expr_types.push(true_type.or(else_type));
+    }
+
+    fn check_if_block(&mut self, if_block: &IfBlock) {
+        let mut value_type: Option<Type> = None;
+        for conditional in if_block.get_conditionals() {
+            let condition_type = self.expression_type(conditional.get_condition());
+            self.expect_type(&condition_type, &Type::Bool, "condition",
+                             &conditional.if_token, vec![]);
+            self.check_block(conditional.get_block());
+            let branch_type = self.block_type.take();
+            if if_block.has_value() {
+                if let Some(ref expected) = value_type {
+                    self.expect_type(&branch_type, expected, "branch of if",
+                                     &conditional.if_token, vec![]);
+                }
+                value_type = value_type.or(branch_type);
+            }
+        }
+        if let Some(&(ref else_token, ref else_block)) = if_block.get_else() {
+            self.check_block(else_block);
+            let branch_type = self.block_type.take();
+            if if_block.has_value() {
+                if let Some(ref expected) = value_type {
+                    self.expect_type(&branch_type, expected, "else branch", else_token, vec![]);
+                }
+                value_type = value_type.or(branch_type);
+            }
+        }
+        self.block_type = value_type;
+    }
+
+    fn check_block(&mut self, block: &Block) {
+        let mut last_type = None;
+        for stmt in &block.statements {
+            last_type = self.statement_type(stmt);
+        }
+        self.block_type = last_type;
+    }
+
+    fn check_declaration(&mut self, decl: &Declaration) {
+        let value_type = self.expression_type(decl.get_value());
+        let index = decl.get_ident().get_index();
+        if let Some(type_expr) = decl.get_type_expr() {
+            let declared_type = self.variable_type(decl.get_ident());
+            if let Some(declared_type) = declared_type {
+                self.expect_type(&value_type, &declared_type, decl.get_name(),
+                                 decl.get_ident().get_token(),
+                                 vec![(type_expr.get_token().clone(), "type given here")]);
+            }
+        }
+        else if let Some(value_type) = value_type {
+            // The variable has the type of its value
+            if let Some(symbol) = self.symbols.get_mut(&index) {
+                symbol.set_type(value_type);
+            }
+        }
+        self.expr_types.push(Some(Type::Empty));
+    }
+
+    fn check_assignment(&mut self, assign: &Assignment) {
+        let value_type = self.expression_type(assign.get_rvalue());
+        let lvalue = assign.get_lvalue();
+        if let Some(symbol) = self.symbols.get(&lvalue.get_index()).cloned() {
+            self.expect_type(&value_type, symbol.get_type(), lvalue.get_name(),
+                             lvalue.get_token(), vec![(symbol.get_declaration().clone(), "declared here")]);
+        }
+        self.expr_types.push(Some(Type::Empty));
+    }
+
+    fn check_fn_call(&mut self, fn_call: &FnCall) {
+        if let Some(intrinsic) = Intrinsic::from_name(fn_call.get_text()) {
+            return self.check_intrinsic_call(intrinsic, fn_call)
+        }
+        let fn_symbol = self.symbols.get(&fn_call.get_name().get_index()).cloned();
+        let (fn_type, declaration) = match fn_symbol {
+            Some(symbol) => match symbol.get_type().clone() {
+                Type::Fn(fn_type) => (fn_type, symbol.get_declaration().clone()),
+                _ => return self.check_unknown_fn_call(fn_call)
+            },
+            None => return self.check_unknown_fn_call(fn_call)
+        };
+        match *fn_call.get_args() {
+            FnCallArgs::SingleExpr(ref expr) => {
+                let arg_type = self.expression_type(expr);
+                if let Some(&(ref name, ref expected)) = fn_type.get_args().first() {
+                    let usage = format!("arg {} of {}", name, fn_call.get_text());
+                    self.expect_type(&arg_type, expected, &usage, fn_call.get_token(),
+                                     vec![(declaration.clone(), "function declared here")]);
+                }
+            },
+            FnCallArgs::Arguments(ref args) => {
+                for call_arg in args {
+                    let arg_type = match call_arg.get_expr() {
+                        Some(expr) => self.expression_type(expr),
+                        None => self.variable_type(call_arg.get_name())
+                    };
+                    if let Some((_ix, expected)) = fn_type.get_arg(call_arg.get_text()) {
+                        let usage = format!("arg {} of {}", call_arg.get_text(), fn_call.get_text());
+                        let references = vec![(call_arg.get_name().get_token().clone(), "argument given here"),
+                                              (declaration.clone(), "function declared here")];
+                        self.expect_type(&arg_type, &expected, &usage, fn_call.get_token(),
+                                         references);
+                    }
+                }
+            }
+        }
+        self.expr_types.push(Some(fn_type.get_return().clone()));
+    }
+
+    fn check_return(&mut self, return_: &Return) {
+        let return_type = match *return_.get_value() {
+            Some(ref value) => self.expression_type(value),
+            None => Some(Type::Empty)
+        };
+        if let Some((name, fn_type)) = self.current_fn.clone() {
+            let usage = format!("return value of {}", name.get_name());
+            self.expect_type(&return_type, fn_type.get_return(), &usage,
+                             &return_.token, vec![(name.get_token().clone(), "function declared here")]);
+        }
+    }
+
+    fn check_while_loop(&mut self, while_: &WhileLoop) {
+        let condition_type = self.expression_type(while_.get_condition());
+        self.expect_type(&condition_type, &Type::Bool, "condition", while_.get_token(), vec![]);
+        self.check_block(while_.get_block());
+    }
+
+    fn check_for_loop(&mut self, for_: &ForLoop) {
+        let start_type = self.expression_type(for_.get_start());
+        self.expect_numeric(&start_type, "start of range", for_.get_token());
+        // The end and step of the range have the same type as the start
+        let range_type = start_type.unwrap_or(Type::Float);
+        let end_type = self.expression_type(for_.get_end());
+        self.expect_type(&end_type, &range_type, "end of range", for_.get_token(), vec![]);
+        if let Some(step) = for_.get_step() {
+            let step_type = self.expression_type(step);
+            self.expect_type(&step_type, &range_type, "step of range", for_.get_token(), vec![]);
+            // Loops count up, so they'd never run or never end otherwise
+            if let Some((value, token)) = literal_number(step) {
+                if value <= 0.0 {
+                    let err_text = format!("The step of a range must be positive, got {}", value);
+                    self.errors.add_error(VerifyError::new(ErrorCode::NonPositiveStep,
+                        token.clone(), vec![], err_text));
+                }
+            }
+        }
+        if let Some(symbol) = self.symbols.get_mut(&for_.get_ident().get_index()) {
+            symbol.set_type(range_type);
+        }
+        self.check_block(for_.get_block());
+    }
+
+    fn check_fn_declaration(&mut self, fn_declaration: &FnDeclaration) {
+        self.declare_fn(fn_declaration);
+        let name = fn_declaration.get_name();
+        let fn_type = match self.variable_type(name) {
+            Some(Type::Fn(fn_type)) => fn_type,
+            _ => return
+        };
+        self.current_fn = Some((name.clone(), fn_type.clone()));
+        self.check_block(fn_declaration.get_block());
+        if fn_declaration.get_block().has_value() {
+            let value_type = self.block_type.take();
+            let usage = format!("return value of {}", name.get_name());
+            self.expect_type(&value_type, fn_type.get_return(), &usage, name.get_token(), vec![]);
+        }
+        else {
+            match self.substitution.resolve(fn_type.get_return()) {
+                // Functions which don't end in a value or `return` one have no value
+                Type::Variable(_) => {
+                    self.substitution.unify(fn_type.get_return(), &Type::Empty);
+                },
+                Type::Empty => {},
+                ref return_type if !fn_declaration.get_block().always_returns() => {
+                    let err_text = format!(
+                        "Function {} can finish without returning a value of type {:?}",
+                        name.get_name(), return_type);
+                    self.errors.add_error(VerifyError::new(ErrorCode::MissingReturn,
+                        name.get_token().clone(), vec![], err_text));
+                },
+                _ => {}
+            }
+        }
+        self.current_fn = None;
+    }
+}
+
+/// Get the value of a number literal, or a negated one, and its token.
+fn literal_number(expr: &Expression) -> Option<(f64, &Token)> {
+    match *expr {
+        Expression::Literal(ref literal) => match literal.get_value() {
+            LiteralValue::Int(value) => Some((value as f64, &literal.token)),
+            LiteralValue::Float(value) => Some((value, &literal.token)),
+            _ => None
+        },
+        Expression::UnaryOp(ref unary_op) if unary_op.operator == Operator::Subtraction =>
+            literal_number(&unary_op.expression).map(|(value, _)| (-value, &unary_op.op_token)),
+        _ => None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use lex::{Token, TokenData, TextLocation, Span};
+    use parse::ASTVisitor;
+    use parse::ast::Unit;
+    use parse::tests::parser;
+    use parse::types::{Type, FnType};
+    use parse::verify::{ErrorCollector, VerifyError};
+    use parse::verify::scope::SymbolTable;
+    use diagnostics::ErrorCode;
+    use super::super::SymbolTableChecker;
+    use super::TypeChecker;
+
+    /// Run the symbol and type checkers on a block
+    fn check_block(input: &'static str) -> (SymbolTable, ErrorCollector) {
+        let mut parser = parser(input);
+        let block = parser.block().unwrap();
+        let mut sym_checker = SymbolTableChecker::new(ErrorCollector::new());
+        sym_checker.check_block(&block);
+        let (mut table, mut errors) = sym_checker.decompose();
+        assert_eq!(errors.get_errors(), &*vec![]);
+        TypeChecker::new(&mut table, &mut errors).check_block(&block);
+        (table, errors)
+    }
+
+    /// Run the symbol and type checkers on a unit
+    fn check_unit(input: &'static str) -> (SymbolTable, ErrorCollector) {
+        let mut parser = parser(input);
+        let mut items = Vec::new();
+        while let Ok(item) = parser.item() {
+            items.push(item);
+        }
+        let unit = Unit::new(items);
+        let mut sym_checker = SymbolTableChecker::new(ErrorCollector::new());
+        sym_checker.check_unit(&unit);
+        let (mut table, mut errors) = sym_checker.decompose();
+        assert_eq!(errors.get_errors(), &*vec![]);
+        TypeChecker::new(&mut table, &mut errors).check_unit(&unit);
+        (table, errors)
+    }
+
+    /// Get the type of the symbol with the given name
+    fn symbol_type(table: &SymbolTable, name: &str) -> Type {
+        table.values()
+            .find(|symbol| symbol.get_declaration().get_text() == name)
+            .expect("Symbol was not declared")
+            .get_type()
+            .clone()
+    }
+
+    fn fn_type(ret: Type, args: Vec<(&str, Type)>) -> Type {
+        let args = args.into_iter().map(|(name, arg)| (name.to_string(), arg)).collect();
+        Type::Fn(FnType::new(Box::new(ret), args))
+    }
+
+    #[test]
+    fn it_finds_non_bool_condition() {
+        let (_table, errors) = check_block("let x = 1 if x => x else 0");
+        let expected = vec![
+            VerifyError::new(ErrorCode::TypeMismatch, Token {
+                span: Span {
+                    start: TextLocation { index: 10, line: 0, column: 10 },
+                    end: TextLocation { index: 12, line: 0, column: 12 }
+                },
+                text: Cow::Borrowed("if"),
+                data: TokenData::Keyword
+            },
+            vec![],
+            "Expected type Bool for condition, got Int".into())
+        ];
+        assert_eq!(errors.get_errors(), &*expected);
+    }
+
+    #[test]
+    fn it_allows_bool_conditions() {
+        let (_table, errors) =
+            check_block("let x = 1 let isOne = x == 1 if isOne => true else x < 0");
+        assert_eq!(errors.get_errors(), &*vec![]);
+    }
+
+    #[test]
+    fn it_finds_non_bool_logical_operand() {
+        let (_table, errors) = check_block("let x = 1 x > 0 and not x");
+        let expected = vec![
+            VerifyError::new(ErrorCode::TypeMismatch, Token {
+                span: Span {
+                    start: TextLocation { index: 20, line: 0, column: 20 },
+                    end: TextLocation { index: 23, line: 0, column: 23 }
+                },
+                text: Cow::Borrowed("not"),
+                data: TokenData::Keyword
+            },
+            vec![],
+            "Expected type Bool for operand of `not`, got Int".into())
+        ];
+        assert_eq!(errors.get_errors(), &*expected);
+    }
+
+    #[test]
+    fn it_finds_mismatched_if_branches() {
+        let (_table, errors) = check_block("let x = 1 if x > 0 => x else false");
+        let expected = vec![
+            VerifyError::new(ErrorCode::TypeMismatch, Token {
+                span: Span {
+                    start: TextLocation { index: 10, line: 0, column: 10 },
+                    end: TextLocation { index: 12, line: 0, column: 12 }
+                },
+                text: Cow::Borrowed("if"),
+                data: TokenData::Keyword
+            },
+            vec![],
+            "Expected type Int for else branch, got Bool".into())
+        ];
+        assert_eq!(errors.get_errors(), &*expected);
+    }
+
+    #[test]
+    fn it_finds_assignment_of_wrong_type() {
+        let (_table, errors) = check_block("let mut x = 1 x = \"one\"");
+        let expected = vec![
+            VerifyError::new(ErrorCode::TypeMismatch, Token {
+                span: Span {
+                    start: TextLocation { index: 14, line: 0, column: 14 },
+                    end: TextLocation { index: 15, line: 0, column: 15 }
+                },
+                text: Cow::Borrowed("x"),
+                data: TokenData::Ident
+            },
+            vec![(Token {
+                span: Span {
+                    start: TextLocation { index: 8, line: 0, column: 8 },
+                    end: TextLocation { index: 9, line: 0, column: 9 }
+                },
+                text: Cow::Borrowed("x"),
+                data: TokenData::Ident
+            }, "declared here")],
+            "Expected type Int for x, got String".into())
+        ];
+        assert_eq!(errors.get_errors(), &*expected);
+    }
+
+    #[test]
+    fn it_gives_declarations_the_type_of_their_value() {
+        let (table, errors) = check_block("let x = true let y = x");
+        assert_eq!(errors.get_errors(), &*vec![]);
+        for symbol in table.values() {
+            assert_eq!(symbol.get_type(), &Type::Bool);
+        }
+    }
+
+    #[test]
+    fn it_checks_function_args_and_returns() {
+        let (_table, errors) = check_unit("fn f(a: Bool) -> Float => a\nfn g() => f(a: 1)");
+        let expected = vec![
+            VerifyError::new(ErrorCode::TypeMismatch, Token {
+                span: Span {
+                    start: TextLocation { index: 3, line: 0, column: 3 },
+                    end: TextLocation { index: 4, line: 0, column: 4 }
+                },
+                text: Cow::Borrowed("f"),
+                data: TokenData::Ident
+            },
+            vec![],
+            "Expected type Float for return value of f, got Bool".into()),
+            VerifyError::new(ErrorCode::TypeMismatch, Token {
+                span: Span {
+                    start: TextLocation { index: 39, line: 1, column: 11 },
+                    end: TextLocation { index: 40, line: 1, column: 12 }
+                },
+                text: Cow::Borrowed("("),
+                data: TokenData::Symbol
+            },
+            vec![
+                (Token {
+                    span: Span {
+                        start: TextLocation { index: 40, line: 1, column: 12 },
+                        end: TextLocation { index: 41, line: 1, column: 13 }
+                    },
+                    text: Cow::Borrowed("a"),
+                    data: TokenData::Ident
+                }, "argument given here"),
+                (Token {
+                    span: Span {
+                        start: TextLocation { index: 3, line: 0, column: 3 },
+                        end: TextLocation { index: 4, line: 0, column: 4 }
+                    },
+                    text: Cow::Borrowed("f"),
+                    data: TokenData::Ident
+                }, "function declared here")
+            ],
+            "Expected type Bool for arg a of f, got Int".into())
+        ];
+        assert_eq!(errors.get_errors(), &*expected);
+    }
+
+    #[test]
+    fn it_infers_parameters_from_usage() {
+        let (table, errors) = check_unit("fn f(a) => a and true");
+        assert_eq!(errors.get_errors(), &*vec![]);
+        assert_eq!(symbol_type(&table, "a"), Type::Bool);
+        assert_eq!(symbol_type(&table, "f"), fn_type(Type::Bool, vec![("a", Type::Bool)]));
+    }
+
+    #[test]
+    fn it_infers_parameters_from_calls() {
+        let (table, errors) = check_unit("fn id(x) => x\nfn g()\n    let y = id(x: \"text\")\n    y");
+        assert_eq!(errors.get_errors(), &*vec![]);
+        assert_eq!(symbol_type(&table, "id"), fn_type(Type::String, vec![("x", Type::String)]));
+        assert_eq!(symbol_type(&table, "y"), Type::String);
+        assert_eq!(symbol_type(&table, "g"), fn_type(Type::String, vec![]));
+    }
+
+    #[test]
+    fn it_defaults_unused_parameters_to_float() {
+        let (table, errors) = check_unit("fn f(a) => 1");
+        assert_eq!(errors.get_errors(), &*vec![]);
+        assert_eq!(symbol_type(&table, "a"), Type::Float);
+    }
+
+    #[test]
+    fn it_infers_empty_returns_for_blocks_without_values() {
+        let (table, errors) = check_unit("fn f()\n    let x = 1\nfn g(a)\n    return a + 1");
+        assert_eq!(errors.get_errors(), &*vec![]);
+        assert_eq!(symbol_type(&table, "f"), fn_type(Type::Empty, vec![]));
+        assert_eq!(symbol_type(&table, "g"), fn_type(Type::Int, vec![("a", Type::Int)]));
+    }
+
+    #[test]
+    fn it_finds_functions_which_can_finish_without_returning() {
+        let (_table, errors) = check_unit(
+            "fn f(n: Int) -> Int\n    while n > 0\n        return n");
+        let expected = vec![
+            VerifyError::new(ErrorCode::MissingReturn, Token {
+                span: Span {
+                    start: TextLocation { index: 3, line: 0, column: 3 },
+                    end: TextLocation { index: 4, line: 0, column: 4 }
+                },
+                text: Cow::Borrowed("f"),
+                data: TokenData::Ident
+            },
+            vec![],
+            "Function f can finish without returning a value of type Int".into())
+        ];
+        assert_eq!(errors.get_errors(), &*expected);
+    }
+
+    #[test]
+    fn it_allows_functions_which_only_finish_by_returning() {
+        let (_table, errors) = check_unit(
+            "fn f() -> Int\n    loop\n        return 1\n\
+             fn g(n: Int) -> Int\n    if n > 0\n        return 1\n    else\n        return 2");
+        assert_eq!(errors.get_errors(), &*vec![]);
+    }
+
+    #[test]
+    fn it_finds_loops_which_can_break_without_returning() {
+        let (_table, errors) = check_unit(
+            "fn f(n: Int) -> Int\n    loop\n        if n > 0\n            break\n        return n");
+        assert_eq!(errors.get_errors().len(), 1);
+        assert_eq!(errors.get_errors()[0].get_code(), ErrorCode::MissingReturn);
+    }
+
+    #[test]
+    fn it_finds_conflicting_calls() {
+        let (_table, errors) = check_unit("fn id(x) => x\nfn f() => id(x: true) and id(x: 1)");
+        let expected = vec![
+            VerifyError::new(ErrorCode::TypeMismatch, Token {
+                span: Span {
+                    start: TextLocation { index: 42, line: 1, column: 28 },
+                    end: TextLocation { index: 43, line: 1, column: 29 }
+                },
+                text: Cow::Borrowed("("),
+                data: TokenData::Symbol
+            },
+            vec![
+                (Token {
+                    span: Span {
+                        start: TextLocation { index: 43, line: 1, column: 29 },
+                        end: TextLocation { index: 44, line: 1, column: 30 }
+                    },
+                    text: Cow::Borrowed("x"),
+                    data: TokenData::Ident
+                }, "argument given here"),
+                (Token {
+                    span: Span {
+                        start: TextLocation { index: 3, line: 0, column: 3 },
+                        end: TextLocation { index: 5, line: 0, column: 5 }
+                    },
+                    text: Cow::Borrowed("id"),
+                    data: TokenData::Ident
+                }, "function declared here")
+            ],
+            "Expected type Bool for arg x of id, got Int".into())
+        ];
+        assert_eq!(errors.get_errors(), &*expected);
+    }
+
+    #[test]
+    fn it_finds_mixed_number_types() {
+        let (_table, errors) = check_block("let x = 1 let y = 2.5 x + y");
+        let expected = vec![
+            VerifyError::new(ErrorCode::TypeMismatch, Token {
+                span: Span {
+                    start: TextLocation { index: 24, line: 0, column: 24 },
+                    end: TextLocation { index: 25, line: 0, column: 25 }
+                },
+                text: Cow::Borrowed("+"),
+                data: TokenData::Symbol
+            },
+            vec![],
+            "Expected type Int for operand of `+`, got Float".into())
+        ];
+        assert_eq!(errors.get_errors(), &*expected);
+    }
+
+    #[test]
+    fn it_allows_explicit_conversions() {
+        let (table, errors) = check_block("let x = 1 let y = 2.5 let z = toFloat(x) + y toInt(z) * x");
+        assert_eq!(errors.get_errors(), &*vec![]);
+        assert_eq!(symbol_type(&table, "z"), Type::Float);
+    }
+
+    #[test]
+    fn it_finds_arithmetic_on_strings() {
+        let (_table, errors) = check_block("let s = \"a\" s - s");
+        let expected = vec![
+            VerifyError::new(ErrorCode::ExpectedNumber, Token {
+                span: Span {
+                    start: TextLocation { index: 14, line: 0, column: 14 },
+                    end: TextLocation { index: 15, line: 0, column: 15 }
+                },
+                text: Cow::Borrowed("-"),
+                data: TokenData::Symbol
+            },
+            vec![],
+            "Expected a number for operand of `-`, got String".into())
+        ];
+        assert_eq!(errors.get_errors(), &*expected);
+    }
+
+    #[test]
+    fn it_finds_equality_on_strings() {
+        let (_table, errors) = check_block("let s = \"a\" s == \"a\"");
+        let expected = vec![
+            VerifyError::new(ErrorCode::TypeMismatch, Token {
+                span: Span {
+                    start: TextLocation { index: 14, line: 0, column: 14 },
+                    end: TextLocation { index: 16, line: 0, column: 16 }
+                },
+                text: Cow::Borrowed("=="),
+                data: TokenData::Symbol
+            },
+            vec![],
+            "Expected a number or Bool for operand of `==`, got String".into())
+        ];
+        assert_eq!(errors.get_errors(), &*expected);
+    }
+
+    #[test]
+    fn it_finds_equality_on_inferred_strings() {
+        let (_table, errors) = check_unit("fn same(a, b) => a != b\nfn f() => same(a: \"x\", b: \"y\")");
+        assert_eq!(errors.get_errors().len(), 1);
+        assert_eq!(errors.get_errors()[0].get_code(), ErrorCode::TypeMismatch);
+        assert_eq!(errors.get_errors()[0].get_text(),
+                   "Expected a number or Bool for operand of `!=`, got String");
+    }
+
+    #[test]
+    fn it_allows_equality_on_numbers_and_bools() {
+        let (_table, errors) = check_block("let x = 1 let b = true x == 2 and b != false and 1.5 == 2.5");
+        assert_eq!(errors.get_errors(), &*vec![]);
+    }
+
+    #[test]
+    fn it_finds_non_positive_steps() {
+        let (_table, errors) = check_block("for i in 10..0 by -1\n    i\nfor j in 0..10 by 0\n    j");
+        let expected = vec![
+            VerifyError::new(ErrorCode::NonPositiveStep, Token {
+                span: Span {
+                    start: TextLocation { index: 18, line: 0, column: 18 },
+                    end: TextLocation { index: 19, line: 0, column: 19 }
+                },
+                text: Cow::Borrowed("-"),
+                data: TokenData::Symbol
+            },
+            vec![],
+            "The step of a range must be positive, got -1".into()),
+            VerifyError::new(ErrorCode::NonPositiveStep, Token {
+                span: Span {
+                    start: TextLocation { index: 45, line: 2, column: 18 },
+                    end: TextLocation { index: 46, line: 2, column: 19 }
+                },
+                text: Cow::Borrowed("0"),
+                data: TokenData::IntLiteral(0)
+            },
+            vec![],
+            "The step of a range must be positive, got 0".into())
+        ];
+        assert_eq!(errors.get_errors(), &*expected);
+    }
+
+    #[test]
+    fn it_allows_positive_steps() {
+        let (_table, errors) = check_block("for i in 0..10 by 2\n    i\nfor x in 0.0..1.0 by 0.25\n    x");
+        assert_eq!(errors.get_errors(), &*vec![]);
+    }
+
+    #[test]
+    fn it_infers_numeric_parameters() {
+        let (table, errors) = check_unit("fn f(a) => a * 2");
+        assert_eq!(errors.get_errors(), &*vec![]);
+        assert_eq!(symbol_type(&table, "f"), fn_type(Type::Int, vec![("a", Type::Int)]));
+    }
+
+    #[test]
+    fn it_checks_args_of_calls_to_variables() {
+        let mut parser = parser("let x = 1 x(a: not 1)");
+        let block = parser.block().unwrap();
+        let mut sym_checker = SymbolTableChecker::new(ErrorCollector::new());
+        sym_checker.check_block(&block);
+        let (mut table, mut errors) = sym_checker.decompose();
+        TypeChecker::new(&mut table, &mut errors).check_block(&block);
+        let found = errors.get_errors();
+        assert_eq!(found[0].get_code(), ErrorCode::NotAFunction);
+        let expected = vec![
+            VerifyError::new(ErrorCode::TypeMismatch, Token {
+                span: Span {
+                    start: TextLocation { index: 15, line: 0, column: 15 },
+                    end: TextLocation { index: 18, line: 0, column: 18 }
+                },
+                text: Cow::Borrowed("not"),
+                data: TokenData::Keyword
+            },
+            vec![],
+            "Expected type Bool for operand of `not`, got Int".into())
+        ];
+        assert_eq!(&found[1..], &*expected);
+    }
+}
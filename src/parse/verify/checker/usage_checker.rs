@@ -2,6 +2,7 @@
 
 use parse::verify::{VerifyError, ErrorCollector};
 use parse::verify::scope::SymbolTable;
+use diagnostics::ErrorCode;
 
 /// Reports warnings for unused variables and un-mutated mutable variables.
 #[derive(Debug)]
@@ -16,15 +17,15 @@ impl UsageChecker {
                 let err_message = format!("{} {} is declared but never used",
                     sym.get_source().get_name(),
                     sym.get_declaration().text);
-                warns.add_warning(
-                    VerifyError::new(sym.get_declaration().clone(), vec![], err_message));
+                warns.add_warning(VerifyError::new(ErrorCode::UnusedVariable,
+                    sym.get_declaration().clone(), vec![], err_message));
             }
             if sym.is_mutable() && !sym.is_mutated() {
                 let err_message = format!("{} {} is declared mutable but never mutated",
                     sym.get_source().get_name(),
                     sym.get_declaration().text);
-                warns.add_warning(
-                    VerifyError::new(sym.get_declaration().clone(), vec![], err_message));
+                warns.add_warning(VerifyError::new(ErrorCode::UnmutatedMutable,
+                    sym.get_declaration().clone(), vec![], err_message));
             }
         }
     }
@@ -33,12 +34,13 @@ impl UsageChecker {
 mod tests {
     use std::borrow::Cow;
 
-    use lex::{Token, TokenData, TextLocation};
+    use lex::{Token, TokenData, TextLocation, Span};
     use parse::ASTVisitor;
     use parse::tests::parser;
     use parse::verify::scope::SymbolTable;
     use parse::verify::{ErrorCollector, VerifyError};
     use parse::verify::checker::*;
+    use diagnostics::ErrorCode;
 
     #[test]
     fn it_detects_unused_declared() {
@@ -50,8 +52,11 @@ mod tests {
         let (sym_table, mut errors) = sym_checker.decompose();
         UsageChecker { }.warn_for_unsused(&mut errors, &sym_table);
         let expected = vec![
-            VerifyError::new(Token {
-                location: TextLocation { index: 4, line: 0, column: 4 },
+            VerifyError::new(ErrorCode::UnusedVariable, Token {
+                span: Span {
+                    start: TextLocation { index: 4, line: 0, column: 4 },
+                    end: TextLocation { index: 5, line: 0, column: 5 }
+                },
                 text: Cow::Borrowed("x"),
                 data: TokenData::Ident
             },
@@ -119,8 +124,11 @@ mod tests {
         let (sym_table, mut errors) = sym_checker.decompose();
         UsageChecker { }.warn_for_unsused(&mut errors, &sym_table);
         let expected = vec![
-            VerifyError::new(Token {
-                location: TextLocation { index: 14, line: 0, column: 14 },
+            VerifyError::new(ErrorCode::UnusedVariable, Token {
+                span: Span {
+                    start: TextLocation { index: 14, line: 0, column: 14 },
+                    end: TextLocation { index: 15, line: 0, column: 15 }
+                },
                 text: Cow::Borrowed("y"),
                 data: TokenData::Ident
             },
@@ -140,8 +148,11 @@ mod tests {
         let (sym_table, mut errors) = sym_checker.decompose();
         UsageChecker { }.warn_for_unsused(&mut errors, &sym_table);
         let expected = vec![
-            VerifyError::new(Token {
-                location: TextLocation { index: 4, line: 0, column: 4 },
+            VerifyError::new(ErrorCode::UnusedVariable, Token {
+                span: Span {
+                    start: TextLocation { index: 4, line: 0, column: 4 },
+                    end: TextLocation { index: 5, line: 0, column: 5 }
+                },
                 text: Cow::Borrowed("x"),
                 data: TokenData::Ident
             },
@@ -0,0 +1,73 @@
+//! Symbols accepted by earlier runs of the verifier.
+
+use std::collections::HashMap;
+
+use lex::{Token, TextLocation};
+use parse::verify::{Symbol, Source};
+use parse::verify::scope::{ScopeIndex, SymbolTable};
+use parse::types::{Type, FnType};
+
+/// Top level symbols which were accepted by earlier runs of the verifier.
+///
+/// Used to verify code incrementally, such as the entries of a REPL. The
+/// symbols are visible to the new code as if they had been declared at the
+/// top of its unit, and new symbols are given indices after them.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Definitions {
+    symbols: SymbolTable,
+    names: HashMap<String, ScopeIndex>,
+    next_index: ScopeIndex
+}
+impl Definitions {
+    pub fn new() -> Definitions {
+        Definitions::default()
+    }
+
+    /// Keep a top level symbol from a verified program, such as a function.
+    pub fn define(&mut self, symbol: Symbol) {
+        debug_assert!(symbol.get_index().is_top_level(),
+            "Attempted to define non top level symbol {:?}", symbol);
+        let mut after = symbol.get_index().clone();
+        after.increment();
+        if after > self.next_index {
+            self.next_index = after;
+        }
+        self.names.insert(symbol.get_declaration().get_text().to_string(), symbol.get_index().clone());
+        self.symbols.insert(symbol.get_index().clone(), symbol);
+    }
+
+    /// Define a global variable declared by the given token.
+    pub fn define_global(&mut self, token: Token, mutable: bool, type_: Type) -> ScopeIndex {
+        let index = self.next_index.clone();
+        self.define(Symbol::new(index.clone(), token, mutable, type_, Source::Global));
+        index
+    }
+
+    /// Define a function which is implemented outside of the code being
+    /// verified, such as by the host application.
+    pub fn define_fn(&mut self, name: &str, fn_type: FnType) -> ScopeIndex {
+        let index = self.next_index.clone();
+        let token = Token::new_ident(name.to_string(), TextLocation::default());
+        self.define(Symbol::new(index.clone(), token, false, Type::Fn(fn_type), Source::DeclaredFn));
+        index
+    }
+
+    /// Get the symbol with the given name.
+    pub fn get(&self, name: &str) -> Option<&Symbol> {
+        self.names.get(name).and_then(|index| self.symbols.get(index))
+    }
+
+    pub fn get_symbols(&self) -> &SymbolTable {
+        &self.symbols
+    }
+
+    /// Get the index of each symbol by its name.
+    pub fn get_names(&self) -> &HashMap<String, ScopeIndex> {
+        &self.names
+    }
+
+    /// Get the index which the next top level symbol should have.
+    pub fn get_next_index(&self) -> &ScopeIndex {
+        &self.next_index
+    }
+}
@@ -51,6 +51,13 @@
 //! z += 12
 //!   ^ Cannot reassign immutable variable `z` defined on line x
 //! ```
+//! ### Type mismatch
+//! ```text
+//! let x: Float = true
+//!     ^ Expected type Float for x, got Bool
+//! if x => 1 else false
+//! ^ Expected type Bool for condition, got Float
+//! ```
 //! ## Warnings
 //!
 //! ### Unused mutable
@@ -72,9 +79,11 @@ pub mod scope;
 mod symbol;
 mod verification_result;
 mod collector;
+mod definitions;
 mod verifier;
 
 pub use self::symbol::*;
 pub use self::collector::ErrorCollector;
+pub use self::definitions::Definitions;
 pub use self::verifier::Verifier;
 pub use self::verification_result::VerifyError;
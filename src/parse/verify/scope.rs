@@ -35,6 +35,11 @@ impl ScopeIndex {
         trace!("Created new scope {:?}", vec);
         ScopeIndex { indices: vec }
     }
+    /// Whether the index is of a symbol at the top level, such as a function
+    #[inline]
+    pub fn is_top_level(&self) -> bool {
+        self.indices.len() == 1
+    }
 }
 
 impl Default for ScopeIndex {
@@ -39,28 +39,39 @@ impl Symbol {
             source: source,
         }
     }
-    pub fn from_declaration(decl: &Declaration, index: ScopeIndex) -> Symbol {
+    pub fn from_declaration(decl: &Declaration, index: ScopeIndex, type_: Type) -> Symbol {
         Symbol {
             decl_token: decl.get_ident().get_token().clone(),
             index: index,
             mutable: decl.mutable,
             used: false,
             mutated: false,
-            type_: Type::Float,
+            type_: type_,
             source: Source::Variable,
         }
     }
-    pub fn from_parameter(ident: &Identifier, index: ScopeIndex) -> Symbol {
+    pub fn from_parameter(ident: &Identifier, index: ScopeIndex, type_: Type) -> Symbol {
         Symbol {
             decl_token: ident.get_token().clone(),
             index: index,
             mutable: false, // Just gonna strait up refuse mutable parameters
             mutated: false,
             used: false,
-            type_: Type::Float,
+            type_: type_,
             source: Source::Parameter,
         }
     }
+    pub fn from_loop_variable(ident: &Identifier, index: ScopeIndex, type_: Type) -> Symbol {
+        Symbol {
+            decl_token: ident.get_token().clone(),
+            index: index,
+            mutable: false,
+            mutated: false,
+            used: false,
+            type_: type_,
+            source: Source::LoopVariable,
+        }
+    }
     pub fn from_fn_decl(ident: &Identifier, index: ScopeIndex, type_: Type) -> Symbol {
         Symbol {
             decl_token: ident.get_token().clone(),
@@ -97,6 +108,9 @@ impl Symbol {
     pub fn get_type(&self) -> &Type {
         &self.type_
     }
+    pub fn set_type(&mut self, type_: Type) {
+        self.type_ = type_;
+    }
     pub fn get_source(&self) -> Source {
         self.source
     }
@@ -111,13 +125,19 @@ pub enum Source {
     Parameter,
     /// The symbol was declared as a function
     DeclaredFn,
+    /// The symbol is the variable of a `for` loop
+    LoopVariable,
+    /// The symbol is a variable declared at the top level of a REPL
+    Global,
 }
 impl Source {
     pub fn get_name(self) -> &'static str {
         match self {
             Source::Variable => "variable",
             Source::Parameter => "function parameter",
-            Source::DeclaredFn => "declared function"
+            Source::DeclaredFn => "declared function",
+            Source::LoopVariable => "loop variable",
+            Source::Global => "global variable"
         }
     }
 }
@@ -1,30 +1,41 @@
 //! Result types for Verification
 
 use lex::{Token};
+use diagnostics::ErrorCode;
 
 /// Compiler error returned by an expression verifier.
 ///
 /// Whether this error is actually a warning or lint depends on
 /// compiler options. Errors are collected in an `ErrorCollector`.
+///
+/// `references` point to related code, each with a short label such as
+/// "declared here".
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct VerifyError {
-    //err: ErrorCode,
+    code: ErrorCode,
     offender: Token,
-    references: Vec<Token>,
+    references: Vec<(Token, &'static str)>,
     text: String,
 }
 impl VerifyError {
-    pub fn new(offender: Token, references: Vec<Token>, text: String) -> VerifyError {
+    pub fn new(code: ErrorCode,
+               offender: Token,
+               references: Vec<(Token, &'static str)>,
+               text: String) -> VerifyError {
         VerifyError {
+            code: code,
             offender: offender,
             references: references,
             text: text,
         }
     }
+    pub fn get_code(&self) -> ErrorCode {
+        self.code
+    }
     pub fn get_offender(&self) -> &Token {
         &self.offender
     }
-    pub fn get_references(&self) -> &[Token] {
+    pub fn get_references(&self) -> &[(Token, &'static str)] {
         &self.references
     }
     pub fn get_text(&self) -> &str {
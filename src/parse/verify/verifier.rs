@@ -1,4 +1,5 @@
 use parse::{ASTVisitor, Program, ErrorCollector};
+use parse::verify::Definitions;
 use parse::ast::Unit;
 use parse::verify::checker::*;
 use parse::verify::scope::SymbolTable;
@@ -13,14 +14,40 @@ pub struct Verifier { }
 
 impl Verifier {
     pub fn verify_unit(&mut self, unit: Unit) -> Result<Program, ErrorCollector> {
-        let errors = ErrorCollector::new();
-        let mut symbol_builder = SymbolTableChecker::new(errors);
+        let symbol_builder = SymbolTableChecker::new(ErrorCollector::new());
+        self.verify_with(unit, symbol_builder, false)
+    }
+
+    /// Verify a unit which can use the symbols accepted by earlier runs.
+    ///
+    /// The symbol table of the program includes the earlier symbols.
+    /// Top level symbols aren't warned about if they're unused, as code
+    /// verified later on may use them.
+    pub fn verify_incremental(&mut self, unit: Unit, definitions: &Definitions)
+                              -> Result<Program, ErrorCollector> {
+        let symbol_builder = SymbolTableChecker::with_definitions(definitions, ErrorCollector::new());
+        self.verify_with(unit, symbol_builder, true)
+    }
+
+    fn verify_with(&mut self, unit: Unit, mut symbol_builder: SymbolTableChecker, incremental: bool)
+                   -> Result<Program, ErrorCollector> {
         symbol_builder.check_unit(&unit);
-        let (symbol_table, mut errors) = symbol_builder.decompose();
+        let (mut symbol_table, mut errors) = symbol_builder.decompose();
         if !errors.get_errors().is_empty() {
             return Err(errors)
         }
-        UsageChecker { }.warn_for_unsused(&mut errors, &symbol_table);
+        TypeChecker::new(&mut symbol_table, &mut errors).check_unit(&unit);
+        if !errors.get_errors().is_empty() {
+            return Err(errors)
+        }
+        if incremental {
+            let mut local_symbols = symbol_table.clone();
+            local_symbols.retain(|index, _| !index.is_top_level());
+            UsageChecker { }.warn_for_unsused(&mut errors, &local_symbols);
+        }
+        else {
+            UsageChecker { }.warn_for_unsused(&mut errors, &symbol_table);
+        }
         Ok(Program::new(unit, symbol_table, errors))
     }
 }
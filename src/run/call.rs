@@ -0,0 +1,186 @@
+//! Calling compiled functions from Rust.
+//!
+//! LLVM's MCJIT can only run functions which don't take arguments, so a
+//! call is compiled into a small function which passes the arguments as
+//! constants and returns the result.
+
+use std::ffi::CString;
+use std::fmt;
+use std::os::raw::c_uint;
+use std::ptr;
+
+use parse::{FnType, Source, SymbolTable, Type};
+use compile::{add_abi_attributes, llvm_type};
+use super::value::Value;
+
+use llvm_sys::prelude::*;
+use llvm_sys::core::{LLVMGetNamedFunction, LLVMAddFunction, LLVMFunctionType,
+                     LLVMAppendBasicBlock, LLVMCreateBuilder, LLVMPositionBuilderAtEnd,
+                     LLVMBuildCall, LLVMBuildRet, LLVMBuildRetVoid, LLVMDisposeBuilder,
+                     LLVMConstInt, LLVMConstReal, LLVMInt64Type, LLVMInt1Type, LLVMFloatType};
+
+/// Reasons a compiled function couldn't be called
+#[derive(Debug, PartialEq, Clone)]
+pub enum CallError {
+    /// There's no function with the given name
+    UnknownFunction(String),
+    /// The wrong number of arguments were given
+    ArgumentCount { function: String, expected: usize, found: usize },
+    /// An argument was given which the function doesn't take
+    UnknownArgument { function: String, name: String },
+    /// An argument which the function takes wasn't given
+    MissingArgument { function: String, name: String },
+    /// An argument was given with the wrong type
+    ArgumentType { function: String, name: String, expected: Type, found: Type },
+    /// The function takes or returns a type which can't be passed to or from Rust
+    UnsupportedType { function: String, found: Type }
+}
+
+impl fmt::Display for CallError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CallError::UnknownFunction(ref name) =>
+                write!(f, "There is no function {}", name),
+            CallError::ArgumentCount { ref function, expected, found } =>
+                write!(f, "{} takes {} arguments, but {} were given", function, expected, found),
+            CallError::UnknownArgument { ref function, ref name } =>
+                write!(f, "{} does not take an argument {}", function, name),
+            CallError::MissingArgument { ref function, ref name } =>
+                write!(f, "Argument {} of {} was not given", name, function),
+            CallError::ArgumentType { ref function, ref name, ref expected, ref found } =>
+                write!(f, "Argument {} of {} should be {}, but a {} was given",
+                       name, function, expected, found),
+            CallError::UnsupportedType { ref function, ref found } =>
+                write!(f, "{} uses {}, which cannot be passed between Rust and protosnirk yet",
+                       function, found)
+        }
+    }
+}
+
+/// Find the type of the top level function `name`.
+pub fn find_fn(symbols: &SymbolTable, name: &str) -> Result<FnType, CallError> {
+    symbols.values()
+        .filter(|symbol| symbol.get_source() == Source::DeclaredFn
+            && symbol.get_index().is_top_level())
+        .find(|symbol| symbol.get_declaration().get_text() == name)
+        .map(|symbol| symbol.get_type().clone().expect_fn())
+        .ok_or_else(|| CallError::UnknownFunction(name.to_string()))
+}
+
+/// Put named arguments in the order `fn_type` declares them, checking
+/// that each one is given once with the right type.
+pub fn bind_args(name: &str, fn_type: &FnType, args: &[(&str, Value)]) -> Result<Vec<Value>, CallError> {
+    let params = fn_type.get_args();
+    if args.len() != params.len() {
+        return Err(CallError::ArgumentCount {
+            function: name.to_string(), expected: params.len(), found: args.len()
+        })
+    }
+    if let Some(&(unknown, _)) = args.iter()
+            .find(|&&(arg_name, _)| fn_type.get_arg(arg_name).is_none()) {
+        return Err(CallError::UnknownArgument {
+            function: name.to_string(), name: unknown.to_string()
+        })
+    }
+    let mut values = Vec::with_capacity(params.len());
+    for &(ref param_name, ref param_type) in params {
+        let value = match args.iter().find(|&&(arg_name, _)| param_name == arg_name) {
+            Some(&(_, value)) => value,
+            None => return Err(CallError::MissingArgument {
+                function: name.to_string(), name: param_name.clone()
+            })
+        };
+        if value.get_type() != *param_type {
+            return Err(CallError::ArgumentType {
+                function: name.to_string(), name: param_name.clone(),
+                expected: param_type.clone(), found: value.get_type()
+            })
+        }
+        values.push(value);
+    }
+    Ok(values)
+}
+
+/// Add a function to `module` which calls `name` with the given arguments
+/// and returns its result.
+///
+/// `name` is declared in the module if it was compiled in another one.
+pub fn build_call(module: LLVMModuleRef, name: &str, fn_type: &FnType, args: &[Value]) -> LLVMValueRef {
+    let return_type = llvm_type(fn_type.get_return());
+    let fn_name = CString::new(name).expect("Identifiers don't contain nul");
+    let call_name = CString::new(format!("__call_{}", name)).expect("Identifiers don't contain nul");
+    unsafe {
+        let mut function = LLVMGetNamedFunction(module, fn_name.as_ptr());
+        if function.is_null() {
+            let mut arg_types = fn_type.get_args().iter()
+                .map(|&(_, ref arg_type)| llvm_type(arg_type))
+                .collect::<Vec<_>>();
+            let declared_type = LLVMFunctionType(return_type, arg_types.as_mut_ptr(),
+                                                 arg_types.len() as c_uint, 0);
+            function = LLVMAddFunction(module, fn_name.as_ptr(), declared_type);
+            add_abi_attributes(function, fn_type);
+        }
+        let call_type = LLVMFunctionType(return_type, ptr::null_mut(), 0, 0);
+        let call_fn = LLVMAddFunction(module, call_name.as_ptr(), call_type);
+        let block = LLVMAppendBasicBlock(call_fn, b"entry\0".as_ptr() as *const _);
+        let builder = LLVMCreateBuilder();
+        LLVMPositionBuilderAtEnd(builder, block);
+        let mut arg_values = args.iter().map(|value| const_value(value)).collect::<Vec<_>>();
+        if *fn_type.get_return() == Type::Empty {
+            LLVMBuildCall(builder, function, arg_values.as_mut_ptr(), arg_values.len() as c_uint,
+                          b"\0".as_ptr() as *const _);
+            LLVMBuildRetVoid(builder);
+        }
+        else {
+            let result = LLVMBuildCall(builder, function, arg_values.as_mut_ptr(),
+                                       arg_values.len() as c_uint, b"result\0".as_ptr() as *const _);
+            LLVMBuildRet(builder, result);
+        }
+        LLVMDisposeBuilder(builder);
+        call_fn
+    }
+}
+
+/// Get an LLVM constant for a value.
+fn const_value(value: &Value) -> LLVMValueRef {
+    unsafe {
+        match *value {
+            Value::Float(float) => LLVMConstReal(LLVMFloatType(), float),
+            Value::Int(int) => LLVMConstInt(LLVMInt64Type(), int as u64, 1),
+            Value::Bool(boolean) => LLVMConstInt(LLVMInt1Type(), boolean as u64, 0),
+            Value::Empty => panic!("Empty values can't be passed to functions")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use parse::{FnType, Type};
+    use run::Value;
+    use super::{bind_args, CallError};
+
+    fn pow_type() -> FnType {
+        FnType::new(Box::new(Type::Float),
+                    vec![("x".into(), Type::Float), ("n".into(), Type::Int)])
+    }
+
+    #[test]
+    fn it_binds_named_arguments_in_declaration_order() {
+        let bound = bind_args("pow", &pow_type(), &[("n", Value::Int(3)), ("x", Value::Float(2.0))]);
+        assert_eq!(bound, Ok(vec![Value::Float(2.0), Value::Int(3)]));
+    }
+
+    #[test]
+    fn it_rejects_mismatched_arguments() {
+        assert_eq!(bind_args("pow", &pow_type(), &[("x", Value::Float(2.0))]),
+                   Err(CallError::ArgumentCount { function: "pow".into(), expected: 2, found: 1 }));
+        assert_eq!(bind_args("pow", &pow_type(), &[("x", Value::Float(2.0)), ("y", Value::Int(3))]),
+                   Err(CallError::UnknownArgument { function: "pow".into(), name: "y".into() }));
+        assert_eq!(bind_args("pow", &pow_type(), &[("x", Value::Float(2.0)), ("x", Value::Float(3.0))]),
+                   Err(CallError::MissingArgument { function: "pow".into(), name: "n".into() }));
+        assert_eq!(bind_args("pow", &pow_type(), &[("x", Value::Int(2)), ("n", Value::Int(3))]),
+                   Err(CallError::ArgumentType {
+                       function: "pow".into(), name: "x".into(), expected: Type::Float, found: Type::Int
+                   }));
+    }
+}
@@ -0,0 +1,263 @@
+//! Rust functions which protosnirk code can call.
+//!
+//! A `HostFn` is defined in the `Definitions` code is verified with, so
+//! calls to it are checked like calls to any other function, and given to
+//! the `MCJIT`, which resolves the compiled code's calls to its address.
+//!
+//! Each `HostFn` made from a closure owns it, and is called through a shim
+//! which takes the closure's address before its arguments. The `MCJIT`
+//! compiles a trampoline for it which passes the address.
+
+use std::any::Any;
+use std::fmt;
+use std::os::raw::c_void;
+use std::panic::{self, AssertUnwindSafe};
+use std::process;
+use std::rc::Rc;
+
+use parse::{Definitions, FnType, ScopeIndex, Type};
+
+/// Reasons a host function couldn't be created
+#[derive(Debug, PartialEq, Clone)]
+pub enum HostFnError {
+    /// The closure takes a different number of arguments than names were given
+    ParamCount { function: String, expected: usize, found: usize }
+}
+
+impl fmt::Display for HostFnError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            HostFnError::ParamCount { ref function, expected, found } =>
+                write!(f, "Host function {} takes {} arguments, but {} names were given",
+                       function, expected, found)
+        }
+    }
+}
+
+/// A closure which is kept for as long as a host function can call it
+#[derive(Clone)]
+struct ClosureRef(Rc<Any>);
+impl ClosureRef {
+    fn get_address(&self) -> u64 {
+        &*self.0 as *const Any as *const u8 as u64
+    }
+}
+impl fmt::Debug for ClosureRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ClosureRef({:#x})", self.get_address())
+    }
+}
+impl PartialEq for ClosureRef {
+    fn eq(&self, other: &ClosureRef) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// A Rust function which can be called from protosnirk code.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HostFn {
+    name: String,
+    fn_type: FnType,
+    /// Address of an `extern "C"` function with the arguments of `fn_type`,
+    /// after the address of `closure` if there is one
+    address: u64,
+    closure: Option<ClosureRef>
+}
+impl HostFn {
+    /// Use an `extern "C"` function.
+    ///
+    /// This is unsafe as the function must take the C equivalents of
+    /// `params` in order and return `return_type`: `f32` for `Float`,
+    /// `i64` for `Int`, `bool` for `Bool` and `*const c_char` for `String`.
+    pub unsafe fn from_extern(name: &str, params: Vec<(String, Type)>, return_type: Type,
+                              function: *const c_void) -> HostFn {
+        HostFn {
+            name: name.to_string(),
+            fn_type: FnType::new(Box::new(return_type), params),
+            address: function as u64,
+            closure: None
+        }
+    }
+
+    /// Use a Rust closure, which takes and returns `f32`s for `Float`s,
+    /// `i64`s for `Int`s and `bool`s for `Bool`s.
+    ///
+    /// Its parameters are given the names in `param_names`, in order.
+    /// A closure which panics aborts the process, as it can't unwind into
+    /// compiled code.
+    pub fn from_closure<F, Args>(name: &str, param_names: &[&str], closure: F)
+            -> Result<HostFn, HostFnError>
+        where F: HostClosure<Args> {
+        let param_types = F::param_types();
+        if param_names.len() != param_types.len() {
+            return Err(HostFnError::ParamCount {
+                function: name.to_string(),
+                expected: param_types.len(),
+                found: param_names.len()
+            })
+        }
+        let params = param_names.iter()
+            .map(|param_name| param_name.to_string())
+            .zip(param_types)
+            .collect();
+        Ok(HostFn {
+            name: name.to_string(),
+            fn_type: FnType::new(Box::new(F::return_type()), params),
+            address: F::shim_address(),
+            closure: Some(ClosureRef(Rc::new(closure)))
+        })
+    }
+
+    /// Define the function so that verified code can call it.
+    pub fn define(&self, definitions: &mut Definitions) -> ScopeIndex {
+        definitions.define_fn(&self.name, self.fn_type.clone())
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+    pub fn get_type(&self) -> &FnType {
+        &self.fn_type
+    }
+    /// Get the address of the function which is called.
+    ///
+    /// Functions made from closures take the closure's address before
+    /// their arguments.
+    pub fn get_address(&self) -> u64 {
+        self.address
+    }
+    /// Get the address of the closure the function calls, if it's made from one.
+    pub fn get_closure_address(&self) -> Option<u64> {
+        self.closure.as_ref().map(|closure| closure.get_address())
+    }
+}
+
+/// Rust types which can be passed between protosnirk and closures.
+pub trait HostType : 'static {
+    fn get_type() -> Type;
+}
+impl HostType for f32 {
+    fn get_type() -> Type { Type::Float }
+}
+impl HostType for i64 {
+    fn get_type() -> Type { Type::Int }
+}
+impl HostType for bool {
+    fn get_type() -> Type { Type::Bool }
+}
+impl HostType for () {
+    fn get_type() -> Type { Type::Empty }
+}
+
+/// Closures which can be used as host functions, with their arguments as
+/// a tuple in `Args`.
+pub trait HostClosure<Args> : 'static {
+    fn param_types() -> Vec<Type>;
+    fn return_type() -> Type;
+    /// Get the address of an `extern "C"` function which takes the address
+    /// of a closure of this type, then its arguments, and calls it.
+    fn shim_address() -> u64;
+}
+
+macro_rules! host_closure {
+    ($shim:ident, $($arg:ident: $arg_type:ident),*) => {
+        impl<F, R, $($arg_type),*> HostClosure<($($arg_type,)*)> for F
+            where F: Fn($($arg_type),*) -> R + 'static, R: HostType, $($arg_type: HostType),* {
+            fn param_types() -> Vec<Type> {
+                vec![$($arg_type::get_type()),*]
+            }
+            fn return_type() -> Type {
+                R::get_type()
+            }
+            fn shim_address() -> u64 {
+                extern "C" fn $shim<F, R, $($arg_type),*>(closure: *const c_void, $($arg: $arg_type),*) -> R
+                    where F: Fn($($arg_type),*) -> R + 'static {
+                    let closure = unsafe { &*(closure as *const F) };
+                    // Unwinding out of an `extern "C"` function is undefined
+                    match panic::catch_unwind(AssertUnwindSafe(|| closure($($arg),*))) {
+                        Ok(result) => result,
+                        Err(_) => process::abort()
+                    }
+                }
+                $shim::<F, R, $($arg_type),*> as usize as u64
+            }
+        }
+    }
+}
+
+host_closure!(shim0, );
+host_closure!(shim1, a: A);
+host_closure!(shim2, a: A, b: B);
+host_closure!(shim3, a: A, b: B, c: C);
+host_closure!(shim4, a: A, b: B, c: C, d: D);
+
+#[cfg(test)]
+mod tests {
+    use std::mem;
+    use std::os::raw::c_void;
+
+    use parse::{FnType, Type};
+    use super::{HostFn, HostFnError};
+
+    /// Get a host function which adds `offset`, with the same closure type
+    /// for every offset
+    fn add_offset(offset: i64) -> HostFn {
+        HostFn::from_closure("add_offset", &["x"], move |x: i64| x + offset)
+            .expect("Could not create host function")
+    }
+
+    /// Call a host function which takes and returns an `Int`
+    fn call_int_fn(host_fn: &HostFn, x: i64) -> i64 {
+        let closure = host_fn.get_closure_address().expect("Function has no closure");
+        let function: extern "C" fn(*const c_void, i64) -> i64 = unsafe {
+            mem::transmute(host_fn.get_address() as usize)
+        };
+        function(closure as *const c_void, x)
+    }
+
+    #[test]
+    fn it_gets_the_types_of_closures() {
+        let offset = 10;
+        let host_fn = HostFn::from_closure("add_offset", &["x", "scale"],
+            move |x: i64, scale: f32| (x + offset) as f32 * scale)
+            .expect("Could not create host function");
+        assert_eq!(host_fn.get_type(), &FnType::new(Box::new(Type::Float),
+            vec![("x".into(), Type::Int), ("scale".into(), Type::Float)]));
+    }
+
+    #[test]
+    fn it_calls_closures_through_their_address() {
+        assert_eq!(call_int_fn(&add_offset(10), 5), 15);
+    }
+
+    #[test]
+    fn it_keeps_closures_of_the_same_type_apart() {
+        let add_ten = add_offset(10);
+        let add_twenty = add_offset(20);
+        assert_eq!(call_int_fn(&add_ten, 5), 15);
+        assert_eq!(call_int_fn(&add_twenty, 5), 25);
+        assert!(add_ten != add_twenty);
+    }
+
+    #[test]
+    fn it_rejects_the_wrong_number_of_names() {
+        let error = HostFn::from_closure("add", &["x"], |x: i64, y: i64| x + y);
+        assert_eq!(error, Err(HostFnError::ParamCount {
+            function: "add".into(), expected: 2, found: 1
+        }));
+    }
+
+    #[test]
+    fn it_passes_bools_to_closures() {
+        let host_fn = HostFn::from_closure("choose", &["flag", "x"],
+            |flag: bool, x: i64| if flag { x } else { -x })
+            .expect("Could not create host function");
+        assert_eq!(host_fn.get_type(), &FnType::new(Box::new(Type::Int),
+            vec![("flag".into(), Type::Bool), ("x".into(), Type::Int)]));
+        let closure = host_fn.get_closure_address().expect("Function has no closure");
+        let function: extern "C" fn(*const c_void, bool, i64) -> i64 = unsafe {
+            mem::transmute(host_fn.get_address() as usize)
+        };
+        assert_eq!(function(closure as *const c_void, false, 5), -5);
+    }
+}
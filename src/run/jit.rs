@@ -1,54 +1,86 @@
 use std::cell::RefCell;
+use std::ffi::CString;
 use std::mem;
+use std::os::raw::c_uint;
 use std::rc::Rc;
+use std::sync::{Once, ONCE_INIT};
 
-use compile::ModuleProvider;
+use compile::{ModuleProvider, OptLevel, PassPipeline};
+use compile::{add_abi_attributes, add_call_abi_attributes, llvm_type};
+use parse::{SymbolTable, Type};
+use super::call::{self, CallError};
+use super::host::HostFn;
 use super::llvm_state::LLVMState;
+use super::value::Value;
 
-use llvm_sys::prelude::LLVMValueRef;
-use iron_llvm::{LLVMRefCtor};
-use iron_llvm::core::{Module, FunctionPassManager};
+use llvm_sys::prelude::{LLVMModuleRef, LLVMValueRef};
+use llvm_sys::core::{LLVMAddFunction, LLVMFunctionType, LLVMAppendBasicBlock, LLVMCreateBuilder,
+                     LLVMPositionBuilderAtEnd, LLVMBuildCall, LLVMBuildRet, LLVMBuildRetVoid,
+                     LLVMDisposeBuilder, LLVMGetParam, LLVMConstInt, LLVMConstIntToPtr,
+                     LLVMInt64Type, LLVMInt8Type, LLVMPointerType};
+use llvm_sys::execution_engine::LLVMLinkInMCJIT;
+use llvm_sys::target::{LLVM_InitializeNativeTarget, LLVM_InitializeNativeAsmPrinter};
+use iron_llvm::{LLVMRef, LLVMRefCtor};
+use iron_llvm::core::Module;
 use iron_llvm::core::value::{FunctionRef};
 use iron_llvm::core::types::{RealTypeRef, RealTypeCtor};
 use iron_llvm::execution_engine::execution_engine::MCJITBuilder;
 use iron_llvm::execution_engine::memory_manager::BindingSectionMemoryManagerBuilder;
 
 pub trait LLVMJIT : ModuleProvider {
-    fn run_function(&mut self, func: LLVMValueRef) -> f64;
-}
+    /// Run a function which takes no arguments and returns `return_type`.
+    fn run_function(&mut self, func: LLVMValueRef, return_type: &Type) -> Value;
 
-fn default_pass_manager(module: &Module, optimize: bool) -> FunctionPassManager {
-    let mut pass_manager = FunctionPassManager::new(module);
-    pass_manager.add_basic_alias_analysis_pass();
-    pass_manager.add_CFG_simplification_pass();
-    if optimize {
-        pass_manager.add_instruction_combining_pass();
-        pass_manager.add_reassociate_pass();
-        pass_manager.add_GVN_pass();
+    /// Call the compiled function `name` with named arguments.
+    ///
+    /// The function is looked up in `symbols`, which should be the table it
+    /// was compiled with, and the arguments are passed in the order it
+    /// declares them.
+    fn call(&mut self, symbols: &SymbolTable, name: &str, args: &[(&str, Value)])
+            -> Result<Value, CallError> {
+        let fn_type = try!(call::find_fn(symbols, name));
+        let arg_values = try!(call::bind_args(name, &fn_type, args));
+        let unsupported = fn_type.get_args().iter()
+            .map(|&(_, ref arg_type)| arg_type)
+            .chain(Some(fn_type.get_return()))
+            .find(|type_| **type_ == Type::String);
+        if let Some(found) = unsupported {
+            return Err(CallError::UnsupportedType { function: name.to_string(), found: found.clone() })
+        }
+        let function = call::build_call(self.get_module().to_ref(), name, &fn_type, &arg_values);
+        Ok(self.run_function(function, fn_type.get_return()))
     }
-    pass_manager.initialize();
-    pass_manager
 }
 
+static INITIALIZE_JIT: Once = ONCE_INIT;
+
 pub struct MCJIT {
     current_module_name: String,
+    // Fields are dropped in order, and the passes must be disposed of
+    // before the module they were created for
+    passes: PassPipeline,
     current_module: Module,
-    pass_manager: FunctionPassManager,
-    optimization: bool,
+    level: OptLevel,
 
     shared_state: Rc<RefCell<LLVMState>>
 }
 
 impl MCJIT {
-    pub fn new(name: String, optimization: bool) -> MCJIT {
+    pub fn new(name: String, level: OptLevel) -> MCJIT {
+        // Modules are compiled for the machine the JIT runs on
+        INITIALIZE_JIT.call_once(|| unsafe {
+            LLVMLinkInMCJIT();
+            LLVM_InitializeNativeTarget();
+            LLVM_InitializeNativeAsmPrinter();
+        });
         let module = Module::new(&name);
-        let pass_manager = default_pass_manager(&module, optimization);
+        let passes = PassPipeline::new(&module, level);
 
         MCJIT {
             current_module_name: name,
             current_module: module,
-            optimization: optimization,
-            pass_manager: pass_manager,
+            level: level,
+            passes: passes,
             shared_state: Rc::new(RefCell::new(LLVMState::new()))
         }
     }
@@ -57,22 +89,61 @@ impl MCJIT {
     ///
     /// iron_llvm tutorial dictates creating a new module per
     /// function written to the command line.
-    fn close_current_module(&mut self) {
+    pub fn close_current_module(&mut self) {
         let new_module = Module::new(&self.current_module_name);
-        let mut pass_manager = FunctionPassManager::new(&new_module);
-        pass_manager.add_basic_alias_analysis_pass();
-        pass_manager.add_instruction_combining_pass();
-        pass_manager.add_reassociate_pass();
-        // TODO incorporate optimization levels
-        pass_manager.add_GVN_pass();
-        pass_manager.add_CFG_simplification_pass();
-        pass_manager.initialize();
-        self.pass_manager = pass_manager;
+        self.passes = PassPipeline::new(&new_module, self.level);
         let current_module = mem::replace(&mut self.current_module, new_module);
+        self.add_engine(current_module);
+    }
+
+    /// Throw away the current module without running it, and create a new one.
+    pub fn discard_current_module(&mut self) {
+        let new_module = Module::new(&self.current_module_name);
+        self.passes = PassPipeline::new(&new_module, self.level);
+        self.current_module = new_module;
+    }
+
+    /// Make a host function callable by modules which declare it.
+    ///
+    /// Closures are called through a trampoline, which is compiled in a
+    /// module of its own.
+    pub fn add_host_fn(&mut self, host_fn: &HostFn) {
+        let address = match host_fn.get_closure_address() {
+            Some(closure) => {
+                let module = Module::new(&format!("{}_trampoline", host_fn.get_name()));
+                build_trampoline(module.to_ref(), host_fn, closure);
+                self.add_engine(module);
+                self.shared_state.borrow()
+                    .execution_engines.last()
+                    .expect("MCJIT did not have JITs")
+                    .get_function_address(host_fn.get_name())
+            },
+            None => host_fn.get_address()
+        };
+        self.shared_state.borrow_mut().add_host_fn(host_fn, address);
+    }
 
+    /// Set the value of a global variable, which modules can declare and use.
+    pub fn set_global(&mut self, name: &str, value: Value) {
+        self.shared_state.borrow_mut().set_global(name, value);
+    }
+
+    /// Create an execution engine for a module, whose calls are resolved to
+    /// globals, host functions and the functions of earlier modules.
+    fn add_engine(&mut self, module: Module) {
         let container = self.shared_state.clone();
         let memory_manager = BindingSectionMemoryManagerBuilder::new()
             .set_get_symbol_address(move |mut parent_state, name| {
+                // Globals and host functions are checked first so they aren't
+                // mistaken for symbols of the process
+                let addr = container.borrow().get_global_address(name);
+                if addr != 0 {
+                    return addr;
+                }
+                let addr = container.borrow().get_host_fn_address(name);
+                if addr != 0 {
+                    return addr;
+                }
                 let addr = parent_state.get_symbol_address(name);
                 if addr != 0 {
                     return addr;
@@ -84,7 +155,7 @@ impl MCJIT {
 
         let (execution_engine, module) = match MCJITBuilder::new()
             .set_mcjit_memory_manager(Box::new(memory_manager))
-            .create(current_module) {
+            .create(module) {
                 Ok((ee, module)) => (ee, module),
                 Err(msg) => panic!(msg)
             };
@@ -100,12 +171,15 @@ impl ModuleProvider for MCJIT {
     fn get_module_mut(&mut self) -> &mut Module {
         &mut self.current_module
     }
-    fn get_pass_manager(&mut self) -> &mut FunctionPassManager {
-        &mut self.pass_manager
+    fn get_passes(&mut self) -> &mut PassPipeline {
+        &mut self.passes
+    }
+    fn optimize_module(&mut self) {
+        self.passes.run_on_module(&mut self.current_module);
     }
 }
 impl LLVMJIT for MCJIT {
-    fn run_function(&mut self, function: LLVMValueRef) -> f64 {
+    fn run_function(&mut self, function: LLVMValueRef, return_type: &Type) -> Value {
         self.close_current_module();
         let function_ref = unsafe { FunctionRef::from_ref(function) };
         let mut args = vec![];
@@ -113,7 +187,56 @@ impl LLVMJIT for MCJIT {
             .execution_engines.last()
             .expect("MCJIT did not have JITs")
             .run_function(&function_ref, args.as_mut_slice());
-        let ty = RealTypeRef::get_double();
-        res.to_float(&ty)
+        match *return_type {
+            Type::Empty => Value::Empty,
+            Type::Float => Value::Float(res.to_float(&RealTypeRef::get_float())),
+            Type::Int => Value::Int(res.to_int(true) as i64),
+            Type::Bool => Value::Bool(res.to_int(false) != 0),
+            ref other => panic!("Cannot return {:?} from a JIT function", other)
+        }
+    }
+}
+
+/// Add a function to `module`, named after `host_fn`, which passes the
+/// address of its closure to it along with its arguments.
+fn build_trampoline(module: LLVMModuleRef, host_fn: &HostFn, closure: u64) {
+    let fn_type = host_fn.get_type();
+    let return_type = llvm_type(fn_type.get_return());
+    let mut arg_types = fn_type.get_args().iter()
+        .map(|&(_, ref arg_type)| llvm_type(arg_type))
+        .collect::<Vec<_>>();
+    let name = CString::new(host_fn.get_name()).expect("Identifiers don't contain nul");
+    unsafe {
+        let trampoline_type = LLVMFunctionType(return_type, arg_types.as_mut_ptr(),
+                                               arg_types.len() as c_uint, 0);
+        let trampoline = LLVMAddFunction(module, name.as_ptr(), trampoline_type);
+        add_abi_attributes(trampoline, fn_type);
+
+        // The shim takes the closure's address as its first argument
+        let closure_type = LLVMPointerType(LLVMInt8Type(), 0);
+        arg_types.insert(0, closure_type);
+        let shim_type = LLVMFunctionType(return_type, arg_types.as_mut_ptr(),
+                                         arg_types.len() as c_uint, 0);
+        let shim = LLVMConstIntToPtr(LLVMConstInt(LLVMInt64Type(), host_fn.get_address(), 0),
+                                     LLVMPointerType(shim_type, 0));
+        let mut args = vec![LLVMConstIntToPtr(LLVMConstInt(LLVMInt64Type(), closure, 0), closure_type)];
+        args.extend((0..fn_type.get_args().len()).map(|ix| LLVMGetParam(trampoline, ix as c_uint)));
+
+        let block = LLVMAppendBasicBlock(trampoline, b"entry\0".as_ptr() as *const _);
+        let builder = LLVMCreateBuilder();
+        LLVMPositionBuilderAtEnd(builder, block);
+        if *fn_type.get_return() == Type::Empty {
+            let call = LLVMBuildCall(builder, shim, args.as_mut_ptr(), args.len() as c_uint,
+                                     b"\0".as_ptr() as *const _);
+            add_call_abi_attributes(call, fn_type, 2);
+            LLVMBuildRetVoid(builder);
+        }
+        else {
+            let result = LLVMBuildCall(builder, shim, args.as_mut_ptr(), args.len() as c_uint,
+                                       b"result\0".as_ptr() as *const _);
+            add_call_abi_attributes(result, fn_type, 2);
+            LLVMBuildRet(builder, result);
+        }
+        LLVMDisposeBuilder(builder);
     }
 }
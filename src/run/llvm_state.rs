@@ -1,13 +1,28 @@
+use std::collections::HashMap;
+
 use iron_llvm::execution_engine::{ExecutionEngine};
 use iron_llvm::execution_engine::execution_engine::FrozenModule;
 
+use super::host::HostFn;
+use super::value::Value;
+
 /// LLVM JIT is done by compiling a module per line of input.
 /// In order to provide a JIT we need to keep track of all the
 /// modules and also provide some symbol resolution.
+///
+/// Global variables and host functions don't belong to any one module.
+/// Each module which uses one declares it, and they're all resolved to
+/// storage or functions kept here.
 #[derive(Default)]
 pub struct LLVMState {
     pub execution_engines: Vec<ExecutionEngine>,
-    pub modules: Vec<FrozenModule>
+    pub modules: Vec<FrozenModule>,
+    /// Storage for global variables, which is boxed so it doesn't move
+    globals: HashMap<String, Box<u64>>,
+    /// Functions provided by the host application, which are kept so their
+    /// closures live as long as compiled code, and the addresses compiled
+    /// code calls them at
+    host_fns: HashMap<String, (HostFn, u64)>
 }
 
 impl LLVMState {
@@ -25,4 +40,37 @@ impl LLVMState {
 
         0
     }
+    /// Get the address of the storage of a global, or 0 if there's no such global.
+    pub fn get_global_address(&self, name: &str) -> u64 {
+        self.globals.get(name)
+            .map(|storage| &**storage as *const u64 as u64)
+            .unwrap_or(0)
+    }
+    /// Get the address of a host function, or 0 if there's no such function.
+    pub fn get_host_fn_address(&self, name: &str) -> u64 {
+        self.host_fns.get(name).map(|&(_, address)| address).unwrap_or(0)
+    }
+    /// Make a host function available to compiled code, which calls it at `address`.
+    ///
+    /// This is the function's own address, or for closures the address of
+    /// a trampoline which passes the closure to it.
+    pub fn add_host_fn(&mut self, host_fn: &HostFn, address: u64) {
+        self.host_fns.insert(host_fn.get_name().to_string(), (host_fn.clone(), address));
+    }
+    /// Set the value of a global, creating it if needed.
+    ///
+    /// Values are stored the way compiled code loads them: `Float`s are
+    /// 32 bit and `Bool`s a single byte, at the start of the storage.
+    pub fn set_global(&mut self, name: &str, value: Value) {
+        let storage = self.globals.entry(name.to_string()).or_insert_with(|| Box::new(0));
+        let address = &mut **storage as *mut u64;
+        unsafe {
+            match value {
+                Value::Empty => { },
+                Value::Float(float) => *(address as *mut f32) = float as f32,
+                Value::Int(int) => *(address as *mut i64) = int,
+                Value::Bool(boolean) => *(address as *mut u8) = boolean as u8
+            }
+        }
+    }
 }
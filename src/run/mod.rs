@@ -1,7 +1,15 @@
 //! Contains the runtime for protosnirk
 
+mod call;
+mod host;
 mod jit;
 mod llvm_state;
+mod repl;
+mod value;
 
 pub use self::llvm_state::LLVMState;
-pub use self::jit::LLVMJIT;
+pub use self::jit::{LLVMJIT, MCJIT};
+pub use self::call::CallError;
+pub use self::host::{HostFn, HostFnError, HostType, HostClosure};
+pub use self::value::Value;
+pub use self::repl::{Repl, Output, needs_more_input};
@@ -0,0 +1,486 @@
+//! Interactive prompt which JIT compiles each entry as it's given.
+//!
+//! Entries are function definitions, top level `let` declarations or
+//! expressions. Definitions are compiled into their own module, which
+//! `MCJIT` keeps so they can be called by later entries. Expressions are
+//! wrapped in a function which is compiled and run straight away.
+//! Declarations are run the same way, and their value is kept in a global
+//! variable which later entries can use.
+//!
+//! Entries are verified incrementally, with the symbols of the entries
+//! before them, and only the new code is compiled; earlier functions and
+//! globals are declared as external.
+
+use std::ffi::{CStr, CString};
+
+use lex::{IterTokenizer, TextLocation, TokenData};
+use parse::{ASTVisitor, Definitions, Parser, SymbolTable, Type, FnType};
+use parse::ast::{Declaration, Expression, Item, Statement, Unit};
+use compile::{ModuleCompiler, ModuleProvider, SimpleModuleProvider, OptLevel, verify_module};
+use diagnostics::{Diagnostic, ErrorCode, Renderer, Severity};
+use super::jit::{LLVMJIT, MCJIT};
+use super::value::Value;
+
+use iron_llvm::LLVMRef;
+use llvm_sys::core::{LLVMGetNamedFunction, LLVMPrintModuleToString, LLVMDisposeMessage};
+
+/// Name used for the source of the REPL in diagnostics
+const REPL_FILE_NAME: &'static str = "<repl>";
+
+/// Keywords which start an entry spanning multiple lines, unless they're
+/// used with the inline `=>` syntax
+const BLOCK_KEYWORDS: &'static [&'static str] = &["fn", "if", "while", "for", "loop", "do"];
+
+/// What is shown after an entry is evaluated
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Output {
+    /// Rendered errors and warnings about the entry
+    pub diagnostics: String,
+    /// The value, type or IR which the entry produced
+    pub result: Option<String>
+}
+impl Output {
+    fn result(result: String) -> Output {
+        Output { diagnostics: String::new(), result: Some(result) }
+    }
+    fn error(message: String) -> Output {
+        Output { diagnostics: format!("{}\n", message), result: None }
+    }
+}
+
+/// An entry which was verified along with the definitions before it
+struct CheckedEntry {
+    /// The source which was verified, after any wrapping
+    source: String,
+    unit: Unit,
+    table: SymbolTable,
+    diagnostics: Vec<Diagnostic>
+}
+
+/// A read-eval-print loop over the `MCJIT`.
+pub struct Repl {
+    /// Only taken while an entry is being compiled
+    jit: Option<MCJIT>,
+    /// Functions and globals which have been defined so far
+    definitions: Definitions,
+    /// Source of the entries which defined something, which diagnostics
+    /// about later entries can point back to
+    history: String,
+    /// Location just after `history`, where the next entry starts
+    history_end: TextLocation,
+    /// Number of entries which have been wrapped in functions, used to name them
+    wrapped_count: usize
+}
+impl Repl {
+    pub fn new() -> Repl {
+        Repl {
+            jit: Some(MCJIT::new(REPL_FILE_NAME.to_string(), OptLevel::O0)),
+            definitions: Definitions::new(),
+            history: String::new(),
+            history_end: TextLocation::default(),
+            wrapped_count: 0
+        }
+    }
+
+    /// Forget everything which has been defined.
+    pub fn reset(&mut self) {
+        *self = Repl::new();
+    }
+
+    /// Evaluate an entry: a definition, a declaration, an expression, or a command.
+    ///
+    /// - `:type <expr>` shows the type of an expression, function or global
+    /// - `:ir <entry>` shows the LLVM IR of an entry without running it
+    /// - `:reset` forgets everything which has been defined
+    pub fn eval(&mut self, entry: &str) -> Output {
+        let entry = entry.trim_right();
+        if entry.trim().is_empty() {
+            return Output::default()
+        }
+        if entry.starts_with(':') {
+            let (command, rest) = match entry.find(char::is_whitespace) {
+                Some(ix) => (&entry[..ix], entry[ix..].trim_left()),
+                None => (entry, "")
+            };
+            return match command {
+                ":type" => self.type_of(rest),
+                ":ir" => self.ir(rest),
+                ":reset" => {
+                    self.reset();
+                    Output::default()
+                },
+                _ => Output::error(format!("Unknown command {}, expected :type, :ir or :reset", command))
+            }
+        }
+        if is_definition(entry) {
+            self.define(entry)
+        }
+        else if let Some(name) = declared_name(entry) {
+            self.declare(entry, &name)
+        }
+        else {
+            self.evaluate(entry)
+        }
+    }
+
+    /// Compile a function definition and keep it for later entries.
+    fn define(&mut self, entry: &str) -> Output {
+        let checked = match self.check(format!("{}\n", entry)) {
+            Ok(checked) => checked,
+            Err(diagnostics) => return Output { diagnostics: diagnostics, result: None }
+        };
+        let mut jit = match self.compile_into_jit(&checked) {
+            Ok(jit) => jit,
+            Err(diagnostics) => return Output { diagnostics: diagnostics, result: None }
+        };
+        jit.close_current_module();
+        self.jit = Some(jit);
+
+        let mut defined = Vec::new();
+        for item in checked.unit.get_items() {
+            if let Item::FnDeclaration(ref decl) = *item {
+                let symbol = checked.table[&decl.get_name().get_index()].clone();
+                defined.push(format!("{}: {}", decl.get_name().get_name(), symbol.get_type()));
+                self.definitions.define(symbol);
+            }
+        }
+        let diagnostics = self.render(&checked.source, &checked.diagnostics);
+        self.remember(&checked.source);
+        Output { diagnostics: diagnostics, result: Some(defined.join("\n")) }
+    }
+
+    /// Run a top level `let` declaration and keep its value in a global.
+    ///
+    /// The declaration is wrapped in a function which returns the value of
+    /// the variable, and later entries use the global in its place.
+    fn declare(&mut self, entry: &str, name: &str) -> Output {
+        let fn_name = self.next_wrapped_name();
+        let mut checked = match self.check(wrap_declaration(&fn_name, entry, name)) {
+            Ok(checked) => checked,
+            Err(diagnostics) => return Output { diagnostics: diagnostics, result: None }
+        };
+        let (ident, mutable) = {
+            let decl = entry_declaration(&checked);
+            (decl.get_ident().clone(), decl.is_mut())
+        };
+        let var_type = checked.table[&ident.get_index()].get_type().clone();
+        if var_type == Type::String {
+            let diagnostics = self.render(&checked.source, &checked.diagnostics)
+                + "String values can't be kept in globals yet\n";
+            return Output { diagnostics: diagnostics, result: None }
+        }
+        // The wrapper's variable is never mutated, the global may well be
+        checked.diagnostics.retain(|diagnostic| !(diagnostic.get_code() == ErrorCode::UnmutatedMutable
+            && diagnostic.get_primary().map(|label| label.get_span()) == Some(ident.get_token().get_span())));
+
+        let mut jit = match self.compile_into_jit(&checked) {
+            Ok(jit) => jit,
+            Err(diagnostics) => return Output { diagnostics: diagnostics, result: None }
+        };
+        let value = run_wrapped(&mut jit, &fn_name, &var_type);
+        jit.set_global(name, value);
+        self.jit = Some(jit);
+
+        self.definitions.define_global(ident.get_token().clone(), mutable, var_type.clone());
+        let diagnostics = self.render(&checked.source, &checked.diagnostics);
+        self.remember(&checked.source);
+        Output { diagnostics: diagnostics, result: Some(format!("{}: {} = {}", name, var_type, value)) }
+    }
+
+    /// Run an expression and show its value.
+    fn evaluate(&mut self, entry: &str) -> Output {
+        let name = self.next_wrapped_name();
+        match self.check(wrap_expression(&name, entry)) {
+            Ok(checked) => self.evaluate_checked(checked, &name),
+            Err(diagnostics) => Output { diagnostics: diagnostics, result: None }
+        }
+    }
+
+    /// Run a checked entry which was wrapped in the function `name`.
+    fn evaluate_checked(&mut self, checked: CheckedEntry, name: &str) -> Output {
+        let diagnostics = self.render(&checked.source, &checked.diagnostics);
+        let return_type = entry_type(&checked).get_return().clone();
+        if return_type == Type::String {
+            let diagnostics = diagnostics + "String values can't be shown yet\n";
+            return Output { diagnostics: diagnostics, result: None }
+        }
+        let mut jit = match self.compile_into_jit(&checked) {
+            Ok(jit) => jit,
+            Err(invalid) => return Output { diagnostics: diagnostics + &invalid, result: None }
+        };
+        let value = run_wrapped(&mut jit, name, &return_type);
+        self.jit = Some(jit);
+        let result = if return_type == Type::Empty { None } else { Some(value.to_string()) };
+        Output { diagnostics: diagnostics, result: result }
+    }
+
+    /// Show the type of an expression, or of a defined function or global.
+    fn type_of(&mut self, entry: &str) -> Output {
+        if let Some(symbol) = self.definitions.get(entry.trim()) {
+            return Output::result(symbol.get_type().to_string())
+        }
+        let checked = match self.check(wrap_expression("__repl_type", entry)) {
+            Ok(checked) => checked,
+            Err(diagnostics) => return Output { diagnostics: diagnostics, result: None }
+        };
+        let result = entry_type(&checked).get_return().to_string();
+        Output { diagnostics: self.render(&checked.source, &checked.diagnostics), result: Some(result) }
+    }
+
+    /// Show the LLVM IR for an entry without running it.
+    fn ir(&mut self, entry: &str) -> Output {
+        let source = if is_definition(entry) {
+            format!("{}\n", entry)
+        }
+        else if let Some(name) = declared_name(entry) {
+            wrap_declaration("__repl_ir", entry, &name)
+        }
+        else {
+            wrap_expression("__repl_ir", entry)
+        };
+        let checked = match self.check(source) {
+            Ok(checked) => checked,
+            Err(diagnostics) => return Output { diagnostics: diagnostics, result: None }
+        };
+        let provider = self.compile(&checked, SimpleModuleProvider::new(REPL_FILE_NAME, OptLevel::O0));
+        let ir = unsafe {
+            let text = LLVMPrintModuleToString(provider.get_module().to_ref());
+            let ir = CStr::from_ptr(text).to_string_lossy().into_owned();
+            LLVMDisposeMessage(text);
+            ir
+        };
+        // Invalid IR is still shown, as it helps to find the problem
+        let mut diagnostics = checked.diagnostics;
+        diagnostics.extend(verify_module(&provider).err().and_then(|error| error.get_diagnostic()));
+        Output {
+            diagnostics: self.render(&checked.source, &diagnostics),
+            result: Some(ir.trim_right().to_string())
+        }
+    }
+
+    /// Parse and verify the source of an entry with the earlier definitions.
+    ///
+    /// The source is located after the history of the REPL, so that
+    /// diagnostics can point to both. If it has errors they're rendered.
+    fn check(&self, source: String) -> Result<CheckedEntry, String> {
+        let (diagnostics, checked) = {
+            let tokenizer = IterTokenizer::starting_at(source.chars(), self.history_end);
+            match Parser::new(tokenizer).parse_unit_incremental(&self.definitions) {
+                Ok(program) => {
+                    let (unit, table, errors) = program.decompose();
+                    (Diagnostic::from_collector(&errors), Some((unit, table)))
+                },
+                Err(error) => (Diagnostic::from_parse_error(&error), None)
+            }
+        };
+        let has_errors = diagnostics.iter()
+            .any(|diagnostic| diagnostic.get_severity() == Severity::Error);
+        match checked {
+            Some((unit, table)) => {
+                if has_errors {
+                    return Err(self.render(&source, &diagnostics))
+                }
+                Ok(CheckedEntry { source: source, unit: unit, table: table, diagnostics: diagnostics })
+            },
+            None => Err(self.render(&source, &diagnostics))
+        }
+    }
+
+    /// Render diagnostics about the source of an entry.
+    fn render(&self, source: &str, diagnostics: &[Diagnostic]) -> String {
+        let full_source = format!("{}{}", self.history, source);
+        Renderer::new(REPL_FILE_NAME, &full_source, false).render_all(diagnostics)
+    }
+
+    /// Add the source of an entry which defined something to the history.
+    fn remember(&mut self, source: &str) {
+        self.history_end = location_after(self.history_end, source);
+        self.history.push_str(source);
+    }
+
+    /// Get a new name for a function to wrap an entry in.
+    fn next_wrapped_name(&mut self) -> String {
+        let name = format!("__repl_{}", self.wrapped_count);
+        self.wrapped_count += 1;
+        name
+    }
+
+    /// Compile the items of an entry into the JIT's current module.
+    ///
+    /// If the compiled module is invalid it's discarded, and the problem
+    /// is rendered.
+    fn compile_into_jit(&mut self, checked: &CheckedEntry) -> Result<MCJIT, String> {
+        let jit = self.jit.take().expect("REPL always has a JIT between entries");
+        let mut jit = self.compile(checked, jit);
+        match verify_module(&jit) {
+            Ok(()) => Ok(jit),
+            Err(error) => {
+                jit.discard_current_module();
+                self.jit = Some(jit);
+                let diagnostics: Vec<Diagnostic> = error.get_diagnostic().into_iter().collect();
+                Err(self.render(&checked.source, &diagnostics))
+            }
+        }
+    }
+
+    /// Compile the items of an entry with the given module provider.
+    ///
+    /// Earlier functions and globals are declared as external.
+    fn compile<M: ModuleProvider>(&self, checked: &CheckedEntry, provider: M) -> M {
+        let mut compiler = ModuleCompiler::new(checked.table.clone(), provider);
+        for symbol in self.definitions.get_symbols().values() {
+            compiler.declare_external(symbol);
+        }
+        compiler.check_unit(&checked.unit);
+        let (provider, _context, _table) = compiler.decompose();
+        provider
+    }
+}
+
+/// Whether more lines should be read before the entry is evaluated.
+///
+/// An entry which starts a block, such as `fn fact(n)`, continues until
+/// an empty line is given.
+pub fn needs_more_input(entry: &str) -> bool {
+    let lines: Vec<&str> = entry.lines().collect();
+    match lines.len() {
+        0 => false,
+        1 => {
+            let first = lines[0];
+            // Commands can be given multi-line code, as in `:ir fn f()`
+            let code = if first.starts_with(':') {
+                first.splitn(2, ' ').nth(1).unwrap_or("")
+            }
+            else {
+                first
+            };
+            let opens_block = code.split_whitespace().next()
+                .map(|word| BLOCK_KEYWORDS.contains(&word))
+                .unwrap_or(false);
+            opens_block && !code.contains("=>")
+        },
+        _ => !lines[lines.len() - 1].trim().is_empty()
+    }
+}
+
+/// Whether an entry defines a function.
+fn is_definition(entry: &str) -> bool {
+    entry.split_whitespace().next() == Some("fn")
+}
+
+/// Wrap an expression in a function with the given name, so it can be
+/// checked and compiled.
+fn wrap_expression(name: &str, entry: &str) -> String {
+    let mut source = format!("fn {}()\n", name);
+    for line in entry.lines() {
+        source.push_str("    ");
+        source.push_str(line);
+        source.push('\n');
+    }
+    source
+}
+
+/// Get the name declared by a top level `let` entry, if it is one.
+fn declared_name(entry: &str) -> Option<String> {
+    let mut tokenizer = IterTokenizer::new(entry.chars());
+    if tokenizer.next().get_text() != "let" {
+        return None
+    }
+    let mut name = tokenizer.next();
+    if name.get_text() == "mut" {
+        name = tokenizer.next();
+    }
+    match name.data {
+        TokenData::Ident => Some(name.get_text().to_string()),
+        _ => None
+    }
+}
+
+/// Wrap a declaration of `var_name` in a function with the given name,
+/// which returns the value of the variable.
+fn wrap_declaration(name: &str, entry: &str, var_name: &str) -> String {
+    let mut source = wrap_expression(name, entry);
+    source.push_str("    ");
+    source.push_str(var_name);
+    source.push('\n');
+    source
+}
+
+/// Get the location just after `text`, if it starts at `start`.
+fn location_after(start: TextLocation, text: &str) -> TextLocation {
+    text.chars().fold(start, |location, ch| TextLocation {
+        index: location.index + 1,
+        line: if ch == '\n' { location.line + 1 } else { location.line },
+        column: if ch == '\n' { 0 } else { location.column + 1 }
+    })
+}
+
+/// Run the function an entry was wrapped in.
+fn run_wrapped(jit: &mut MCJIT, name: &str, return_type: &Type) -> Value {
+    let fn_name = CString::new(name).expect("Generated names don't contain nul");
+    let function = unsafe {
+        LLVMGetNamedFunction(jit.get_module().to_ref(), fn_name.as_ptr())
+    };
+    jit.run_function(function, return_type)
+}
+
+/// Get the declaration which a `let` entry was wrapped around.
+fn entry_declaration(checked: &CheckedEntry) -> &Declaration {
+    if let Some(&Item::FnDeclaration(ref decl)) = checked.unit.get_items().last() {
+        if let Some(&Statement::Expression(Expression::Declaration(ref declaration)))
+                = decl.get_block().get_stmts().first() {
+            return declaration
+        }
+    }
+    panic!("Expected a wrapped declaration, got {:?}", checked.unit)
+}
+
+/// Get the type of the function which an entry was wrapped in.
+fn entry_type(checked: &CheckedEntry) -> FnType {
+    match checked.unit.get_items().last() {
+        Some(&Item::FnDeclaration(ref decl)) =>
+            checked.table[&decl.get_name().get_index()].get_type().clone().expect_fn(),
+        other => panic!("Expected a wrapped entry, got {:?}", other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lex::TextLocation;
+    use super::{needs_more_input, wrap_expression, wrap_declaration, declared_name, location_after};
+
+    #[test]
+    fn it_reads_blocks_until_an_empty_line() {
+        assert!(!needs_more_input("1 + 2\n"));
+        assert!(!needs_more_input("fn double(x) => x * 2\n"));
+        assert!(needs_more_input("fn fact(n)\n"));
+        assert!(needs_more_input("fn fact(n)\n    if n <= 1\n"));
+        assert!(!needs_more_input("fn fact(n)\n    if n <= 1\n\n"));
+        assert!(needs_more_input(":ir loop\n"));
+        assert!(!needs_more_input(":reset\n"));
+    }
+
+    #[test]
+    fn it_wraps_expressions_in_functions() {
+        assert_eq!(wrap_expression("__repl_0", "if x\n    1\nelse\n    2"),
+                   "fn __repl_0()\n    if x\n        1\n    else\n        2\n");
+    }
+
+    #[test]
+    fn it_wraps_declarations_to_return_their_value() {
+        assert_eq!(declared_name("let x = 1"), Some("x".to_string()));
+        assert_eq!(declared_name("let mut total: Int = 0"), Some("total".to_string()));
+        assert_eq!(declared_name("let = 1"), None);
+        assert_eq!(declared_name("x + 1"), None);
+        assert_eq!(wrap_declaration("__repl_1", "let mut x = 1", "x"),
+                   "fn __repl_1()\n    let mut x = 1\n    x\n");
+    }
+
+    #[test]
+    fn it_finds_the_end_of_the_history() {
+        let start = TextLocation { index: 4, line: 1, column: 0 };
+        assert_eq!(location_after(start, "let x = 1\nx"),
+                   TextLocation { index: 15, line: 2, column: 1 });
+        assert_eq!(location_after(start, ""), start);
+    }
+}
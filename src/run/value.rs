@@ -0,0 +1,35 @@
+use std::fmt;
+
+use parse::Type;
+
+/// A value returned from running protosnirk code.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Value {
+    /// Returned by functions which don't produce a value
+    Empty,
+    Float(f64),
+    Int(i64),
+    Bool(bool)
+}
+impl Value {
+    /// Get the protosnirk type of the value.
+    pub fn get_type(&self) -> Type {
+        match *self {
+            Value::Empty => Type::Empty,
+            Value::Float(_) => Type::Float,
+            Value::Int(_) => Type::Int,
+            Value::Bool(_) => Type::Bool
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Value::Empty => write!(f, "()"),
+            Value::Float(value) => write!(f, "{}", value),
+            Value::Int(value) => write!(f, "{}", value),
+            Value::Bool(value) => write!(f, "{}", value)
+        }
+    }
+}
@@ -31,5 +31,6 @@ ensure_parse_tests! {
     fn_def: item,
     fn_indented_params: item,
     fn_inline: item,
+    fn_typed: item,
     factorial: parse_unit,
 }